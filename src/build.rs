@@ -0,0 +1,339 @@
+// ABOUTME: Build module for running the whole pipeline in one step
+// ABOUTME: Turns a markdown file into HTML, slide images, PPTX and PDF outputs
+
+use log::{info, warn};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::config::Config as AppConfig;
+use crate::errors::{BigError, Result};
+use crate::html;
+use crate::inline::InlineConfig;
+use crate::overflow::{DEFAULT_MIN_FONT_PX, OVERFLOW_REPORT_FILE};
+use crate::pptx;
+use crate::render;
+use crate::resources::ResourceFile;
+use crate::template::TemplateConfig;
+use crate::transition::Transition;
+use crate::utils;
+
+/// A final output a build can produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifact {
+    Html,
+    Png,
+    Pptx,
+    Pdf,
+}
+
+impl FromStr for Artifact {
+    type Err = BigError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "html" => Ok(Artifact::Html),
+            "png" | "images" => Ok(Artifact::Png),
+            "pptx" => Ok(Artifact::Pptx),
+            "pdf" => Ok(Artifact::Pdf),
+            other => Err(BigError::ValidationError(format!(
+                "Unknown artifact '{}', expected one of: html, png, pptx, pdf",
+                other
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Artifact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Artifact::Html => "html",
+            Artifact::Png => "png",
+            Artifact::Pptx => "pptx",
+            Artifact::Pdf => "pdf",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Configuration for a full build
+pub struct BuildConfig {
+    /// Path to the markdown source
+    pub markdown_path: PathBuf,
+
+    /// Directory the final artifacts are written to
+    pub output_dir: PathBuf,
+
+    /// Artifacts to produce
+    pub artifacts: Vec<Artifact>,
+
+    /// Keep the intermediate HTML and slide images in the output directory
+    pub keep_intermediates: bool,
+
+    /// CSS files to include
+    pub css_files: Vec<ResourceFile>,
+
+    /// JavaScript files to include
+    pub js_files: Vec<ResourceFile>,
+
+    /// Whether to embed resources in HTML
+    pub embed_resources: bool,
+
+    /// Inline every referenced asset so the HTML is self-contained
+    pub inline: Option<InlineConfig>,
+
+    /// Width of the slides in pixels
+    pub width: u32,
+
+    /// Height of the slides in pixels
+    pub height: u32,
+
+    /// Title for the presentation
+    pub title: String,
+
+    /// Aspect ratio of the PPTX (16:9 or 4:3)
+    pub aspect_ratio: String,
+
+    /// Device scale factor of the slide images
+    pub scale: f64,
+
+    /// Number of browser tabs rendering slides at once
+    pub jobs: usize,
+
+    /// How long to wait for each slide to become ready, in milliseconds
+    pub slide_timeout_ms: u64,
+
+    /// Number of slides the deck must have; checked against the markdown and the render
+    pub expected_slides: Option<usize>,
+
+    /// Text smaller than this many CSS pixels is reported
+    pub min_font_px: f64,
+
+    /// Where to write the overflow report; defaults to the slides directory if it is kept
+    pub overflow_report: Option<PathBuf>,
+
+    /// Fail when content overflows or is clipped on any slide
+    pub fail_on_overflow: bool,
+
+    /// Default transition and timing of the PPTX slides
+    pub transition: Transition,
+
+    /// Template whose masters, layouts, theme and fonts the PPTX is built on
+    pub template: Option<TemplateConfig>,
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
+        Self {
+            markdown_path: PathBuf::new(),
+            output_dir: PathBuf::new(),
+            artifacts: vec![Artifact::Pptx],
+            keep_intermediates: false,
+            css_files: Vec::new(),
+            js_files: Vec::new(),
+            embed_resources: true,
+            inline: None,
+            width: 1920,
+            height: 1080,
+            title: "Presentation".to_string(),
+            aspect_ratio: "16:9".to_string(),
+            scale: 1.0,
+            jobs: 1,
+            slide_timeout_ms: render::DEFAULT_SLIDE_TIMEOUT_MS,
+            expected_slides: None,
+            min_font_px: DEFAULT_MIN_FONT_PX,
+            overflow_report: None,
+            fail_on_overflow: false,
+            transition: Transition::default(),
+            template: None,
+        }
+    }
+}
+
+/// Files produced by a build
+#[derive(Debug, Default)]
+pub struct BuildOutput {
+    pub html: Option<PathBuf>,
+    pub slides: Vec<PathBuf>,
+    pub pptx: Option<PathBuf>,
+    pub pdf: Option<PathBuf>,
+}
+
+/// Removes a temporary file or directory when dropped
+pub(crate) struct TempPath(pub(crate) PathBuf);
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let result = if self.0.is_dir() {
+            fs::remove_dir_all(&self.0)
+        } else {
+            fs::remove_file(&self.0)
+        };
+        if let Err(e) = result {
+            if self.0.exists() {
+                warn!("Failed to remove temporary path {:?}: {}", self.0, e);
+            }
+        }
+    }
+}
+
+/// Run the markdown → HTML → images → PPTX/PDF pipeline in one go
+pub fn build_presentation(config: &BuildConfig, app_config: &AppConfig) -> Result<BuildOutput> {
+    info!("Building presentation from: {:?}", config.markdown_path);
+
+    utils::validate_file_exists(&config.markdown_path)?;
+    if config.artifacts.is_empty() {
+        return Err(BigError::ValidationError(
+            "No artifacts requested for build".to_string(),
+        ));
+    }
+    utils::ensure_directory_exists(&config.output_dir)?;
+
+    let wants = |artifact| config.artifacts.contains(&artifact);
+    let needs_slides = wants(Artifact::Png) || wants(Artifact::Pptx);
+    let needs_browser = needs_slides || wants(Artifact::Pdf);
+    let stem = config
+        .markdown_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "presentation".to_string());
+
+    // The markdown says how many slides the render must produce
+    let markdown_slides = html::parse_slides(&config.markdown_path)?.len();
+    if let Some(expected) = config.expected_slides {
+        if expected != markdown_slides {
+            return Err(BigError::SlideCountMismatch {
+                expected,
+                found: markdown_slides,
+                location: config.markdown_path.display().to_string(),
+            });
+        }
+    }
+
+    let mut output = BuildOutput::default();
+
+    // Stage 1: HTML
+    let html_content = html::generate_html(
+        &config.markdown_path,
+        &config.css_files,
+        &config.js_files,
+        config.embed_resources,
+        config.inline.as_ref(),
+        None,
+    )?;
+
+    if wants(Artifact::Html) || config.keep_intermediates {
+        let html_path = config.output_dir.join(format!("{}.html", stem));
+        html::write_html_to_file(&html_content, &html_path)?;
+        info!("HTML written: {:?}", html_path);
+        output.html = Some(html_path);
+    }
+
+    if !needs_browser {
+        return Ok(output);
+    }
+
+    // The browser renders a temporary copy whose base URL is the markdown directory,
+    // so relative image paths still resolve
+    let markdown_dir = config
+        .markdown_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let render_html =
+        TempPath(env::temp_dir().join(format!("big-build-{}-{}.html", stem, uuid::Uuid::new_v4())));
+    html::write_html_to_file(
+        &html::with_base_href(&html_content, markdown_dir)?,
+        &render_html.0,
+    )?;
+
+    let mut render_config =
+        app_config.get_render_config(Some(config.width), Some(config.height), None, None, None);
+    render_config.scale = config.scale;
+    render_config.jobs = config.jobs;
+    render_config.slide_timeout_ms = config.slide_timeout_ms;
+    render_config.expected_slides = Some(markdown_slides);
+    render_config.min_font_px = config.min_font_px;
+    render_config.overflow_report = config.overflow_report.clone();
+    render_config.fail_on_overflow = config.fail_on_overflow;
+
+    // The browser renders a temporary copy, so name the deck in count mismatches
+    let rendered_from_markdown = |e| match e {
+        BigError::SlideCountMismatch {
+            expected, found, ..
+        } => BigError::SlideCountMismatch {
+            expected,
+            found,
+            location: format!("the HTML rendered from {}", config.markdown_path.display()),
+        },
+        e => e,
+    };
+
+    // Slides and PDF share one browser
+    let mut session = render::RenderSession::new();
+
+    // Stage 2: slide images
+    let mut temp_slides_dir = None;
+    if needs_slides {
+        let slides_dir = if wants(Artifact::Png) || config.keep_intermediates {
+            let dir = config.output_dir.join("slides");
+            render_config
+                .overflow_report
+                .get_or_insert_with(|| dir.join(OVERFLOW_REPORT_FILE));
+            dir
+        } else {
+            let dir = TempPath(env::temp_dir().join(format!("big-build-{}", uuid::Uuid::new_v4())));
+            let path = dir.0.clone();
+            temp_slides_dir = Some(dir);
+            path
+        };
+
+        // Clear images left by an earlier build so they don't end up in the PPTX
+        let slide_pattern = format!("{}_*.{}", render_config.base_name, render_config.format);
+        if slides_dir.is_dir() {
+            for stale in pptx::find_slide_images(&slides_dir, &slide_pattern).unwrap_or_default() {
+                fs::remove_file(&stale).map_err(BigError::FileReadError)?;
+            }
+        }
+
+        output.slides = session
+            .generate_slides(&render_html.0, &slides_dir, &render_config)
+            .map_err(rendered_from_markdown)?;
+        info!("Rendered {} slides", output.slides.len());
+
+        // Stage 3: PPTX
+        if wants(Artifact::Pptx) {
+            let pptx_path = config.output_dir.join(format!("{}.pptx", stem));
+            let mut pptx_config = app_config.get_pptx_config(
+                Some(config.title.clone()),
+                Some(slide_pattern),
+                Some(config.aspect_ratio.clone()),
+                Some(config.markdown_path.clone()),
+            );
+            pptx_config.transition = config.transition;
+            pptx_config.template = config.template.clone();
+            pptx::generate_pptx(&slides_dir, &pptx_path, &pptx_config)?;
+            info!("PPTX written: {:?}", pptx_path);
+            output.pptx = Some(pptx_path);
+        }
+
+        // Images only live as long as the build unless they were asked for
+        if temp_slides_dir.is_some() {
+            output.slides.clear();
+        }
+    }
+
+    // PDF straight from the HTML
+    if wants(Artifact::Pdf) {
+        let pdf_path = config.output_dir.join(format!("{}.pdf", stem));
+        session
+            .generate_pdf(&render_html.0, &pdf_path, &render_config)
+            .map_err(rendered_from_markdown)?;
+        info!("PDF written: {:?}", pdf_path);
+        output.pdf = Some(pdf_path);
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,164 @@
+// ABOUTME: On-disk cache for remote resources such as CSS, JavaScript, images and fonts
+// ABOUTME: Keeps downloads in the user cache directory and revalidates them with ETag/Last-Modified
+
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::errors::{BigError, Result};
+
+/// How long a cached resource is used before it is revalidated with the server
+pub const MAX_AGE_HOURS: i64 = 24;
+
+/// How remote resources are fetched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CachePolicy {
+    /// Use fresh cached copies and revalidate stale ones
+    #[default]
+    Normal,
+    /// Only use cached copies, never the network
+    Offline,
+    /// Revalidate every cached copy with the server
+    Refresh,
+}
+
+static POLICY: RwLock<CachePolicy> = RwLock::new(CachePolicy::Normal);
+
+/// Set how remote resources are fetched for the rest of the process
+pub fn set_policy(policy: CachePolicy) {
+    *POLICY.write().unwrap_or_else(|e| e.into_inner()) = policy;
+}
+
+/// How remote resources are currently fetched
+pub fn policy() -> CachePolicy {
+    *POLICY.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// What is known about a cached resource
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// URL the resource was downloaded from
+    pub url: String,
+
+    /// ETag sent by the server, if any
+    pub etag: Option<String>,
+
+    /// Last-Modified date sent by the server, if any
+    pub last_modified: Option<String>,
+
+    /// When the resource was last downloaded or revalidated
+    pub fetched_at: DateTime<Utc>,
+
+    /// Size of the cached content in bytes
+    pub size: u64,
+}
+
+impl CacheEntry {
+    /// Whether the entry is recent enough to use without asking the server
+    pub fn is_fresh(&self) -> bool {
+        Utc::now() - self.fetched_at < Duration::hours(MAX_AGE_HOURS)
+    }
+}
+
+/// A directory of cached resources, keyed by URL
+pub struct ResourceCache {
+    dir: PathBuf,
+}
+
+impl ResourceCache {
+    /// Use `dir` to hold the cache
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The cache in the user cache directory (`$XDG_CACHE_HOME/big-slides` on Linux)
+    pub fn open() -> Result<Self> {
+        let base = dirs::cache_dir().ok_or_else(|| {
+            BigError::ConfigError("Could not determine the user cache directory".to_string())
+        })?;
+        Ok(Self::new(base.join("big-slides").join("resources")))
+    }
+
+    /// Directory holding the cached files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Cached content and metadata for `url`, if present and readable
+    pub fn get(&self, url: &str) -> Option<(CacheEntry, Vec<u8>)> {
+        let (meta_path, body_path) = self.paths(url);
+        let meta = fs::read_to_string(meta_path).ok()?;
+        let entry: CacheEntry = match toml::from_str(&meta) {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Ignoring unreadable cache entry for {}: {}", url, e);
+                return None;
+            }
+        };
+        let content = fs::read(body_path).ok()?;
+        Some((entry, content))
+    }
+
+    /// Store the content of a resource along with its metadata
+    pub fn put(&self, entry: &CacheEntry, content: &[u8]) -> Result<()> {
+        let (_, body_path) = self.paths(&entry.url);
+        fs::create_dir_all(&self.dir).map_err(BigError::FileReadError)?;
+        fs::write(body_path, content).map_err(BigError::FileReadError)?;
+        self.update(entry)
+    }
+
+    /// Rewrite the metadata of a cached resource, e.g. after revalidating it
+    pub fn update(&self, entry: &CacheEntry) -> Result<()> {
+        let (meta_path, _) = self.paths(&entry.url);
+        let meta = toml::to_string(entry)
+            .map_err(|e| BigError::ConfigError(format!("Failed to write cache entry: {}", e)))?;
+        fs::write(meta_path, meta).map_err(BigError::FileReadError)
+    }
+
+    /// All cached resources, sorted by URL
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for file in fs::read_dir(&self.dir).map_err(BigError::FileReadError)? {
+            let path = file.map_err(BigError::FileReadError)?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("toml") {
+                continue;
+            }
+            let meta = fs::read_to_string(&path).map_err(BigError::FileReadError)?;
+            match toml::from_str(&meta) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!("Skipping unreadable cache entry {:?}: {}", path, e),
+            }
+        }
+        entries.sort_by(|a: &CacheEntry, b| a.url.cmp(&b.url));
+        Ok(entries)
+    }
+
+    /// Remove every cached resource, returning how many there were
+    pub fn clear(&self) -> Result<usize> {
+        let count = self.entries()?.len();
+        if self.dir.is_dir() {
+            fs::remove_dir_all(&self.dir).map_err(BigError::FileReadError)?;
+        }
+        Ok(count)
+    }
+
+    /// Metadata and content paths for `url`
+    fn paths(&self, url: &str) -> (PathBuf, PathBuf) {
+        let key: String = Sha256::digest(url.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        (
+            self.dir.join(format!("{}.toml", key)),
+            self.dir.join(format!("{}.body", key)),
+        )
+    }
+}
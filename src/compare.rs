@@ -0,0 +1,231 @@
+// ABOUTME: Visual regression checks for rendered slides
+// ABOUTME: Diffs slide images against a baseline directory and writes highlighted diff images
+
+use image::{Rgba, RgbaImage};
+use log::info;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::errors::{BigError, Result};
+use crate::pptx;
+
+/// Colour of changed pixels in diff images
+const HIGHLIGHT: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+/// Configuration for comparing two directories of slide images
+pub struct CompareConfig {
+    /// Directory of known-good slide images
+    pub baseline_dir: PathBuf,
+
+    /// Directory of freshly rendered slide images
+    pub current_dir: PathBuf,
+
+    /// Directory diff images are written to; none are written if `None`
+    pub diff_dir: Option<PathBuf>,
+
+    /// Pattern matching the slide images in both directories
+    pub pattern: String,
+
+    /// Percentage of changed pixels above which a slide counts as changed
+    pub threshold: f64,
+
+    /// Largest per-channel difference that still counts as the same pixel,
+    /// so antialiasing and compression noise don't register as changes
+    pub tolerance: u8,
+}
+
+impl Default for CompareConfig {
+    fn default() -> Self {
+        Self {
+            baseline_dir: PathBuf::new(),
+            current_dir: PathBuf::new(),
+            diff_dir: None,
+            pattern: "slide_*.png".to_string(),
+            threshold: 0.1,
+            tolerance: 8,
+        }
+    }
+}
+
+/// How a slide image differs from its baseline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Difference {
+    /// Both images have the same size; `score` is the percentage of pixels that changed
+    Pixels { score: f64 },
+    /// The images have different sizes, so every pixel counts as changed
+    Resized {
+        baseline: (u32, u32),
+        current: (u32, u32),
+    },
+    /// The slide is new
+    Added,
+    /// The slide is gone
+    Removed,
+}
+
+impl Difference {
+    /// Percentage of pixels that changed, 100 unless both images have the same size
+    pub fn score(&self) -> f64 {
+        match self {
+            Difference::Pixels { score } => *score,
+            _ => 100.0,
+        }
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Pixels { score } => write!(f, "{:.3}% of pixels changed", score),
+            Difference::Resized { baseline, current } => write!(
+                f,
+                "resized from {}x{} to {}x{}",
+                baseline.0, baseline.1, current.0, current.1
+            ),
+            Difference::Added => write!(f, "not in the baseline"),
+            Difference::Removed => write!(f, "missing from the current slides"),
+        }
+    }
+}
+
+/// The comparison of one slide image with its baseline
+#[derive(Debug, Clone)]
+pub struct SlideComparison {
+    /// File name of the slide image, the same in both directories
+    pub file: String,
+
+    pub difference: Difference,
+
+    /// Image highlighting the changed pixels, if one was written
+    pub diff_image: Option<PathBuf>,
+}
+
+impl SlideComparison {
+    /// Whether the slide changed by more than `threshold` percent of its pixels
+    pub fn exceeds(&self, threshold: f64) -> bool {
+        self.difference.score() > threshold
+    }
+}
+
+/// Compare every slide image in the current directory with the image of the same name
+/// in the baseline, in file name order. Slides only found on one side are reported as
+/// added or removed.
+pub fn compare_slides(config: &CompareConfig) -> Result<Vec<SlideComparison>> {
+    info!(
+        "Comparing {:?} against baseline {:?}",
+        config.current_dir, config.baseline_dir
+    );
+
+    let by_name = |dir: &Path| -> Result<BTreeMap<String, PathBuf>> {
+        Ok(pptx::find_slide_images(dir, &config.pattern)?
+            .into_iter()
+            .filter_map(|path| Some((path.file_name()?.to_string_lossy().into_owned(), path)))
+            .collect())
+    };
+    let baseline = by_name(&config.baseline_dir)?;
+    let current = by_name(&config.current_dir)?;
+
+    if let Some(diff_dir) = &config.diff_dir {
+        fs::create_dir_all(diff_dir).map_err(BigError::FileReadError)?;
+
+        // Diff images left by an earlier comparison would pass for current ones
+        for stale in pptx::find_slide_images(diff_dir, "*.diff.png").unwrap_or_default() {
+            fs::remove_file(&stale).map_err(BigError::FileReadError)?;
+        }
+    }
+
+    let mut names: Vec<&String> = baseline.keys().chain(current.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut comparisons = Vec::with_capacity(names.len());
+    for name in names {
+        let (difference, diff) = match (baseline.get(name), current.get(name)) {
+            (Some(baseline), Some(current)) => {
+                let baseline = open_image(baseline)?;
+                let current = open_image(current)?;
+                if baseline.dimensions() == current.dimensions() {
+                    let (score, diff) = diff_images(&baseline, &current, config.tolerance);
+                    (Difference::Pixels { score }, (score > 0.0).then_some(diff))
+                } else {
+                    let difference = Difference::Resized {
+                        baseline: baseline.dimensions(),
+                        current: current.dimensions(),
+                    };
+                    (difference, None)
+                }
+            }
+            (None, Some(_)) => (Difference::Added, None),
+            (Some(_), None) => (Difference::Removed, None),
+            (None, None) => unreachable!("every name comes from one of the directories"),
+        };
+
+        // Diff images are PNG whatever the slides are, named after the slide
+        let diff_image = match (diff, &config.diff_dir) {
+            (Some(diff), Some(diff_dir)) => {
+                let stem = Path::new(name).file_stem().unwrap_or_default();
+                let path = diff_dir.join(format!("{}.diff.png", stem.to_string_lossy()));
+                diff.save(&path).map_err(|e| {
+                    BigError::ValidationError(format!("Failed to write {:?}: {}", path, e))
+                })?;
+                Some(path)
+            }
+            _ => None,
+        };
+
+        comparisons.push(SlideComparison {
+            file: name.clone(),
+            difference,
+            diff_image,
+        });
+    }
+
+    Ok(comparisons)
+}
+
+fn open_image(path: &Path) -> Result<RgbaImage> {
+    image::open(path)
+        .map(|image| image.to_rgba8())
+        .map_err(|e| BigError::ValidationError(format!("Failed to read image {:?}: {}", path, e)))
+}
+
+/// Compare two images of the same size pixel by pixel. Returns the percentage of pixels
+/// where a channel differs by more than `tolerance`, and a faded copy of `current` with
+/// those pixels highlighted.
+pub fn diff_images(baseline: &RgbaImage, current: &RgbaImage, tolerance: u8) -> (f64, RgbaImage) {
+    let (width, height) = current.dimensions();
+    let mut diff = RgbaImage::new(width, height);
+    let mut changed: u64 = 0;
+
+    for ((before, after), out) in baseline
+        .pixels()
+        .zip(current.pixels())
+        .zip(diff.pixels_mut())
+    {
+        let differs = before
+            .0
+            .iter()
+            .zip(after.0.iter())
+            .any(|(a, b)| a.abs_diff(*b) > tolerance);
+        *out = if differs {
+            changed += 1;
+            HIGHLIGHT
+        } else {
+            // Keep the slide recognisable without competing with the highlight
+            let [r, g, b, _] = after.0;
+            let luma = (u32::from(r) * 299 + u32::from(g) * 587 + u32::from(b) * 114) / 1000;
+            let faded = (255 - (255 - luma) / 4) as u8;
+            Rgba([faded, faded, faded, 255])
+        };
+    }
+
+    let total = u64::from(width) * u64::from(height);
+    let score = if total == 0 {
+        0.0
+    } else {
+        changed as f64 * 100.0 / total as f64
+    };
+    (score, diff)
+}
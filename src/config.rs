@@ -1,11 +1,30 @@
 // ABOUTME: Configuration module for the big-slides application
-// ABOUTME: Provides configuration settings and environment variable handling
+// ABOUTME: Provides configuration settings, environment variable and big.toml handling
 
-// No need for these imports
+use crate::build::Artifact;
+use crate::cache::CachePolicy;
+use crate::errors::{BigError, Result};
+use crate::overflow::DEFAULT_MIN_FONT_PX;
 use crate::pptx::PptxConfig;
-use crate::render::RenderConfig;
+use crate::render::{RenderConfig, SlideSelection, DEFAULT_SLIDE_TIMEOUT_MS};
+use crate::resources;
+use crate::transition::{self, Transition, TransitionEffect, TransitionSpeed};
+use log::{info, warn};
+use std::cell::RefCell;
 use std::env;
-use std::path::PathBuf;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Name of the per-deck project configuration file
+pub const PROJECT_CONFIG_FILE: &str = "big.toml";
+
+/// CSS used when none is configured, bundled into the binary
+pub const DEFAULT_CSS: &str = "builtin:big.css";
+
+/// JavaScript used when none is configured, bundled into the binary
+pub const DEFAULT_JS: &str = "builtin:big.js";
 
 /// Global configuration for the application
 pub struct Config {
@@ -15,6 +34,7 @@ pub struct Config {
     pub embed_resources: bool,
     pub default_css: String,
     pub default_js: String,
+    pub cache_policy: CachePolicy,
 }
 
 impl Default for Config {
@@ -24,10 +44,9 @@ impl Default for Config {
             html_template_path: None,
             default_timeout_ms: 30000, // 30 seconds
             embed_resources: true,
-            default_css: "https://raw.githubusercontent.com/harperreed/big/gh-pages/big.css"
-                .to_string(),
-            default_js: "https://raw.githubusercontent.com/harperreed/big/gh-pages/big.js"
-                .to_string(),
+            default_css: DEFAULT_CSS.to_string(),
+            default_js: DEFAULT_JS.to_string(),
+            cache_policy: CachePolicy::Normal,
         }
     }
 }
@@ -51,12 +70,8 @@ impl Config {
             .map(|s| s.to_lowercase() != "false")
             .unwrap_or(true);
 
-        let default_css = env::var("DEFAULT_CSS").unwrap_or_else(|_| {
-            "https://raw.githubusercontent.com/harperreed/big/gh-pages/big.css".to_string()
-        });
-        let default_js = env::var("DEFAULT_JS").unwrap_or_else(|_| {
-            "https://raw.githubusercontent.com/harperreed/big/gh-pages/big.js".to_string()
-        });
+        let default_css = env::var("DEFAULT_CSS").unwrap_or_else(|_| DEFAULT_CSS.to_string());
+        let default_js = env::var("DEFAULT_JS").unwrap_or_else(|_| DEFAULT_JS.to_string());
 
         Self {
             browser_path,
@@ -65,9 +80,31 @@ impl Config {
             embed_resources,
             default_css,
             default_js,
+            cache_policy: CachePolicy::Normal,
         }
     }
 
+    /// Load configuration from the command line, environment and project file, in that order
+    pub fn from_project(
+        project: &ProjectConfig,
+        browser_path: Option<String>,
+        offline: Option<bool>,
+    ) -> Result<Self> {
+        let mut config = Self::from_env();
+        config.browser_path =
+            project.resolve_optional("browser_path", browser_path, Some("BROWSER_PATH"))?;
+        config.default_timeout_ms = project.resolve(
+            "timeout_ms",
+            None,
+            Some("DEFAULT_TIMEOUT_MS"),
+            config.default_timeout_ms,
+        )?;
+        if project.resolve("offline", offline, Some("BIG_OFFLINE"), false)? {
+            config.cache_policy = CachePolicy::Offline;
+        }
+        Ok(config)
+    }
+
     /// Get a render configuration with defaults from this config
     pub fn get_render_config(
         &self,
@@ -80,10 +117,20 @@ impl Config {
         RenderConfig {
             width: width.unwrap_or(1920),
             height: height.unwrap_or(1080),
+            scale: 1.0,
             format: format.unwrap_or_else(|| "png".to_string()),
+            quality: None,
             base_name: base_name.unwrap_or_else(|| "slide".to_string()),
             timeout_ms: timeout_ms.unwrap_or(self.default_timeout_ms),
             browser_path: self.browser_path.clone(),
+            jobs: 1,
+            slide_timeout_ms: DEFAULT_SLIDE_TIMEOUT_MS,
+            expected_slides: None,
+            slides: None,
+            incremental: false,
+            min_font_px: DEFAULT_MIN_FONT_PX,
+            overflow_report: None,
+            fail_on_overflow: false,
         }
     }
 
@@ -93,11 +140,446 @@ impl Config {
         title: Option<String>,
         pattern: Option<String>,
         aspect_ratio: Option<String>,
+        markdown_path: Option<PathBuf>,
     ) -> PptxConfig {
         PptxConfig {
             title: title.unwrap_or_else(|| "Presentation".to_string()),
             pattern: pattern.unwrap_or_else(|| "*.png".to_string()),
             aspect_ratio: aspect_ratio.unwrap_or_else(|| "16:9".to_string()),
+            markdown_path,
+            native: false,
+            splice_dir: None,
+            transition: Transition::default(),
+            template: None,
+        }
+    }
+}
+
+/// Where a configuration value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// Command line argument
+    Cli,
+    /// Environment variable
+    Env(String),
+    /// Project config file
+    File(PathBuf),
+    /// Frontmatter of the markdown deck
+    Frontmatter(PathBuf),
+    /// Built-in default
+    Default,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Cli => write!(f, "command line"),
+            ConfigSource::Env(name) => write!(f, "environment ({})", name),
+            ConfigSource::File(path) => write!(f, "{}", path.display()),
+            ConfigSource::Frontmatter(path) => write!(f, "frontmatter of {}", path.display()),
+            ConfigSource::Default => write!(f, "built-in default"),
+        }
+    }
+}
+
+/// A setting that can be read from the environment or a project config file
+pub trait ConfigValue: Sized {
+    /// Parse the value from an environment variable
+    fn from_env(value: &str) -> Option<Self>;
+
+    /// Read the value from big.toml, resolving relative paths against the file's directory
+    fn from_toml(value: &toml::Value, base_dir: &Path) -> Option<Self>;
+
+    /// Render the value for `config show`
+    fn describe(&self) -> String;
+}
+
+impl ConfigValue for String {
+    fn from_env(value: &str) -> Option<Self> {
+        Some(value.to_string())
+    }
+
+    fn from_toml(value: &toml::Value, _base_dir: &Path) -> Option<Self> {
+        value.as_str().map(str::to_string)
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl ConfigValue for bool {
+    fn from_env(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "true" | "1" | "yes" => Some(true),
+            "false" | "0" | "no" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn from_toml(value: &toml::Value, _base_dir: &Path) -> Option<Self> {
+        value.as_bool()
+    }
+
+    fn describe(&self) -> String {
+        self.to_string()
+    }
+}
+
+macro_rules! integer_config_value {
+    ($($t:ty),*) => {
+        $(
+            impl ConfigValue for $t {
+                fn from_env(value: &str) -> Option<Self> {
+                    value.trim().parse().ok()
+                }
+
+                fn from_toml(value: &toml::Value, _base_dir: &Path) -> Option<Self> {
+                    value.as_integer().and_then(|n| <$t>::try_from(n).ok())
+                }
+
+                fn describe(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+integer_config_value!(u8, u16, u32, u64, usize);
+
+impl ConfigValue for f64 {
+    fn from_env(value: &str) -> Option<Self> {
+        value.trim().parse().ok()
+    }
+
+    fn from_toml(value: &toml::Value, _base_dir: &Path) -> Option<Self> {
+        value
+            .as_float()
+            .or_else(|| value.as_integer().map(|n| n as f64))
+    }
+
+    fn describe(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ConfigValue for PathBuf {
+    fn from_env(value: &str) -> Option<Self> {
+        Some(PathBuf::from(value))
+    }
+
+    fn from_toml(value: &toml::Value, base_dir: &Path) -> Option<Self> {
+        value.as_str().map(|path| base_dir.join(path))
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self.display().to_string())
+    }
+}
+
+/// Resource lists (CSS/JS): URLs are kept as-is, local paths are relative to big.toml
+impl ConfigValue for Vec<String> {
+    fn from_env(value: &str) -> Option<Self> {
+        Some(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+
+    fn from_toml(value: &toml::Value, base_dir: &Path) -> Option<Self> {
+        let items = match value {
+            toml::Value::String(s) => vec![s.as_str()],
+            toml::Value::Array(items) => items
+                .iter()
+                .map(toml::Value::as_str)
+                .collect::<Option<Vec<_>>>()?,
+            _ => return None,
+        };
+        Some(
+            items
+                .into_iter()
+                .map(|item| {
+                    if resources::is_local_path(item) {
+                        base_dir.join(item).to_string_lossy().into_owned()
+                    } else {
+                        item.to_string()
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl ConfigValue for Vec<Artifact> {
+    fn from_env(value: &str) -> Option<Self> {
+        value.split(',').map(|s| s.parse().ok()).collect()
+    }
+
+    fn from_toml(value: &toml::Value, _base_dir: &Path) -> Option<Self> {
+        value
+            .as_array()?
+            .iter()
+            .map(|item| item.as_str()?.parse().ok())
+            .collect()
+    }
+
+    fn describe(&self) -> String {
+        let names: Vec<String> = self.iter().map(|a| a.to_string()).collect();
+        format!("{:?}", names)
+    }
+}
+
+impl ConfigValue for SlideSelection {
+    fn from_env(value: &str) -> Option<Self> {
+        value.parse().ok()
+    }
+
+    fn from_toml(value: &toml::Value, _base_dir: &Path) -> Option<Self> {
+        value.as_str()?.parse().ok()
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self.to_string())
+    }
+}
+
+impl ConfigValue for TransitionEffect {
+    fn from_env(value: &str) -> Option<Self> {
+        value.parse().ok()
+    }
+
+    fn from_toml(value: &toml::Value, _base_dir: &Path) -> Option<Self> {
+        value.as_str()?.parse().ok()
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self.to_string())
+    }
+}
+
+impl ConfigValue for TransitionSpeed {
+    fn from_env(value: &str) -> Option<Self> {
+        value.parse().ok()
+    }
+
+    fn from_toml(value: &toml::Value, _base_dir: &Path) -> Option<Self> {
+        value.as_str()?.parse().ok()
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self.to_string())
+    }
+}
+
+/// Durations are written like `5s` or `500ms`; plain numbers are seconds
+impl ConfigValue for Duration {
+    fn from_env(value: &str) -> Option<Self> {
+        transition::parse_duration(value).ok()
+    }
+
+    fn from_toml(value: &toml::Value, _base_dir: &Path) -> Option<Self> {
+        match value {
+            toml::Value::String(s) => transition::parse_duration(s).ok(),
+            toml::Value::Integer(n) => u64::try_from(*n).ok().map(Duration::from_secs),
+            toml::Value::Float(n) => Duration::try_from_secs_f64(*n).ok(),
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl<T: ConfigValue> ConfigValue for Option<T> {
+    fn from_env(value: &str) -> Option<Self> {
+        T::from_env(value).map(Some)
+    }
+
+    fn from_toml(value: &toml::Value, base_dir: &Path) -> Option<Self> {
+        T::from_toml(value, base_dir).map(Some)
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Some(value) => value.describe(),
+            None => "(unset)".to_string(),
+        }
+    }
+}
+
+/// A setting as resolved for `config show`
+#[derive(Debug, Clone)]
+pub struct ResolvedSetting {
+    pub key: String,
+    pub value: String,
+    pub source: ConfigSource,
+}
+
+/// Settings from a big.toml project file.
+/// Values are resolved with the precedence: command line > environment > file > built-in default.
+#[derive(Debug, Default)]
+pub struct ProjectConfig {
+    /// Path of the loaded file, if one was found
+    pub path: Option<PathBuf>,
+    table: toml::Table,
+    resolved: RefCell<Vec<ResolvedSetting>>,
+}
+
+impl ProjectConfig {
+    /// Load a project config file
+    pub fn load(path: &Path) -> Result<Self> {
+        info!("Loading project configuration: {:?}", path);
+        let content = fs::read_to_string(path).map_err(BigError::FileReadError)?;
+        let table = content
+            .parse::<toml::Table>()
+            .map_err(|e| BigError::ConfigError(format!("{}: {}", path.display(), e)))?;
+
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            table,
+            resolved: RefCell::new(Vec::new()),
+        })
+    }
+
+    /// Find big.toml next to the markdown file, then in the current directory.
+    /// Returns an empty config if neither exists.
+    pub fn discover(markdown_path: Option<&Path>) -> Result<Self> {
+        let mut candidates = Vec::new();
+        if let Some(dir) = markdown_path.and_then(Path::parent) {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            candidates.push(dir.join(PROJECT_CONFIG_FILE));
+        }
+        candidates.push(PathBuf::from(PROJECT_CONFIG_FILE));
+
+        match candidates.into_iter().find(|path| path.is_file()) {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Resolve a setting, falling back to `default` when nothing else sets it
+    pub fn resolve<T: ConfigValue>(
+        &self,
+        key: &str,
+        cli: Option<T>,
+        env_var: Option<&str>,
+        default: T,
+    ) -> Result<T> {
+        self.resolve_with_deck(key, cli, env_var, None, default)
+    }
+
+    /// Resolve a setting the deck's frontmatter can also provide.
+    /// The frontmatter sits between big.toml and the built-in default.
+    pub fn resolve_with_deck<T: ConfigValue>(
+        &self,
+        key: &str,
+        cli: Option<T>,
+        env_var: Option<&str>,
+        deck: Option<(T, &Path)>,
+        default: T,
+    ) -> Result<T> {
+        let (value, source) = if let Some(value) = cli {
+            (value, ConfigSource::Cli)
+        } else if let Some((name, value)) = env_var.and_then(|name| {
+            let raw = env::var(name).ok().filter(|v| !v.is_empty())?;
+            match T::from_env(&raw) {
+                Some(value) => Some((name, value)),
+                None => {
+                    warn!("Ignoring invalid value for {}: {:?}", name, raw);
+                    None
+                }
+            }
+        }) {
+            (value, ConfigSource::Env(name.to_string()))
+        } else if let Some(value) = self.file_value(key)? {
+            let path = self.path.clone().unwrap_or_default();
+            (value, ConfigSource::File(path))
+        } else if let Some((value, markdown_path)) = deck {
+            (
+                value,
+                ConfigSource::Frontmatter(markdown_path.to_path_buf()),
+            )
+        } else {
+            (default, ConfigSource::Default)
+        };
+
+        self.resolved.borrow_mut().push(ResolvedSetting {
+            key: key.to_string(),
+            value: value.describe(),
+            source,
+        });
+        Ok(value)
+    }
+
+    /// Resolve a setting that has no built-in default
+    pub fn resolve_optional<T: ConfigValue>(
+        &self,
+        key: &str,
+        cli: Option<T>,
+        env_var: Option<&str>,
+    ) -> Result<Option<T>> {
+        self.resolve(key, cli.map(Some), env_var, None)
+    }
+
+    /// Every setting resolved so far, in resolution order
+    pub fn resolved(&self) -> Vec<ResolvedSetting> {
+        self.resolved.borrow().clone()
+    }
+
+    /// Keys in the file that no resolved setting used, usually typos
+    pub fn unused_keys(&self) -> Vec<String> {
+        let resolved = self.resolved.borrow();
+        let mut unused = Vec::new();
+        for (name, value) in &self.table {
+            let keys: Vec<String> = match value.as_table() {
+                Some(section) => section
+                    .keys()
+                    .map(|key| format!("{}.{}", name, key))
+                    .collect(),
+                None => vec![name.clone()],
+            };
+            unused.extend(
+                keys.into_iter()
+                    .filter(|key| !resolved.iter().any(|setting| &setting.key == key)),
+            );
         }
+        unused
+    }
+
+    /// Look up a dotted key such as `slides.width` in the file
+    fn file_value<T: ConfigValue>(&self, key: &str) -> Result<Option<T>> {
+        let mut parts = key.split('.');
+        let mut value = parts.next().and_then(|first| self.table.get(first));
+        for part in parts {
+            value = value.and_then(|v| v.get(part));
+        }
+        let Some(value) = value else {
+            return Ok(None);
+        };
+
+        let path = self.path.clone().unwrap_or_default();
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        T::from_toml(value, base_dir).map(Some).ok_or_else(|| {
+            BigError::ConfigError(format!(
+                "{}: `{}` has an invalid value: {}",
+                path.display(),
+                key,
+                value
+            ))
+        })
     }
 }
@@ -0,0 +1,421 @@
+// ABOUTME: Environment diagnostics for the big-slides application
+// ABOUTME: Probes browsers, headless launches, output directories and remote resources
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::cache::ResourceCache;
+use crate::errors::BigError;
+use crate::render;
+
+/// Browser names looked up on `PATH`, in the order headless_chrome tries them
+const BROWSER_NAMES: &[&str] = &[
+    "google-chrome-stable",
+    "google-chrome",
+    "chromium",
+    "chromium-browser",
+    "microsoft-edge-stable",
+    "chrome",
+    "msedge",
+];
+
+/// Places browsers are installed outside `PATH`
+const BROWSER_LOCATIONS: &[&str] = &[
+    "/opt/google/chrome/chrome",
+    "/snap/bin/chromium",
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+    r"C:\Program Files\Google\Chrome\Application\chrome.exe",
+    r"C:\Program Files (x86)\Google\Chrome\Application\chrome.exe",
+    r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
+];
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Failed,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warning => "warn",
+            CheckStatus::Failed => "FAIL",
+        };
+        f.pad(label)
+    }
+}
+
+/// The result of one diagnostic, with a suggested fix when something is wrong
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warning(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Warning,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn failed(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Failed,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// What the doctor should look at
+pub struct DoctorConfig {
+    /// Browser path from the command line, environment or big.toml, as renders resolve it
+    pub browser_path: Option<String>,
+
+    /// Directories outputs will be written to
+    pub output_dirs: Vec<PathBuf>,
+
+    /// Remote CSS/JS the decks use
+    pub remote_resources: Vec<String>,
+
+    /// Only check the cache for remote resources, as offline renders do
+    pub offline: bool,
+
+    /// How long to wait for each remote resource
+    pub network_timeout: Duration,
+}
+
+impl Default for DoctorConfig {
+    fn default() -> Self {
+        Self {
+            browser_path: None,
+            output_dirs: vec![PathBuf::from(".")],
+            remote_resources: Vec::new(),
+            offline: false,
+            network_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Run every check
+pub fn run_checks(config: &DoctorConfig) -> Vec<Check> {
+    let mut checks = vec![check_browser_setting(config.browser_path.as_deref())];
+    checks.extend(check_installed_browsers());
+    checks.extend(check_headless_launch(config.browser_path.as_deref()));
+    checks.extend(config.output_dirs.iter().map(|dir| check_output_dir(dir)));
+    checks.extend(
+        config
+            .remote_resources
+            .iter()
+            .map(|url| check_remote_resource(url, config.offline, config.network_timeout)),
+    );
+    checks
+}
+
+/// Report which browser renders will use and whether `BROWSER_PATH` is honored
+pub fn check_browser_setting(browser_path: Option<&str>) -> Check {
+    const NAME: &str = "Browser path";
+    let from_env = env::var("BROWSER_PATH")
+        .ok()
+        .filter(|path| !path.is_empty());
+
+    let Some(path) = browser_path else {
+        return match render::browser_executable(None) {
+            Some(path) => Check::ok(NAME, format!("{} (auto-detected)", path.display())),
+            None => Check::failed(
+                NAME,
+                "No browser configured and none could be found",
+                "Install Chrome or Chromium, or set --browser-path or BROWSER_PATH to its binary",
+            ),
+        };
+    };
+
+    let source = match &from_env {
+        Some(env_path) if env_path == path => " (from BROWSER_PATH)",
+        _ => "",
+    };
+    if !Path::new(path).is_file() {
+        return Check::failed(
+            NAME,
+            format!("{}{} does not exist", path, source),
+            "Point --browser-path, BROWSER_PATH or browser_path in big.toml at an installed Chrome or Chromium",
+        );
+    }
+    match from_env {
+        Some(env_path) if env_path != path => Check::warning(
+            NAME,
+            format!("{} is used; BROWSER_PATH={} is overridden", path, env_path),
+            "Drop --browser-path if BROWSER_PATH should be used",
+        ),
+        _ => Check::ok(NAME, format!("{}{}", path, source)),
+    }
+}
+
+/// List the browsers installed on `PATH` and in the usual locations, with their versions
+pub fn check_installed_browsers() -> Vec<Check> {
+    const NAME: &str = "Installed browser";
+    let mut found: Vec<PathBuf> = Vec::new();
+    let on_path = env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let candidates = BROWSER_NAMES
+        .iter()
+        .flat_map(|name| {
+            on_path
+                .iter()
+                .flat_map(move |dir| [dir.join(name), dir.join(format!("{}.exe", name))])
+        })
+        .chain(BROWSER_LOCATIONS.iter().map(PathBuf::from));
+
+    for candidate in candidates {
+        if !candidate.is_file() {
+            continue;
+        }
+        // Distribution wrappers often link to the same binary
+        let resolved = fs::canonicalize(&candidate).unwrap_or_else(|_| candidate.clone());
+        if !found.contains(&resolved) {
+            found.push(resolved);
+        }
+    }
+
+    if found.is_empty() {
+        return vec![Check::warning(
+            NAME,
+            "No Chrome, Chromium or Edge found on PATH or in the usual locations",
+            "Install Chrome or Chromium (e.g. `apt install chromium`), or set BROWSER_PATH to its binary",
+        )];
+    }
+    found
+        .into_iter()
+        .map(|path| match browser_version(&path) {
+            Some(version) => Check::ok(NAME, format!("{}: {}", path.display(), version)),
+            None => Check::warning(
+                NAME,
+                format!("{}: version unknown", path.display()),
+                format!(
+                    "Run `{} --version` to check the binary starts at all",
+                    path.display()
+                ),
+            ),
+        })
+        .collect()
+}
+
+/// The version a browser binary reports, if it runs
+fn browser_version(path: &Path) -> Option<String> {
+    let output = Command::new(path).arg("--version").output().ok()?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !version.is_empty()).then_some(version)
+}
+
+/// Start the browser renders use headlessly, with and without its sandbox
+pub fn check_headless_launch(browser_path: Option<&str>) -> Vec<Check> {
+    let Some(path) = render::browser_executable(browser_path) else {
+        return Vec::new();
+    };
+    if !path.is_file() {
+        return Vec::new();
+    }
+
+    let attempt = |sandbox: bool| {
+        // A fixed port makes a broken browser fail once instead of on ten ports in turn
+        let port = TcpListener::bind(("127.0.0.1", 0))
+            .and_then(|listener| listener.local_addr())
+            .map(|address| address.port())
+            .ok();
+        let start = Instant::now();
+        render::launch(&path, (800, 600), Duration::from_secs(30), sandbox, port)
+            .and_then(|browser| {
+                browser
+                    .get_version()
+                    .map(|version| version.product)
+                    .map_err(|e| BigError::BrowserError {
+                        message: e.to_string(),
+                        source: None,
+                    })
+            })
+            .map(|product| {
+                format!(
+                    "{} started in {:.1}s",
+                    product,
+                    start.elapsed().as_secs_f64()
+                )
+            })
+            .map_err(|e| e.to_string())
+    };
+    let sandboxed = attempt(true);
+    let unsandboxed = attempt(false);
+
+    let with = "Headless launch with sandbox";
+    let without = "Headless launch without sandbox";
+    match (sandboxed, unsandboxed) {
+        (Ok(detail), Ok(other)) => vec![Check::ok(with, detail), Check::ok(without, other)],
+        (Err(e), Ok(detail)) => vec![
+            Check::warning(
+                with,
+                e,
+                "Chrome's sandbox is unavailable, which is common in containers and as root. \
+                 Renders fall back to launching without it; to keep the sandbox, run as a \
+                 non-root user or enable unprivileged user namespaces",
+            ),
+            Check::ok(without, detail),
+        ],
+        (Ok(detail), Err(e)) => vec![
+            Check::ok(with, detail),
+            Check::warning(
+                without,
+                e,
+                "Renders use the sandbox, so this does not matter",
+            ),
+        ],
+        (Err(e), Err(other)) => {
+            let fix = format!(
+                "Run `{} --headless --dump-dom about:blank` to see Chrome's own error; \
+                 missing shared libraries are the usual cause in containers",
+                path.display()
+            );
+            vec![
+                Check::failed(with, e, fix.clone()),
+                Check::failed(without, other, fix),
+            ]
+        }
+    }
+}
+
+/// Check that outputs can be written to `dir`, or to the directory that will hold it
+pub fn check_output_dir(dir: &Path) -> Check {
+    const NAME: &str = "Output directory";
+    if dir.exists() && !dir.is_dir() {
+        return Check::failed(
+            NAME,
+            format!("{} is a file, not a directory", dir.display()),
+            "Choose another output directory",
+        );
+    }
+
+    // A missing directory is created by the nearest ancestor that exists
+    let existing = dir
+        .ancestors()
+        .map(|ancestor| {
+            if ancestor.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                ancestor
+            }
+        })
+        .find(|ancestor| ancestor.is_dir());
+    let Some(existing) = existing else {
+        return Check::failed(
+            NAME,
+            format!("No parent of {} exists", dir.display()),
+            "Choose an output directory on an existing drive",
+        );
+    };
+
+    let probe = existing.join(format!(".big-doctor-{}.tmp", uuid::Uuid::new_v4()));
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            if existing == dir {
+                Check::ok(NAME, format!("{} is writable", dir.display()))
+            } else {
+                Check::ok(
+                    NAME,
+                    format!(
+                        "{} will be created in {}",
+                        dir.display(),
+                        existing.display()
+                    ),
+                )
+            }
+        }
+        Err(e) => Check::failed(
+            NAME,
+            format!("{} is not writable: {}", existing.display(), e),
+            format!(
+                "Fix the permissions of {} or choose another output directory",
+                existing.display()
+            ),
+        ),
+    }
+}
+
+/// Check that a remote resource can be downloaded, or is cached when offline
+pub fn check_remote_resource(url: &str, offline: bool, timeout: Duration) -> Check {
+    const NAME: &str = "Remote resource";
+    let cached = ResourceCache::open()
+        .ok()
+        .is_some_and(|cache| cache.get(url).is_some());
+
+    if offline {
+        return if cached {
+            Check::ok(NAME, format!("{} is cached", url))
+        } else {
+            Check::failed(
+                NAME,
+                format!("{} is not cached and --offline is set", url),
+                "Build once without --offline to cache it, or use a local copy",
+            )
+        };
+    }
+
+    let response = reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .user_agent(format!("big-slides/{}", env!("CARGO_PKG_VERSION")))
+        .build()
+        .and_then(|client| client.get(url).send());
+    let fallback = if cached {
+        "; a cached copy will be used meanwhile"
+    } else {
+        ""
+    };
+    match response {
+        Ok(response) if response.status().is_success() => {
+            Check::ok(NAME, format!("{} ({})", url, response.status()))
+        }
+        Ok(response) => Check::warning(
+            NAME,
+            format!("{} returned {}", url, response.status()),
+            format!("Check the URL is still correct{}", fallback),
+        ),
+        Err(e) if cached => Check::warning(
+            NAME,
+            format!("{} is unreachable: {}", url, e),
+            format!(
+                "Check your network or proxy (HTTPS_PROXY){}, or run with --offline",
+                fallback
+            ),
+        ),
+        Err(e) => Check::failed(
+            NAME,
+            format!("{} is unreachable: {}", url, e),
+            "Check your network or proxy (HTTPS_PROXY), or download the file and use a local path",
+        ),
+    }
+}
@@ -12,6 +12,9 @@ pub enum BigError {
     #[error("Failed to fetch remote resource: {0}")]
     FetchError(#[from] reqwest::Error),
 
+    #[error("Offline and resource is not cached: {0}")]
+    OfflineError(String),
+
     #[error("Invalid resource path: {0}")]
     InvalidResourcePath(String),
 
@@ -34,6 +37,9 @@ pub enum BigError {
     #[error("PPTX generation error: {0}")]
     PptxError(String),
 
+    #[error("PDF generation error: {0}")]
+    PdfError(String),
+
     #[error("Input validation error: {0}")]
     ValidationError(String),
 
@@ -43,6 +49,25 @@ pub enum BigError {
     #[error("Path not found: {0}")]
     PathNotFoundError(PathBuf),
 
+    #[error("Slide discovery failed: {0}")]
+    SlideDiscoveryError(String),
+
+    #[error("Expected {expected} slides but found {found} in {location}")]
+    SlideCountMismatch {
+        expected: usize,
+        found: usize,
+        location: String,
+    },
+
+    #[error("Content overflows or is clipped on slide(s) {0}")]
+    SlideOverflow(String),
+
+    #[error("Slides differ from the baseline: {0}")]
+    SlidesChanged(String),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(String),
+
     #[error("No slides found matching pattern: {0}")]
     NoSlidesFoundError(String),
 
@@ -55,6 +80,9 @@ pub enum BigError {
     #[error("Watch error: {0}")]
     WatchError(String),
 
+    #[error("{0} environment check(s) failed")]
+    DiagnosticsFailed(usize),
+
     #[error("Unknown error: {0}")]
     UnknownError(String),
 }
@@ -73,4 +101,11 @@ impl From<zip::result::ZipError> for BigError {
     }
 }
 
+// Implement conversion from PDF errors
+impl From<lopdf::Error> for BigError {
+    fn from(err: lopdf::Error) -> Self {
+        BigError::PdfError(format!("PDF operation failed: {}", err))
+    }
+}
+
 pub type Result<T> = std::result::Result<T, BigError>;
@@ -2,12 +2,89 @@
 // ABOUTME: Converts markdown to HTML with styling and embedded resources
 
 use crate::errors::{BigError, Result};
-use crate::resources::ResourceFile;
-use comrak::{markdown_to_html, ComrakOptions};
-use log::info;
+use crate::inline::{InlineConfig, Inliner, Location};
+use crate::resources::{self, ResourceFile};
+use crate::transition::SlideTransition;
+use comrak::nodes::NodeValue;
+use comrak::{markdown_to_html, parse_document, Arena, ComrakOptions};
+use log::{info, warn};
+use quick_xml::escape::escape;
 use std::fs;
 use std::path::Path;
 
+/// Line that separates a slide's content from its speaker notes
+const NOTES_SEPARATOR: &str = "???";
+
+/// Opening of an HTML comment holding speaker notes
+const NOTES_COMMENT_START: &str = "<!-- notes:";
+
+/// A single slide parsed from markdown
+#[derive(Debug, Clone, Default)]
+pub struct Slide {
+    /// Rendered HTML for the slide body
+    pub html: String,
+
+    /// Speaker notes, kept out of the rendered slide
+    pub notes: Option<String>,
+
+    /// Markdown source of the slide body, without notes
+    pub markdown: String,
+
+    /// Transition and timing set by a `<!-- transition: ... -->` comment on the slide
+    pub transition: SlideTransition,
+}
+
+/// Metadata about a deck, read from its frontmatter
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeckMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub date: Option<String>,
+    pub description: Option<String>,
+    pub language: Option<String>,
+
+    /// Stylesheet for the deck's theme, added after the other CSS
+    pub theme: Option<String>,
+
+    /// Aspect ratio for the PPTX (16:9 or 4:3)
+    pub aspect_ratio: Option<String>,
+    pub keywords: Vec<String>,
+
+    /// Extra CSS and JS for this deck, relative to the markdown file
+    pub css: Vec<String>,
+    pub js: Vec<String>,
+
+    /// Any other keys, shown as `<meta>` tags
+    pub extra: Vec<(String, String)>,
+}
+
+/// A parsed markdown deck
+#[derive(Debug, Clone, Default)]
+pub struct Deck {
+    pub metadata: DeckMetadata,
+    pub slides: Vec<Slide>,
+}
+
+/// Parse a markdown file into its metadata and slides
+pub fn parse_deck(markdown_path: &Path) -> Result<Deck> {
+    if !markdown_path.exists() {
+        return Err(BigError::PathNotFoundError(markdown_path.to_path_buf()));
+    }
+
+    let markdown_content = fs::read_to_string(markdown_path).map_err(BigError::FileReadError)?;
+    let (metadata, content) = parse_frontmatter(&markdown_content);
+
+    Ok(Deck {
+        metadata,
+        slides: split_slides(content),
+    })
+}
+
+/// Parse a markdown file into its slides, in presentation order
+pub fn parse_slides(markdown_path: &Path) -> Result<Vec<Slide>> {
+    Ok(parse_deck(markdown_path)?.slides)
+}
+
 /// Generate HTML from a markdown file with optional CSS and JS resources
 /// This version is for backward compatibility with existing test code
 #[allow(dead_code)]
@@ -17,15 +94,25 @@ pub fn generate_html_without_reload(
     js_files: &[ResourceFile],
     embed_resources: bool,
 ) -> Result<String> {
-    generate_html(markdown_path, css_files, js_files, embed_resources, None)
+    generate_html(
+        markdown_path,
+        css_files,
+        js_files,
+        embed_resources,
+        None,
+        None,
+    )
 }
 
-/// Generate HTML from a markdown file with optional CSS and JS resources
+/// Generate HTML from a markdown file with optional CSS and JS resources.
+/// With `inline` set, resources are embedded and the assets they and the slides
+/// reference become data URIs, giving a single self-contained file.
 pub fn generate_html(
     markdown_path: &Path,
     css_files: &[ResourceFile],
     js_files: &[ResourceFile],
     embed_resources: bool,
+    inline: Option<&InlineConfig>,
     auto_reload_script: Option<String>,
 ) -> Result<String> {
     info!("Generating HTML from markdown: {:?}", markdown_path);
@@ -39,26 +126,66 @@ pub fn generate_html(
     let markdown_content = fs::read_to_string(markdown_path).map_err(BigError::FileReadError)?;
 
     // Parse frontmatter and content
-    let (title, _author, _date, content) = parse_frontmatter(&markdown_content);
+    let (metadata, content) = parse_frontmatter(&markdown_content);
 
-    // Process content to handle "#" headers as slide breaks
-    let processed_content = process_content_for_slides(content);
+    // Split content into slides, pulling out any speaker notes
+    let slides = split_slides(content);
 
-    // Convert markdown to HTML, with options to allow raw HTML
-    let mut options = ComrakOptions::default();
-    options.render.unsafe_ = true; // Allow raw HTML
-    let html_content = markdown_to_html(&processed_content, &options);
+    // Resources named in the frontmatter come after the ones we were given
+    let markdown_dir = markdown_path.parent().unwrap_or(Path::new(""));
+    let deck_resource = |path: &String| {
+        if resources::is_local_path(path) {
+            ResourceFile::new(&markdown_dir.join(path).to_string_lossy())
+        } else {
+            ResourceFile::new(path)
+        }
+    };
+    // A bare theme name such as `dark` picks one of the built-in themes
+    let theme = metadata.theme.as_ref().map(|theme| {
+        if Path::new(theme).extension().is_none() && resources::is_local_path(theme) {
+            format!("{}{}.css", resources::BUILTIN_SCHEME, theme)
+        } else {
+            theme.clone()
+        }
+    });
+    let css_files: Vec<ResourceFile> = css_files
+        .iter()
+        .cloned()
+        .chain(metadata.css.iter().map(deck_resource))
+        .chain(theme.iter().map(deck_resource))
+        .collect();
+    let js_files: Vec<ResourceFile> = js_files
+        .iter()
+        .cloned()
+        .chain(metadata.js.iter().map(deck_resource))
+        .collect();
 
     // Build the full HTML document
-    let mut html_doc = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    let language = metadata.language.as_deref().unwrap_or("en");
+    let mut html_doc = format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n",
+        escape(language)
+    );
     html_doc.push_str("<meta charset=\"UTF-8\">\n");
     html_doc
         .push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0, maximum-scale=1.0, user-scalable=0\">\n");
-    html_doc.push_str(&format!("<title>{}</title>\n", title));
+    let title = metadata.title.as_deref().unwrap_or("Presentation");
+    html_doc.push_str(&format!("<title>{}</title>\n", escape(title)));
+    html_doc.push_str(&metadata_tags(&metadata));
+
+    let inliner = inline.map(Inliner::new);
+    let embed_resources = embed_resources || inliner.is_some();
+    let markdown_location = Location::Dir(markdown_dir.to_path_buf());
 
     // Add CSS
-    for css in css_files {
-        match css.tag("css", embed_resources) {
+    for css in &css_files {
+        let tag = match (&inliner, Location::of(css)) {
+            (Some(inliner), Some(location)) => css.content().map(|content| {
+                format!("<style>{}</style>", inliner.inline_css(&content, &location))
+            }),
+            _ => css.tag("css", embed_resources),
+        };
+        match tag {
             Ok(tag) => {
                 html_doc.push_str(&tag);
                 html_doc.push('\n');
@@ -75,23 +202,20 @@ pub fn generate_html(
 
     html_doc.push_str("</head>\n<body>\n");
 
-    // Process raw HTML to split into slides
-    // Output directly as divs under body, matching Python renderer
-    let slides = extract_slides(&html_content);
-    for slide in slides {
+    // Output slides directly as divs under body, matching Python renderer
+    for slide in &slides {
         html_doc.push_str("<div>");
-
-        // Extract slide title and remove any paragraph tags for clean output
-        let processed_content = extract_slide_content(&slide);
-
-        // Add the processed content
-        html_doc.push_str(&processed_content);
-
+        match &inliner {
+            Some(inliner) => {
+                html_doc.push_str(&inliner.inline_html(&slide.html, &markdown_location))
+            }
+            None => html_doc.push_str(&slide.html),
+        }
         html_doc.push_str("</div>\n");
     }
 
     // Add JavaScript
-    for js in js_files {
+    for js in &js_files {
         match js.tag("js", embed_resources) {
             Ok(tag) => {
                 html_doc.push_str(&tag);
@@ -117,22 +241,104 @@ pub fn generate_html(
     Ok(html_doc)
 }
 
-/// Parse frontmatter in the format: % Title\n% Author\n% Date
-fn parse_frontmatter(content: &str) -> (String, String, String, String) {
-    let lines: Vec<&str> = content.lines().collect();
+/// `<meta>` tags describing the deck
+fn metadata_tags(metadata: &DeckMetadata) -> String {
+    let mut tags = Vec::new();
+    let mut push = |name: &str, content: &str| {
+        tags.push(format!(
+            "<meta name=\"{}\" content=\"{}\">\n",
+            escape(name),
+            escape(content)
+        ));
+    };
+
+    if let Some(author) = &metadata.author {
+        push("author", author);
+    }
+    if let Some(description) = &metadata.description {
+        push("description", description);
+    }
+    if !metadata.keywords.is_empty() {
+        push("keywords", &metadata.keywords.join(", "));
+    }
+    if let Some(date) = &metadata.date {
+        push("date", date);
+    }
+    for (name, value) in &metadata.extra {
+        push(name, value);
+    }
+
+    tags.concat()
+}
+
+/// A value from a YAML or TOML frontmatter block
+enum FrontmatterValue {
+    Text(String),
+    List(Vec<String>),
+}
+
+impl FrontmatterValue {
+    fn into_text(self) -> String {
+        match self {
+            FrontmatterValue::Text(text) => text,
+            FrontmatterValue::List(items) => items.join(", "),
+        }
+    }
 
-    // Default values
-    let mut title = "Presentation".to_string();
-    let mut author = "".to_string();
-    let mut date = "".to_string();
+    fn into_list(self, split_commas: bool) -> Vec<String> {
+        match self {
+            FrontmatterValue::Text(text) if split_commas => text
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            FrontmatterValue::Text(text) => vec![text],
+            FrontmatterValue::List(items) => items,
+        }
+    }
+}
+
+impl DeckMetadata {
+    /// Build metadata from frontmatter keys, keeping unknown keys as extras
+    fn from_entries(entries: Vec<(String, FrontmatterValue)>) -> Self {
+        let mut metadata = DeckMetadata::default();
+        for (key, value) in entries {
+            match key.to_lowercase().replace('-', "_").as_str() {
+                "title" => metadata.title = Some(value.into_text()),
+                "author" => metadata.author = Some(value.into_text()),
+                "date" => metadata.date = Some(value.into_text()),
+                "description" => metadata.description = Some(value.into_text()),
+                "lang" | "language" => metadata.language = Some(value.into_text()),
+                "theme" => metadata.theme = Some(value.into_text()),
+                "aspect_ratio" | "aspectratio" => metadata.aspect_ratio = Some(value.into_text()),
+                "keywords" | "tags" => metadata.keywords = value.into_list(true),
+                "css" => metadata.css = value.into_list(false),
+                "js" => metadata.js = value.into_list(false),
+                _ => metadata.extra.push((key, value.into_text())),
+            }
+        }
+        metadata
+    }
+}
+
+/// Parse frontmatter, returning the deck metadata and the remaining content.
+/// Accepts a `---` YAML block, a `+++` TOML block, or the `% Title\n% Author\n% Date` form.
+fn parse_frontmatter(content: &str) -> (DeckMetadata, String) {
+    if let Some(parsed) = parse_fenced_frontmatter(content) {
+        return parsed;
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut metadata = DeckMetadata::default();
+    let field = |line: &str| Some(line.trim_start_matches("% ").trim().to_string());
 
     // Check if we have frontmatter
     if lines.len() >= 3 && lines[0].starts_with("% ") {
-        title = lines[0].trim_start_matches("% ").trim().to_string();
+        metadata.title = field(lines[0]);
         if lines.len() >= 4 && lines[1].starts_with("% ") {
-            author = lines[1].trim_start_matches("% ").trim().to_string();
+            metadata.author = field(lines[1]).filter(|s| !s.is_empty());
             if lines[2].starts_with("% ") {
-                date = lines[2].trim_start_matches("% ").trim().to_string();
+                metadata.date = field(lines[2]).filter(|s| !s.is_empty());
 
                 // Find the first empty line after frontmatter
                 let mut start_idx = 3;
@@ -142,22 +348,118 @@ fn parse_frontmatter(content: &str) -> (String, String, String, String) {
                 start_idx = std::cmp::min(start_idx + 1, lines.len());
 
                 // Return the rest of the content
-                return (title, author, date, lines[start_idx..].join("\n"));
+                return (metadata, lines[start_idx..].join("\n"));
             }
         }
     }
 
     // If we didn't find frontmatter, return the original content
-    (title, author, date, content.to_string())
+    (metadata, content.to_string())
+}
+
+/// Parse a `---` (YAML) or `+++` (TOML) frontmatter block at the top of the content.
+/// A `---` block that isn't a YAML mapping is left alone, since `---` also separates slides.
+fn parse_fenced_frontmatter(content: &str) -> Option<(DeckMetadata, String)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let fence = lines.first()?.trim_end();
+    if fence != "---" && fence != "+++" {
+        return None;
+    }
+
+    let end = lines[1..]
+        .iter()
+        .position(|line| line.trim_end() == fence)?
+        + 1;
+    let block = lines[1..end].join("\n");
+    let entries = if fence == "+++" {
+        toml_entries(&block)
+    } else {
+        yaml_entries(&block)
+    }?;
+
+    let rest = lines[end + 1..].join("\n");
+    Some((DeckMetadata::from_entries(entries), rest))
+}
+
+/// Top-level keys of a YAML mapping
+fn yaml_entries(block: &str) -> Option<Vec<(String, FrontmatterValue)>> {
+    fn scalar(value: &serde_norway::Value) -> Option<String> {
+        match value {
+            serde_norway::Value::String(s) => Some(s.clone()),
+            serde_norway::Value::Number(n) => Some(n.to_string()),
+            serde_norway::Value::Bool(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+
+    let mapping = match serde_norway::from_str::<serde_norway::Value>(block) {
+        Ok(serde_norway::Value::Mapping(mapping)) => mapping,
+        Ok(_) => return None,
+        Err(e) => {
+            warn!("Ignoring frontmatter that isn't valid YAML: {}", e);
+            return None;
+        }
+    };
+
+    let mut entries = Vec::new();
+    for (key, value) in &mapping {
+        let Some(key) = scalar(key) else { continue };
+        let value = match value {
+            serde_norway::Value::Sequence(items) => {
+                FrontmatterValue::List(items.iter().filter_map(scalar).collect())
+            }
+            other => match scalar(other) {
+                Some(text) => FrontmatterValue::Text(text),
+                None => continue,
+            },
+        };
+        entries.push((key, value));
+    }
+    Some(entries)
+}
+
+/// Top-level keys of a TOML table
+fn toml_entries(block: &str) -> Option<Vec<(String, FrontmatterValue)>> {
+    fn scalar(value: &toml::Value) -> Option<String> {
+        match value {
+            toml::Value::String(s) => Some(s.clone()),
+            toml::Value::Table(_) | toml::Value::Array(_) => None,
+            other => Some(other.to_string()),
+        }
+    }
+
+    let table = match block.parse::<toml::Table>() {
+        Ok(table) => table,
+        Err(e) => {
+            warn!("Ignoring frontmatter that isn't valid TOML: {}", e);
+            return None;
+        }
+    };
+
+    let mut entries = Vec::new();
+    for (key, value) in table {
+        let value = match &value {
+            toml::Value::Array(items) => {
+                FrontmatterValue::List(items.iter().filter_map(scalar).collect())
+            }
+            other => match scalar(other) {
+                Some(text) => FrontmatterValue::Text(text),
+                None => continue,
+            },
+        };
+        entries.push((key, value));
+    }
+    Some(entries)
 }
 
-/// Process content to convert headers to slide breaks
-fn process_content_for_slides(content: String) -> String {
+/// Process content into slide sections, starting a new section at each "#" header
+fn process_content_for_slides(content: String) -> Vec<String> {
     // Replace "#" that are not part of headings with "!--HASH--!"
     let content = content.replace(r"\#", "!--HASH--!");
 
     // Split content by lines to properly handle headers
     let lines: Vec<&str> = content.lines().collect();
+    let mut sections = Vec::new();
     let mut result = String::new();
     let mut is_first_section = true;
 
@@ -172,9 +474,9 @@ fn process_content_for_slides(content: String) -> String {
                          (trimmed.chars().nth(1).unwrap() == ' ' || // "# Text"
                           !trimmed.chars().nth(1).unwrap().is_whitespace()))); // "#Text"
 
-        // Add slide break for headers (except the first one)
+        // Start a new section for headers (except the first one)
         if is_header && !is_first_section {
-            result.push_str("\n\n---\n\n");
+            sections.push(std::mem::take(&mut result));
         }
 
         // For "#Text" format (no space), convert to "# Text" format for proper markdown rendering
@@ -195,18 +497,128 @@ fn process_content_for_slides(content: String) -> String {
             is_first_section = false;
         }
     }
+    sections.push(result);
 
     // Restore any escaped hashes
-    result.replace("!--HASH--!", "#")
+    sections
+        .into_iter()
+        .map(|section| section.replace("!--HASH--!", "#"))
+        .collect()
+}
+
+/// Split markdown content into rendered slides.
+/// Every "#" header starts a new slide, and a `---` rule inside a section splits it further.
+fn split_slides(content: String) -> Vec<Slide> {
+    // Convert markdown to HTML, with options to allow raw HTML
+    let mut options = ComrakOptions::default();
+    options.render.unsafe_ = true; // Allow raw HTML
+
+    let mut slides = Vec::new();
+    for section in process_content_for_slides(content) {
+        // Notes belong to the slide they were written in, so split the section first
+        for part in split_on_rules(&section, &options) {
+            let (part, notes) = extract_notes(&part);
+            let (part, transition) = extract_transition(&part);
+            let html_content = markdown_to_html(&part, &options);
+            slides.push(Slide {
+                html: extract_slide_content(html_content.trim()),
+                notes,
+                markdown: part,
+                transition,
+            });
+        }
+    }
+
+    slides
+}
+
+/// Split a slide section at its top-level `---` rules, returning the markdown of each part
+fn split_on_rules(body: &str, options: &ComrakOptions) -> Vec<String> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, body, options);
+    let lines: Vec<&str> = body.lines().collect();
+
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for node in root.children() {
+        let ast = node.data.borrow();
+        if let NodeValue::ThematicBreak = ast.value {
+            // Source positions are 1-based
+            let rule_line = ast.sourcepos.start.line.max(1);
+            parts.push(lines[start..rule_line - 1].join("\n"));
+            start = rule_line;
+        }
+    }
+    parts.push(lines[start.min(lines.len())..].join("\n"));
+
+    parts
+}
+
+/// Separate speaker notes from the body of a slide.
+/// Notes are either everything after a `???` line or the text of `<!-- notes: ... -->` comments.
+fn extract_notes(section: &str) -> (String, Option<String>) {
+    let mut body = section.to_string();
+    let mut notes = Vec::new();
+
+    // Pull out comment-style notes first, they can appear anywhere in the slide
+    while let Some(start) = body.find(NOTES_COMMENT_START) {
+        let text_start = start + NOTES_COMMENT_START.len();
+        let Some(len) = body[text_start..].find("-->") else {
+            break;
+        };
+        notes.push(body[text_start..text_start + len].trim().to_string());
+        body.replace_range(start..text_start + len + 3, "");
+    }
+
+    // Everything after a "???" line is notes
+    if let Some(pos) = body.lines().position(|line| line.trim() == NOTES_SEPARATOR) {
+        let lines: Vec<&str> = body.lines().collect();
+        notes.push(lines[pos + 1..].join("\n").trim().to_string());
+        body = lines[..pos].join("\n");
+        body.push('\n');
+    }
+
+    notes.retain(|n| !n.is_empty());
+    if notes.is_empty() {
+        (body, None)
+    } else {
+        (body, Some(notes.join("\n\n")))
+    }
 }
 
-/// Extract individual slides from the HTML content
-fn extract_slides(html_content: &str) -> Vec<String> {
-    // Split by horizontal rule
-    let parts: Vec<&str> = html_content.split("<hr />").collect();
+/// Separate `<!-- transition: fade; advance: 5s -->` comments from the body of a slide.
+/// Later comments override earlier ones; invalid settings are ignored with a warning.
+fn extract_transition(part: &str) -> (String, SlideTransition) {
+    let mut body = String::with_capacity(part.len());
+    let mut transition = SlideTransition::default();
+    let mut rest = part;
+
+    while let Some(start) = rest.find("<!--") {
+        let text_start = start + 4;
+        let Some(len) = rest[text_start..].find("-->") else {
+            break;
+        };
+        let end = text_start + len + 3;
+        match SlideTransition::parse_comment(&rest[text_start..text_start + len]) {
+            Some(Ok(parsed)) => {
+                body.push_str(&rest[..start]);
+                transition = SlideTransition {
+                    effect: parsed.effect.or(transition.effect),
+                    speed: parsed.speed.or(transition.speed),
+                    advance_after: parsed.advance_after.or(transition.advance_after),
+                };
+            }
+            Some(Err(e)) => {
+                warn!("Ignoring transition comment: {}", e);
+                body.push_str(&rest[..start]);
+            }
+            None => body.push_str(&rest[..end]),
+        }
+        rest = &rest[end..];
+    }
+    body.push_str(rest);
 
-    // Convert to owned strings
-    parts.into_iter().map(|p| p.trim().to_string()).collect()
+    (body, transition)
 }
 
 /// Extract and clean slide content
@@ -257,6 +669,16 @@ fn extract_slide_content(slide: &str) -> String {
     }
 }
 
+/// Point relative URLs in a generated document at `base_dir`, so it can be rendered from elsewhere
+pub fn with_base_href(html_content: &str, base_dir: &Path) -> Result<String> {
+    let base_dir = fs::canonicalize(base_dir).map_err(BigError::FileReadError)?;
+    let base = url::Url::from_directory_path(&base_dir).map_err(|_| {
+        BigError::ValidationError(format!("Cannot use {:?} as a base URL", base_dir))
+    })?;
+    let base_tag = format!("<head>\n<base href=\"{}\">\n", escape(base.as_str()));
+    Ok(html_content.replacen("<head>\n", &base_tag, 1))
+}
+
 /// Utility function to write HTML content to a file
 pub fn write_html_to_file(html_content: &str, output_path: &Path) -> Result<()> {
     info!("Writing HTML to file: {:?}", output_path);
@@ -0,0 +1,121 @@
+// ABOUTME: Slide image formats for the big-slides application
+// ABOUTME: Parses format names, encodes AVIF, and transcodes images PowerPoint can't show
+
+use image::DynamicImage;
+use std::fmt;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::errors::{BigError, Result};
+
+/// Quality used for lossy formats when none is given
+pub const DEFAULT_QUALITY: u8 = 80;
+
+/// Hidden directory next to AVIF slides holding the PNG copies packaged into PPTX
+pub const PNG_COPY_DIR: &str = ".big-png";
+
+/// A format slide images can be rendered to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Avif,
+}
+
+impl ImageFormat {
+    /// Whether `--quality` applies to the format
+    pub fn is_lossy(self) -> bool {
+        !matches!(self, ImageFormat::Png)
+    }
+
+    /// Whether PowerPoint shows the format on every platform
+    pub fn is_pptx_compatible(self) -> bool {
+        matches!(self, ImageFormat::Png | ImageFormat::Jpeg)
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = BigError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "png" => Ok(ImageFormat::Png),
+            "jpeg" | "jpg" => Ok(ImageFormat::Jpeg),
+            "webp" => Ok(ImageFormat::Webp),
+            "avif" => Ok(ImageFormat::Avif),
+            other => Err(BigError::ValidationError(format!(
+                "Unknown image format '{}', expected one of: png, jpeg, webp, avif",
+                other
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpeg",
+            ImageFormat::Webp => "webp",
+            ImageFormat::Avif => "avif",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Encode a PNG screenshot as AVIF at `quality` (0-100)
+pub fn encode_avif(png: &[u8], quality: u8) -> Result<Vec<u8>> {
+    let image = image::load_from_memory(png)
+        .map_err(|e| BigError::ScreenshotError(format!("Failed to decode screenshot: {}", e)))?
+        .to_rgba8();
+    let (width, height) = image.dimensions();
+    let pixels: Vec<rgb::RGBA8> = image
+        .pixels()
+        .map(|pixel| rgb::RGBA8::new(pixel[0], pixel[1], pixel[2], pixel[3]))
+        .collect();
+
+    let encoded = ravif::Encoder::new()
+        .with_quality(f32::from(quality))
+        .with_speed(6)
+        .encode_rgba(ravif::Img::new(
+            pixels.as_slice(),
+            width as usize,
+            height as usize,
+        ))
+        .map_err(|e| BigError::ScreenshotError(format!("Failed to encode AVIF: {}", e)))?;
+    Ok(encoded.avif_file)
+}
+
+/// Write a PNG screenshot to `output_file` as AVIF, keeping a PNG copy for packaging.
+/// AVIF can be written but not read back, so PPTX is built from the copy instead.
+pub fn save_avif_slide(png: &[u8], output_file: &Path, quality: u8) -> Result<()> {
+    let avif = encode_avif(png, quality)?;
+    fs::write(output_file, avif).map_err(BigError::FileReadError)?;
+
+    let copy = png_copy_path(output_file);
+    if let Some(dir) = copy.parent() {
+        fs::create_dir_all(dir).map_err(BigError::FileReadError)?;
+    }
+    fs::write(copy, png).map_err(BigError::FileReadError)
+}
+
+/// Where the PNG copy of the AVIF slide at `path` is kept
+pub fn png_copy_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.parent()
+        .unwrap_or(Path::new(""))
+        .join(PNG_COPY_DIR)
+        .join(format!("{}.png", stem))
+}
+
+/// Re-encode a decoded slide image as PNG for formats PowerPoint can't show
+pub fn transcode_to_png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)
+        .map_err(|e| BigError::PptxError(format!("Failed to transcode image to PNG: {}", e)))?;
+    Ok(png)
+}
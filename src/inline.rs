@@ -0,0 +1,396 @@
+// ABOUTME: Self-contained HTML support for the big-slides application
+// ABOUTME: Rewrites image sources, CSS imports, fonts and url() references into data URIs
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use log::{info, warn};
+use quick_xml::escape::unescape;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+use crate::resources::ResourceFile;
+
+/// Largest asset inlined by default, in kilobytes
+pub const DEFAULT_INLINE_LIMIT_KB: u64 = 10 * 1024;
+
+/// How deeply `@import`ed stylesheets are followed
+const MAX_IMPORT_DEPTH: usize = 8;
+
+/// Settings for producing a self-contained HTML file
+#[derive(Debug, Clone)]
+pub struct InlineConfig {
+    /// Assets larger than this many bytes are left as references
+    pub max_bytes: u64,
+}
+
+impl Default for InlineConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_INLINE_LIMIT_KB * 1024,
+        }
+    }
+}
+
+/// Where a document or stylesheet came from, for resolving relative references
+#[derive(Debug, Clone)]
+pub enum Location {
+    /// A local directory
+    Dir(PathBuf),
+    /// A remote document
+    Url(Url),
+}
+
+impl Location {
+    /// Location that references inside a resource are relative to
+    pub fn of(resource: &ResourceFile) -> Option<Self> {
+        if resource.is_builtin {
+            None
+        } else if resource.is_remote {
+            Url::parse(&resource.path).ok().map(Location::Url)
+        } else {
+            let dir = Path::new(&resource.path).parent()?;
+            Some(Location::Dir(dir.to_path_buf()))
+        }
+    }
+
+    /// Resolve a reference found in a document at this location.
+    /// Returns `None` for references that don't need inlining, like data URIs and anchors.
+    fn resolve(&self, reference: &str) -> Option<Target> {
+        let reference = reference.trim();
+        if reference.is_empty() || reference.starts_with('#') {
+            return None;
+        }
+
+        if let Some(rest) = reference.strip_prefix("//") {
+            return Url::parse(&format!("https://{}", rest))
+                .ok()
+                .map(Target::Url);
+        }
+
+        if let Ok(url) = Url::parse(reference) {
+            return match url.scheme() {
+                "http" | "https" => Some(Target::Url(url)),
+                "file" => url.to_file_path().ok().map(Target::File),
+                // Windows drive letters parse as one-letter schemes; data: and others stay as they are
+                scheme if scheme.len() == 1 => Some(Target::File(PathBuf::from(reference))),
+                _ => None,
+            };
+        }
+
+        match self {
+            Location::Url(base) => base.join(reference).ok().map(Target::Url),
+            Location::Dir(dir) => {
+                // Going through a file URL decodes escapes and drops queries and fragments
+                let dir = dir
+                    .canonicalize()
+                    .or_else(|_| std::env::current_dir().map(|cwd| cwd.join(dir)))
+                    .ok()?;
+                let url = Url::from_directory_path(&dir).ok()?.join(reference).ok()?;
+                url.to_file_path().ok().map(Target::File)
+            }
+        }
+    }
+}
+
+/// A resolved reference to an asset
+#[derive(Debug, Clone)]
+enum Target {
+    File(PathBuf),
+    Url(Url),
+}
+
+impl Target {
+    fn location(&self) -> Option<Location> {
+        match self {
+            Target::File(path) => path.parent().map(|dir| Location::Dir(dir.to_path_buf())),
+            Target::Url(url) => Some(Location::Url(url.clone())),
+        }
+    }
+
+    fn key(&self) -> String {
+        match self {
+            Target::File(path) => path.to_string_lossy().into_owned(),
+            Target::Url(url) => url.to_string(),
+        }
+    }
+}
+
+/// Turns references to external assets into data URIs
+pub struct Inliner {
+    config: InlineConfig,
+    cache: RefCell<HashMap<String, Option<String>>>,
+}
+
+impl Inliner {
+    /// Create an inliner using the given settings
+    pub fn new(config: &InlineConfig) -> Self {
+        Self {
+            config: config.clone(),
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Inline the `src` of every `<img>` in a fragment of HTML
+    pub fn inline_html(&self, html: &str, base: &Location) -> String {
+        let mut output = String::with_capacity(html.len());
+        let mut rest = html;
+
+        while let Some(start) = find_ignore_case(rest, "<img") {
+            let Some(tag_len) = rest[start..].find('>') else {
+                break;
+            };
+            let tag = &rest[start..start + tag_len];
+            output.push_str(&rest[..start]);
+
+            match attribute_value(tag, "src") {
+                Some((value_start, value_end)) => {
+                    let raw = &tag[value_start..value_end];
+                    let reference = unescape(raw).map(|s| s.into_owned());
+                    let data_uri = reference
+                        .ok()
+                        .and_then(|reference| self.data_uri(&reference, base, 0));
+                    output.push_str(&tag[..value_start]);
+                    output.push_str(data_uri.as_deref().unwrap_or(raw));
+                    output.push_str(&tag[value_end..]);
+                }
+                None => output.push_str(tag),
+            }
+            rest = &rest[start + tag_len..];
+        }
+
+        output.push_str(rest);
+        output
+    }
+
+    /// Inline `@import`s, fonts and every other `url()` in a stylesheet
+    pub fn inline_css(&self, css: &str, base: &Location) -> String {
+        self.inline_css_at_depth(css, base, 0)
+    }
+
+    fn inline_css_at_depth(&self, css: &str, base: &Location, depth: usize) -> String {
+        let mut output = String::with_capacity(css.len());
+        let mut rest = css;
+
+        loop {
+            let next_url = find_ignore_case(rest, "url(");
+            let next_import = find_ignore_case(rest, "@import");
+            let (start, is_import) = match (next_url, next_import) {
+                (Some(u), Some(i)) if i < u => (i, true),
+                (Some(u), _) => (u, false),
+                (None, Some(i)) => (i, true),
+                (None, None) => break,
+            };
+
+            if is_import {
+                // `@import "file.css"` without url(); the url() form is handled below
+                let after = &rest[start + "@import".len()..];
+                let trimmed = after.trim_start();
+                let quote = trimmed.chars().next().filter(|c| *c == '"' || *c == '\'');
+                let Some(quote) = quote else {
+                    output.push_str(&rest[..start + "@import".len()]);
+                    rest = after;
+                    continue;
+                };
+                let value_start = start + "@import".len() + (after.len() - trimmed.len()) + 1;
+                let Some(len) = rest[value_start..].find(quote) else {
+                    break;
+                };
+                let reference = &rest[value_start..value_start + len];
+                output.push_str(&rest[..value_start - 1]);
+                match self.stylesheet_data_uri(reference, base, depth) {
+                    Some(data_uri) => output.push_str(&format!("url(\"{}\")", data_uri)),
+                    None => output.push_str(&rest[value_start - 1..value_start + len + 1]),
+                }
+                rest = &rest[value_start + len + 1..];
+                continue;
+            }
+
+            let value_start = start + "url(".len();
+            let Some(len) = rest[value_start..].find(')') else {
+                break;
+            };
+            let raw = &rest[value_start..value_start + len];
+            let reference = raw.trim().trim_matches(|c| c == '"' || c == '\'');
+
+            // A url() right after @import is a stylesheet, everything else is an asset
+            let is_import = output.trim_end().to_lowercase().ends_with("@import");
+            let data_uri = if is_import {
+                self.stylesheet_data_uri(reference, base, depth)
+            } else {
+                self.data_uri(reference, base, depth)
+            };
+
+            output.push_str(&rest[..value_start]);
+            match data_uri {
+                Some(data_uri) => output.push_str(&format!("\"{}\"", data_uri)),
+                None => output.push_str(raw),
+            }
+            output.push(')');
+            rest = &rest[value_start + len + 1..];
+        }
+
+        output.push_str(rest);
+        output
+    }
+
+    /// Data URI for an imported stylesheet, with its own references inlined
+    fn stylesheet_data_uri(
+        &self,
+        reference: &str,
+        base: &Location,
+        depth: usize,
+    ) -> Option<String> {
+        let target = base.resolve(reference)?;
+        if depth >= MAX_IMPORT_DEPTH {
+            warn!("Not inlining {}: @import nesting is too deep", reference);
+            return None;
+        }
+
+        let css = self.load(&target, reference)?;
+        let css = String::from_utf8_lossy(&css);
+        let css = match target.location() {
+            Some(location) => self.inline_css_at_depth(&css, &location, depth + 1),
+            None => css.into_owned(),
+        };
+        Some(format!("data:text/css;base64,{}", STANDARD.encode(css)))
+    }
+
+    /// Data URI for an image, font or other asset
+    fn data_uri(&self, reference: &str, base: &Location, depth: usize) -> Option<String> {
+        let target = base.resolve(reference)?;
+        let key = target.key();
+        if let Some(cached) = self.cache.borrow().get(&key) {
+            return cached.clone();
+        }
+
+        let data_uri = self.load(&target, reference).map(|bytes| {
+            // SVGs can reference further assets of their own
+            let mime = mime_type(&key, &bytes);
+            let bytes = match (mime, target.location()) {
+                ("image/svg+xml", Some(location)) if depth < MAX_IMPORT_DEPTH => {
+                    let svg = String::from_utf8_lossy(&bytes);
+                    self.inline_css_at_depth(&svg, &location, depth + 1)
+                        .into_bytes()
+                }
+                _ => bytes,
+            };
+            format!("data:{};base64,{}", mime, STANDARD.encode(bytes))
+        });
+
+        self.cache.borrow_mut().insert(key, data_uri.clone());
+        data_uri
+    }
+
+    /// Read an asset, warning and returning `None` if it can't or shouldn't be inlined
+    fn load(&self, target: &Target, reference: &str) -> Option<Vec<u8>> {
+        // Local files are measured before they are read
+        if let Target::File(path) = target {
+            if let Ok(metadata) = fs::metadata(path) {
+                if metadata.len() > self.config.max_bytes {
+                    self.warn_too_large(reference, metadata.len());
+                    return None;
+                }
+            }
+        }
+
+        let resource = match target {
+            Target::File(path) => ResourceFile::new(&path.to_string_lossy()),
+            Target::Url(url) => ResourceFile::new(url.as_str()),
+        };
+        let bytes = match resource.bytes() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Could not inline {}: {}", reference, e);
+                return None;
+            }
+        };
+        if bytes.len() as u64 > self.config.max_bytes {
+            self.warn_too_large(reference, bytes.len() as u64);
+            return None;
+        }
+
+        info!("Inlined {} ({} bytes)", reference, bytes.len());
+        Some(bytes)
+    }
+
+    fn warn_too_large(&self, reference: &str, size: u64) {
+        warn!(
+            "Not inlining {}: {} bytes is over the {} byte limit",
+            reference, size, self.config.max_bytes
+        );
+    }
+}
+
+/// Media type of an asset, from its extension or else its contents
+fn mime_type(name: &str, bytes: &[u8]) -> &'static str {
+    let name = name.split(['?', '#']).next().unwrap_or(name);
+    let extension = Path::new(name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "eot" => "application/vnd.ms-fontobject",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        _ => match image::guess_format(bytes) {
+            Ok(format) => match format {
+                image::ImageFormat::Png => "image/png",
+                image::ImageFormat::Jpeg => "image/jpeg",
+                image::ImageFormat::Gif => "image/gif",
+                image::ImageFormat::WebP => "image/webp",
+                image::ImageFormat::Avif => "image/avif",
+                image::ImageFormat::Bmp => "image/bmp",
+                image::ImageFormat::Ico => "image/x-icon",
+                _ => "application/octet-stream",
+            },
+            Err(_) => "application/octet-stream",
+        },
+    }
+}
+
+/// Byte offset of `needle` in `haystack`, ignoring ASCII case
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Byte range of a quoted attribute's value within an HTML tag
+fn attribute_value(tag: &str, name: &str) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    while let Some(pos) = find_ignore_case(&tag[offset..], name) {
+        let start = offset + pos;
+        offset = start + name.len();
+
+        // The name must stand alone, not be the end of e.g. `data-src`
+        let preceded_by_space = tag[..start].chars().last().is_some_and(char::is_whitespace);
+        let after = tag[offset..].trim_start();
+        if !preceded_by_space || !after.starts_with('=') {
+            continue;
+        }
+
+        let value = after[1..].trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value_start = tag.len() - value.len() + 1;
+        let len = tag[value_start..].find(quote)?;
+        return Some((value_start, value_start + len));
+    }
+    None
+}
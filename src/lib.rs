@@ -2,21 +2,35 @@
 // ABOUTME: Contains core functionality for generating HTML, slides, and PPTX files.
 
 // Reexport modules
+pub mod build;
+pub mod cache;
+pub mod compare;
 pub mod config;
+pub mod doctor;
 pub mod errors;
 pub mod html;
+pub mod images;
+pub mod inline;
+pub mod links;
+pub mod manifest;
+pub mod merge;
+mod native;
+pub mod overflow;
 pub mod pptx;
 pub mod render;
 pub mod resources;
+pub mod template;
+pub mod transition;
 pub mod utils;
 pub mod watch;
 
 // Reexport common types and functions
+pub use build::{build_presentation, Artifact, BuildConfig};
 pub use config::Config;
 pub use errors::{BigError, Result};
 pub use html::{generate_html, write_html_to_file};
 pub use pptx::{find_slide_images, generate_pptx, PptxConfig};
-pub use render::{generate_slides, RenderConfig};
+pub use render::{generate_pdf, generate_slides, RenderConfig, RenderSession};
 pub use resources::ResourceFile;
 pub use watch::{watch_markdown, WatchConfig};
 
@@ -0,0 +1,107 @@
+// ABOUTME: Hyperlink positions on rendered slides
+// ABOUTME: Records where each link sits on a slide image so the PPTX can keep it clickable
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::errors::{BigError, Result};
+
+/// Name of the link manifest written next to the slide images
+pub const LINKS_FILE: &str = "slide-links.json";
+
+/// URL schemes that still work once the slide is a picture in a PPTX
+const CLICKABLE_SCHEMES: [&str; 3] = ["http:", "https:", "mailto:"];
+
+/// Finds the links on the slide at `index` after it has been shown.
+/// Returns a JSON list of `LinkArea`s, one for each line a link's text covers.
+pub(crate) const LINKS_JS: &str = r#"
+(function (index) {
+  var slide = document.querySelectorAll('body > div')[index];
+  if (!slide) return null;
+
+  var width = window.innerWidth, height = window.innerHeight;
+  var areas = [];
+  Array.prototype.forEach.call(slide.querySelectorAll('a[href]'), function (link) {
+    if (window.getComputedStyle(link).visibility === 'hidden') return;
+    Array.prototype.forEach.call(link.getClientRects(), function (rect) {
+      // Only the part inside the viewport ends up in the image
+      var left = Math.max(0, rect.left), top = Math.max(0, rect.top);
+      var right = Math.min(width, rect.right), bottom = Math.min(height, rect.bottom);
+      if (right - left < 1 || bottom - top < 1) return;
+      areas.push({
+        href: link.href,
+        left: left,
+        top: top,
+        width: right - left,
+        height: bottom - top
+      });
+    });
+  });
+  return JSON.stringify(areas);
+})"#;
+
+/// Part of a slide that links to `href`, in CSS pixels from the top left of the viewport
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkArea {
+    pub href: String,
+    pub left: f64,
+    pub top: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl LinkArea {
+    /// Whether the link can be followed from a PPTX, rather than pointing into the deck
+    /// or at a local file
+    pub fn is_clickable(&self) -> bool {
+        let href = self.href.to_lowercase();
+        CLICKABLE_SCHEMES
+            .iter()
+            .any(|scheme| href.starts_with(scheme))
+    }
+}
+
+/// The links on every slide image in a directory, keyed by image file name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LinkManifest {
+    /// Viewport the slides were rendered in, in CSS pixels
+    pub width: u32,
+    pub height: u32,
+
+    pub slides: BTreeMap<String, Vec<LinkArea>>,
+}
+
+impl LinkManifest {
+    /// Load the manifest in `dir`, if there is a readable one
+    pub fn load(dir: &Path) -> Option<Self> {
+        let json = fs::read_to_string(dir.join(LINKS_FILE)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Write the manifest to `dir`, or remove the old one if no slide has links
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(LINKS_FILE);
+        if self.slides.is_empty() {
+            if path.exists() {
+                fs::remove_file(&path).map_err(BigError::FileReadError)?;
+            }
+            return Ok(());
+        }
+
+        let json = serde_json::to_string_pretty(self).map_err(|e| {
+            BigError::SerializationError(format!("Failed to write link manifest: {}", e))
+        })?;
+        fs::write(path, json).map_err(BigError::FileReadError)
+    }
+
+    /// Record the links of the image `file`, dropping it if it has none
+    pub fn set(&mut self, file: &str, links: Vec<LinkArea>) {
+        if links.is_empty() {
+            self.slides.remove(file);
+        } else {
+            self.slides.insert(file.to_string(), links);
+        }
+    }
+}
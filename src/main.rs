@@ -3,10 +3,22 @@
 
 use clap::{Args, Parser, Subcommand};
 use log::{error, info};
-use std::path::PathBuf;
-
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use big_slides::build::Artifact;
+use big_slides::cache::{self, CachePolicy, ResourceCache};
+use big_slides::compare::CompareConfig;
+use big_slides::config::{ProjectConfig, DEFAULT_CSS, DEFAULT_JS};
+use big_slides::doctor::{CheckStatus, DoctorConfig};
 use big_slides::errors::BigError;
 use big_slides::errors::Result as BigResult;
+use big_slides::inline::{InlineConfig, DEFAULT_INLINE_LIMIT_KB};
+use big_slides::merge::{MergeConfig, Placement};
+use big_slides::overflow::{DEFAULT_MIN_FONT_PX, OVERFLOW_REPORT_FILE};
+use big_slides::render::SlideSelection;
+use big_slides::template::TemplateConfig;
+use big_slides::transition::{parse_duration, Transition, TransitionEffect, TransitionSpeed};
 use big_slides::utils;
 
 #[derive(Parser)]
@@ -20,6 +32,14 @@ struct Cli {
     #[arg(short, long)]
     verbose: bool,
 
+    /// Only use cached copies of remote CSS/JS, never the network
+    #[arg(long, global = true, conflicts_with = "refresh")]
+    offline: bool,
+
+    /// Revalidate cached copies of remote CSS/JS with their servers
+    #[arg(long, global = true)]
+    refresh: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -35,19 +55,75 @@ enum Commands {
     /// Generate PPTX from slides
     GeneratePptx(GeneratePptxArgs),
 
+    /// Generate PDF from HTML, one page per slide
+    GeneratePdf(GeneratePdfArgs),
+
+    /// Build the whole pipeline from markdown in one step
+    Build(BuildArgs),
+
     /// Watch for changes and auto-regenerate outputs
     Watch(WatchArgs),
+
+    /// Inspect the configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+
+    /// Manage the cache of remote CSS/JS resources
+    #[command(subcommand)]
+    Cache(CacheCommand),
+
+    /// Diagnose the browser, output directories and network, and suggest fixes
+    Doctor(DoctorArgs),
+
+    /// Compare rendered slides with a baseline and highlight what changed
+    Compare(CompareArgs),
 }
 
-#[derive(Args)]
+impl Commands {
+    /// Markdown file whose directory is searched for big.toml
+    fn markdown_path(&self) -> Option<&Path> {
+        match self {
+            Commands::GenerateHtml(args) => args.input.as_deref(),
+            Commands::GeneratePptx(args) => args.markdown.as_deref(),
+            Commands::Build(args) => args.input.as_deref(),
+            Commands::Watch(args) => args.input.as_deref(),
+            Commands::Config(ConfigCommand::Show(args)) => args.input.as_deref(),
+            Commands::Doctor(args) => args.input.as_deref(),
+            Commands::GenerateSlides(_)
+            | Commands::GeneratePdf(_)
+            | Commands::Cache(_)
+            | Commands::Compare(_) => None,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the resolved configuration and where each value came from
+    Show(ConfigShowArgs),
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// List the cached resources
+    List,
+
+    /// Remove every cached resource
+    Clear,
+}
+
+// Every argument is optional on the command line so that values from the environment and
+// big.toml can fill them in. Defaults are applied when the settings are resolved.
+
+#[derive(Args, Default)]
 struct GenerateHtmlArgs {
     /// Path to the markdown file
     #[arg(short, long)]
-    input: PathBuf,
+    input: Option<PathBuf>,
 
     /// Path to output HTML file
     #[arg(short, long)]
-    output: PathBuf,
+    output: Option<PathBuf>,
 
     /// CSS files to include (local paths or URLs)
     #[arg(long, value_delimiter = ',')]
@@ -57,70 +133,296 @@ struct GenerateHtmlArgs {
     #[arg(long, value_delimiter = ',')]
     js: Option<Vec<String>>,
 
-    /// Mode for CSS/JS: 'embed' to embed content or 'link' to reference
-    #[arg(long, default_value = "embed")]
-    mode: String,
+    /// Mode for CSS/JS: 'embed' to embed content or 'link' to reference [default: embed]
+    #[arg(long)]
+    mode: Option<String>,
+
+    /// Inline images, fonts and stylesheet references as data URIs for a single-file HTML
+    #[arg(long)]
+    self_contained: bool,
+
+    /// Largest asset to inline with --self-contained, in kilobytes [default: 10240]
+    #[arg(long)]
+    inline_limit_kb: Option<u64>,
 }
 
-#[derive(Args)]
+#[derive(Args, Default)]
 struct GenerateSlidesArgs {
     /// Path to the HTML file to render
     #[arg(short, long)]
-    input: PathBuf,
+    input: Option<PathBuf>,
 
     /// Directory to output slide images
     #[arg(short, long)]
-    output_dir: PathBuf,
+    output_dir: Option<PathBuf>,
 
-    /// Base filename for slides (will be appended with slide number)
-    #[arg(long, default_value = "slide")]
-    base_name: String,
+    /// Base filename for slides (will be appended with slide number) [default: slide]
+    #[arg(long)]
+    base_name: Option<String>,
 
-    /// Format for the slide images (png, jpeg)
-    #[arg(long, default_value = "png")]
-    format: String,
+    /// Format for the slide images (png, jpeg, webp, avif) [default: png]
+    #[arg(long)]
+    format: Option<String>,
 
-    /// Width of the slides in pixels
-    #[arg(long, default_value = "1920")]
-    width: u32,
+    /// Quality of jpeg, webp and avif images, from 0 to 100 [default: 80]
+    #[arg(long)]
+    quality: Option<u8>,
 
-    /// Height of the slides in pixels
-    #[arg(long, default_value = "1080")]
-    height: u32,
+    /// Width of the slides in pixels [default: 1920]
+    #[arg(long)]
+    width: Option<u32>,
+
+    /// Height of the slides in pixels [default: 1080]
+    #[arg(long)]
+    height: Option<u32>,
 
     /// Timeout in milliseconds for browser operations
     #[arg(long)]
     timeout_ms: Option<u64>,
+
+    /// Device scale factor, e.g. 2 for images twice the width and height [default: 1]
+    #[arg(long)]
+    scale: Option<f64>,
+
+    /// Number of browser tabs rendering slides at once [default: 1]
+    #[arg(long)]
+    jobs: Option<usize>,
+
+    /// Milliseconds to wait for each slide's fonts, images and bigReady() before capturing it [default: 10000]
+    #[arg(long)]
+    slide_timeout_ms: Option<u64>,
+
+    /// Fail unless the deck has exactly this many slides
+    #[arg(long)]
+    expected_slides: Option<usize>,
+
+    /// Only render these slides, e.g. 3,5,10-20; files keep their slide numbers
+    #[arg(long)]
+    slides: Option<SlideSelection>,
+
+    /// Only re-render slides that changed since the last render into the output directory
+    #[arg(long)]
+    incremental: bool,
+
+    /// Text smaller than this many CSS pixels is reported [default: 18]
+    #[arg(long)]
+    min_font_size: Option<f64>,
+
+    /// JSON file to write each slide's overflow, clipping and font size to [default: overflow-report.json in the output directory]
+    #[arg(long)]
+    overflow_report: Option<PathBuf>,
+
+    /// Fail when content overflows or is clipped on any slide
+    #[arg(long)]
+    fail_on_overflow: bool,
 }
 
-#[derive(Args)]
+#[derive(Args, Default)]
 struct GeneratePptxArgs {
     /// Directory containing slide images
     #[arg(short, long)]
-    input_dir: PathBuf,
+    input_dir: Option<PathBuf>,
 
     /// Output PPTX file path
     #[arg(short, long)]
-    output: PathBuf,
+    output: Option<PathBuf>,
 
-    /// Pattern to match slide images (e.g., "slide_*.png")
-    #[arg(long, default_value = "*.png")]
-    pattern: String,
+    /// Pattern to match slide images (e.g., "slide_*.png") [default: *.png]
+    #[arg(long)]
+    pattern: Option<String>,
 
-    /// Title for the presentation
-    #[arg(long, default_value = "Presentation")]
-    title: String,
+    /// Title for the presentation [default: Presentation]
+    #[arg(long)]
+    title: Option<String>,
+
+    /// Aspect ratio (16:9 or 4:3) [default: 16:9, or the template's slide size]
+    #[arg(long)]
+    aspect_ratio: Option<String>,
+
+    /// Markdown source to take speaker notes, transitions, slide titles and alt text from
+    #[arg(long)]
+    markdown: Option<PathBuf>,
+
+    /// Build editable text slides from the markdown instead of slide images
+    #[arg(long)]
+    native: bool,
+
+    /// Directory of re-rendered slide images that replace the slides with the same number
+    #[arg(long)]
+    splice: Option<PathBuf>,
+
+    /// Transition between slides (fade, push, wipe, none) [default: none]
+    #[arg(long)]
+    transition: Option<TransitionEffect>,
+
+    /// Speed of the transition (slow, medium, fast) [default: medium]
+    #[arg(long)]
+    transition_speed: Option<TransitionSpeed>,
+
+    /// Advance to the next slide automatically after this long, e.g. 5s or 500ms
+    #[arg(long, value_parser = parse_duration)]
+    advance_after: Option<Duration>,
+
+    /// .pptx or .potx whose masters, layouts, theme and fonts the slides use
+    #[arg(long)]
+    template: Option<PathBuf>,
 
-    /// Aspect ratio (16:9 or 4:3)
-    #[arg(long, default_value = "16:9")]
-    aspect_ratio: String,
+    /// Name of the template layout new slides use [default: the template's blank layout]
+    #[arg(long)]
+    layout: Option<String>,
+
+    /// Existing PPTX to add the slides to instead of creating a new one; -o defaults to it
+    #[arg(long)]
+    into: Option<PathBuf>,
+
+    /// Insert the slides before this slide of the --into deck, counting from 1 [default: after the last]
+    #[arg(long)]
+    position: Option<usize>,
+
+    /// Replace the slides of this section of the --into deck
+    #[arg(long)]
+    section: Option<String>,
 }
 
-#[derive(Args)]
+#[derive(Args, Default)]
+struct GeneratePdfArgs {
+    /// Path to the HTML file to render
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+
+    /// Output PDF file path
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Width of the pages in pixels [default: 1920]
+    #[arg(long)]
+    width: Option<u32>,
+
+    /// Height of the pages in pixels [default: 1080]
+    #[arg(long)]
+    height: Option<u32>,
+
+    /// Timeout in milliseconds for browser operations
+    #[arg(long)]
+    timeout_ms: Option<u64>,
+
+    /// Milliseconds to wait for each slide's fonts, images and bigReady() before capturing it [default: 10000]
+    #[arg(long)]
+    slide_timeout_ms: Option<u64>,
+
+    /// Fail unless the deck has exactly this many slides
+    #[arg(long)]
+    expected_slides: Option<usize>,
+}
+
+#[derive(Args, Default)]
+struct BuildArgs {
+    /// Path to the markdown file
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+
+    /// Directory to write the outputs to
+    #[arg(short, long)]
+    output_dir: Option<PathBuf>,
+
+    /// Artifacts to produce (html, png, pptx, pdf) [default: pptx]
+    #[arg(long, value_delimiter = ',')]
+    artifacts: Option<Vec<Artifact>>,
+
+    /// Keep the intermediate HTML and slide images in the output directory
+    #[arg(long)]
+    keep_intermediates: bool,
+
+    /// CSS files to include (local paths or URLs)
+    #[arg(long, value_delimiter = ',')]
+    css: Option<Vec<String>>,
+
+    /// JavaScript files to include (local paths or URLs)
+    #[arg(long, value_delimiter = ',')]
+    js: Option<Vec<String>>,
+
+    /// Mode for CSS/JS: 'embed' to embed content or 'link' to reference [default: embed]
+    #[arg(long)]
+    mode: Option<String>,
+
+    /// Inline images, fonts and stylesheet references as data URIs for a single-file HTML
+    #[arg(long)]
+    self_contained: bool,
+
+    /// Largest asset to inline with --self-contained, in kilobytes [default: 10240]
+    #[arg(long)]
+    inline_limit_kb: Option<u64>,
+
+    /// Width of the slides in pixels [default: 1920]
+    #[arg(long)]
+    width: Option<u32>,
+
+    /// Height of the slides in pixels [default: 1080]
+    #[arg(long)]
+    height: Option<u32>,
+
+    /// Title for the presentation [default: Presentation]
+    #[arg(long)]
+    title: Option<String>,
+
+    /// Aspect ratio (16:9 or 4:3) [default: 16:9, or the template's slide size]
+    #[arg(long)]
+    aspect_ratio: Option<String>,
+
+    /// Device scale factor, e.g. 2 for images twice the width and height [default: 1]
+    #[arg(long)]
+    scale: Option<f64>,
+
+    /// Number of browser tabs rendering slides at once [default: 1]
+    #[arg(long)]
+    jobs: Option<usize>,
+
+    /// Milliseconds to wait for each slide's fonts, images and bigReady() before capturing it [default: 10000]
+    #[arg(long)]
+    slide_timeout_ms: Option<u64>,
+
+    /// Fail unless the deck has exactly this many slides
+    #[arg(long)]
+    expected_slides: Option<usize>,
+
+    /// Text smaller than this many CSS pixels is reported [default: 18]
+    #[arg(long)]
+    min_font_size: Option<f64>,
+
+    /// JSON file to write each slide's overflow, clipping and font size to [default: in the slides directory, if kept]
+    #[arg(long)]
+    overflow_report: Option<PathBuf>,
+
+    /// Fail when content overflows or is clipped on any slide
+    #[arg(long)]
+    fail_on_overflow: bool,
+
+    /// Transition between slides (fade, push, wipe, none) [default: none]
+    #[arg(long)]
+    transition: Option<TransitionEffect>,
+
+    /// Speed of the transition (slow, medium, fast) [default: medium]
+    #[arg(long)]
+    transition_speed: Option<TransitionSpeed>,
+
+    /// Advance to the next slide automatically after this long, e.g. 5s or 500ms
+    #[arg(long, value_parser = parse_duration)]
+    advance_after: Option<Duration>,
+
+    /// .pptx or .potx whose masters, layouts, theme and fonts the slides use
+    #[arg(long)]
+    template: Option<PathBuf>,
+
+    /// Name of the template layout new slides use [default: the template's blank layout]
+    #[arg(long)]
+    layout: Option<String>,
+}
+
+#[derive(Args, Default)]
 struct WatchArgs {
     /// Path to the markdown file to watch
     #[arg(short, long)]
-    input: PathBuf,
+    input: Option<PathBuf>,
 
     /// Path to output HTML file (defaults to input filename with .html extension)
     #[arg(short, long)]
@@ -134,9 +436,17 @@ struct WatchArgs {
     #[arg(long, value_delimiter = ',')]
     js: Option<Vec<String>>,
 
-    /// Mode for CSS/JS: 'embed' to embed content or 'link' to reference
-    #[arg(long, default_value = "embed")]
-    mode: String,
+    /// Mode for CSS/JS: 'embed' to embed content or 'link' to reference [default: embed]
+    #[arg(long)]
+    mode: Option<String>,
+
+    /// Inline images, fonts and stylesheet references as data URIs for a single-file HTML
+    #[arg(long)]
+    self_contained: bool,
+
+    /// Largest asset to inline with --self-contained, in kilobytes [default: 10240]
+    #[arg(long)]
+    inline_limit_kb: Option<u64>,
 
     /// Directory to output slide images (optional)
     #[arg(long)]
@@ -146,27 +456,683 @@ struct WatchArgs {
     #[arg(long)]
     pptx_output: Option<PathBuf>,
 
-    /// Debounce time in milliseconds
-    #[arg(long, default_value = "500")]
-    debounce_ms: u64,
+    /// Debounce time in milliseconds [default: 500]
+    #[arg(long)]
+    debounce_ms: Option<u64>,
 
-    /// Start a local web server to serve the HTML
-    #[arg(long, default_value = "true")]
-    serve: bool,
+    /// Start a local web server to serve the HTML [default: true]
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    serve: Option<bool>,
 
-    /// Port for local web server
-    #[arg(long, default_value = "8080")]
-    port: u16,
+    /// Port for local web server [default: 8080]
+    #[arg(long)]
+    port: Option<u16>,
 
-    /// Enable auto-reload on file changes
-    #[arg(long, default_value = "true")]
-    auto_reload: bool,
+    /// Enable auto-reload on file changes [default: true]
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    auto_reload: Option<bool>,
 
     /// WebSocket port for auto-reload (defaults to HTTP port + 1)
     #[arg(long)]
     ws_port: Option<u16>,
 }
 
+#[derive(Args)]
+struct ConfigShowArgs {
+    /// Markdown file whose big.toml should be used (defaults to the current directory)
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+}
+
+#[derive(Args, Default)]
+struct CompareArgs {
+    /// Directory of known-good slide images
+    #[arg(short, long)]
+    baseline: Option<PathBuf>,
+
+    /// Directory of freshly rendered slide images
+    #[arg(short, long)]
+    current: Option<PathBuf>,
+
+    /// Directory to write diff images to [default: diff inside the current directory]
+    #[arg(long)]
+    diff_dir: Option<PathBuf>,
+
+    /// Pattern to match slide images [default: slide_*.png]
+    #[arg(long)]
+    pattern: Option<String>,
+
+    /// Percentage of changed pixels above which a slide fails [default: 0.1]
+    #[arg(long)]
+    threshold: Option<f64>,
+
+    /// Largest per-channel difference, from 0 to 255, ignored as noise [default: 8]
+    #[arg(long)]
+    tolerance: Option<u8>,
+}
+
+#[derive(Args)]
+struct DoctorArgs {
+    /// Markdown file whose big.toml should be checked (defaults to the current directory)
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+
+    /// More directories to check for write access, besides the configured outputs
+    #[arg(long)]
+    output_dir: Vec<PathBuf>,
+}
+
+/// CSS, JavaScript and embedding settings shared by several commands
+struct ResourceSettings {
+    css: Vec<String>,
+    js: Vec<String>,
+    mode: String,
+    self_contained: bool,
+    inline_limit_kb: u64,
+}
+
+impl ResourceSettings {
+    fn resolve(
+        project: &ProjectConfig,
+        css: &Option<Vec<String>>,
+        js: &Option<Vec<String>>,
+        mode: &Option<String>,
+        self_contained: bool,
+        inline_limit_kb: Option<u64>,
+    ) -> BigResult<Self> {
+        Ok(Self {
+            css: project.resolve(
+                "html.css",
+                css.clone(),
+                Some("DEFAULT_CSS"),
+                vec![DEFAULT_CSS.to_string()],
+            )?,
+            js: project.resolve(
+                "html.js",
+                js.clone(),
+                Some("DEFAULT_JS"),
+                vec![DEFAULT_JS.to_string()],
+            )?,
+            mode: project.resolve("html.mode", mode.clone(), None, "embed".to_string())?,
+            self_contained: project.resolve(
+                "html.self_contained",
+                self_contained.then_some(true),
+                None,
+                false,
+            )?,
+            inline_limit_kb: project.resolve(
+                "html.inline_limit_kb",
+                inline_limit_kb,
+                None,
+                DEFAULT_INLINE_LIMIT_KB,
+            )?,
+        })
+    }
+
+    /// Whether to embed resources rather than link to them
+    fn embed(&self) -> bool {
+        self.mode.to_lowercase() != "link"
+    }
+
+    /// Inlining settings when a self-contained file was asked for
+    fn inline(&self) -> Option<InlineConfig> {
+        self.self_contained.then(|| InlineConfig {
+            max_bytes: self.inline_limit_kb * 1024,
+        })
+    }
+
+    fn css_files(&self) -> Vec<big_slides::ResourceFile> {
+        resource_files(&self.css)
+    }
+
+    fn js_files(&self) -> Vec<big_slides::ResourceFile> {
+        resource_files(&self.js)
+    }
+}
+
+/// Overflow checking settings shared by generate-slides and build
+struct OverflowSettings {
+    min_font_px: f64,
+    report: Option<PathBuf>,
+    fail: bool,
+}
+
+impl OverflowSettings {
+    fn resolve(
+        project: &ProjectConfig,
+        min_font_size: Option<f64>,
+        report: &Option<PathBuf>,
+        fail: bool,
+    ) -> BigResult<Self> {
+        Ok(Self {
+            min_font_px: project.resolve(
+                "slides.min_font_size",
+                min_font_size,
+                None,
+                DEFAULT_MIN_FONT_PX,
+            )?,
+            report: project.resolve_optional("slides.overflow_report", report.clone(), None)?,
+            fail: project.resolve("slides.fail_on_overflow", fail.then_some(true), None, false)?,
+        })
+    }
+}
+
+/// Resolved generate-html settings
+struct HtmlSettings {
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    resources: ResourceSettings,
+}
+
+impl GenerateHtmlArgs {
+    fn resolve(&self, project: &ProjectConfig) -> BigResult<HtmlSettings> {
+        Ok(HtmlSettings {
+            input: project.resolve_optional("html.input", self.input.clone(), None)?,
+            output: project.resolve_optional("html.output", self.output.clone(), None)?,
+            resources: ResourceSettings::resolve(
+                project,
+                &self.css,
+                &self.js,
+                &self.mode,
+                self.self_contained,
+                self.inline_limit_kb,
+            )?,
+        })
+    }
+}
+
+/// Resolved generate-slides settings
+struct SlidesSettings {
+    input: Option<PathBuf>,
+    output_dir: Option<PathBuf>,
+    base_name: String,
+    format: String,
+    quality: Option<u8>,
+    width: u32,
+    height: u32,
+    timeout_ms: u64,
+    scale: f64,
+    jobs: usize,
+    slide_timeout_ms: u64,
+    expected_slides: Option<usize>,
+    slides: Option<SlideSelection>,
+    incremental: bool,
+    overflow: OverflowSettings,
+}
+
+impl GenerateSlidesArgs {
+    fn resolve(
+        &self,
+        project: &ProjectConfig,
+        config: &big_slides::Config,
+    ) -> BigResult<SlidesSettings> {
+        Ok(SlidesSettings {
+            input: project.resolve_optional("slides.input", self.input.clone(), None)?,
+            output_dir: project.resolve_optional(
+                "slides.output_dir",
+                self.output_dir.clone(),
+                None,
+            )?,
+            base_name: project.resolve(
+                "slides.base_name",
+                self.base_name.clone(),
+                None,
+                "slide".to_string(),
+            )?,
+            format: project.resolve(
+                "slides.format",
+                self.format.clone(),
+                None,
+                "png".to_string(),
+            )?,
+            quality: resolve_quality(project, self.quality)?,
+            width: project.resolve("slides.width", self.width, None, 1920)?,
+            height: project.resolve("slides.height", self.height, None, 1080)?,
+            timeout_ms: project.resolve(
+                "slides.timeout_ms",
+                self.timeout_ms,
+                Some("DEFAULT_TIMEOUT_MS"),
+                config.default_timeout_ms,
+            )?,
+            scale: resolve_scale(project, self.scale)?,
+            jobs: resolve_jobs(project, self.jobs)?,
+            slide_timeout_ms: resolve_slide_timeout(project, self.slide_timeout_ms)?,
+            expected_slides: project.resolve_optional(
+                "slides.expected_slides",
+                self.expected_slides,
+                None,
+            )?,
+            slides: project.resolve_optional("slides.slides", self.slides.clone(), None)?,
+            incremental: project.resolve(
+                "slides.incremental",
+                self.incremental.then_some(true),
+                None,
+                false,
+            )?,
+            overflow: OverflowSettings::resolve(
+                project,
+                self.min_font_size,
+                &self.overflow_report,
+                self.fail_on_overflow,
+            )?,
+        })
+    }
+}
+
+/// Resolved generate-pptx settings
+struct PptxSettings {
+    input_dir: Option<PathBuf>,
+    output: Option<PathBuf>,
+    pattern: String,
+    title: String,
+    aspect_ratio: Option<String>,
+    markdown: Option<PathBuf>,
+    native: bool,
+    splice: Option<PathBuf>,
+    transition: Transition,
+    template: Option<TemplateConfig>,
+    merge: Option<MergeConfig>,
+}
+
+impl GeneratePptxArgs {
+    fn resolve(&self, project: &ProjectConfig) -> BigResult<PptxSettings> {
+        let markdown = project.resolve_optional("pptx.markdown", self.markdown.clone(), None)?;
+        let aspect_ratio = resolve_aspect_ratio(project, &self.aspect_ratio, markdown.as_deref())?;
+        let layout = project.resolve_optional("pptx.layout", self.layout.clone(), None)?;
+        Ok(PptxSettings {
+            input_dir: project.resolve_optional("pptx.input_dir", self.input_dir.clone(), None)?,
+            output: project.resolve_optional("pptx.output", self.output.clone(), None)?,
+            pattern: project.resolve(
+                "pptx.pattern",
+                self.pattern.clone(),
+                None,
+                "*.png".to_string(),
+            )?,
+            title: resolve_title(project, &self.title, markdown.as_deref())?,
+            template: resolve_template(project, &self.template, layout.clone(), &aspect_ratio)?,
+            merge: self.resolve_merge(project, layout)?,
+            aspect_ratio,
+            markdown,
+            native: project.resolve("pptx.native", self.native.then_some(true), None, false)?,
+            splice: project.resolve_optional("pptx.splice", self.splice.clone(), None)?,
+            transition: resolve_transition(
+                project,
+                self.transition,
+                self.transition_speed,
+                self.advance_after,
+            )?,
+        })
+    }
+
+    /// Where to add the slides when they go into an existing deck
+    fn resolve_merge(
+        &self,
+        project: &ProjectConfig,
+        layout: Option<String>,
+    ) -> BigResult<Option<MergeConfig>> {
+        let into = project.resolve_optional("pptx.into", self.into.clone(), None)?;
+        let position = project.resolve_optional("pptx.position", self.position, None)?;
+        let section = project.resolve_optional("pptx.section", self.section.clone(), None)?;
+        let placement = match (position, section) {
+            (Some(_), Some(_)) => {
+                return Err(BigError::ValidationError(
+                    "--position and --section can't be used together".to_string(),
+                ))
+            }
+            (Some(position), None) => Placement::Before(position),
+            (None, Some(section)) => Placement::Section(section),
+            (None, None) => Placement::End,
+        };
+        match into {
+            Some(deck) => Ok(Some(MergeConfig {
+                deck,
+                placement,
+                layout,
+            })),
+            None if placement != Placement::End => Err(BigError::ValidationError(
+                "--position and --section need an existing deck to go --into".to_string(),
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Resolved generate-pdf settings
+struct PdfSettings {
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    width: u32,
+    height: u32,
+    timeout_ms: u64,
+    slide_timeout_ms: u64,
+    expected_slides: Option<usize>,
+}
+
+impl GeneratePdfArgs {
+    fn resolve(
+        &self,
+        project: &ProjectConfig,
+        config: &big_slides::Config,
+    ) -> BigResult<PdfSettings> {
+        Ok(PdfSettings {
+            input: project.resolve_optional("pdf.input", self.input.clone(), None)?,
+            output: project.resolve_optional("pdf.output", self.output.clone(), None)?,
+            width: project.resolve("slides.width", self.width, None, 1920)?,
+            height: project.resolve("slides.height", self.height, None, 1080)?,
+            timeout_ms: project.resolve(
+                "slides.timeout_ms",
+                self.timeout_ms,
+                Some("DEFAULT_TIMEOUT_MS"),
+                config.default_timeout_ms,
+            )?,
+            slide_timeout_ms: resolve_slide_timeout(project, self.slide_timeout_ms)?,
+            expected_slides: project.resolve_optional(
+                "slides.expected_slides",
+                self.expected_slides,
+                None,
+            )?,
+        })
+    }
+}
+
+/// Resolved compare settings
+struct CompareSettings {
+    baseline: Option<PathBuf>,
+    current: Option<PathBuf>,
+    diff_dir: Option<PathBuf>,
+    pattern: String,
+    threshold: f64,
+    tolerance: u8,
+}
+
+impl CompareArgs {
+    fn resolve(&self, project: &ProjectConfig) -> BigResult<CompareSettings> {
+        let defaults = CompareConfig::default();
+        let threshold = project.resolve(
+            "compare.threshold",
+            self.threshold,
+            None,
+            defaults.threshold,
+        )?;
+        if !(0.0..=100.0).contains(&threshold) {
+            return Err(BigError::ValidationError(format!(
+                "Threshold must be a percentage from 0 to 100, got {}",
+                threshold
+            )));
+        }
+
+        Ok(CompareSettings {
+            baseline: project.resolve_optional("compare.baseline", self.baseline.clone(), None)?,
+            current: project.resolve_optional("compare.current", self.current.clone(), None)?,
+            diff_dir: project.resolve_optional("compare.diff_dir", self.diff_dir.clone(), None)?,
+            pattern: project.resolve(
+                "compare.pattern",
+                self.pattern.clone(),
+                None,
+                defaults.pattern,
+            )?,
+            threshold,
+            tolerance: project.resolve(
+                "compare.tolerance",
+                self.tolerance,
+                None,
+                defaults.tolerance,
+            )?,
+        })
+    }
+}
+
+/// Resolved build settings
+struct BuildSettings {
+    input: Option<PathBuf>,
+    output_dir: Option<PathBuf>,
+    artifacts: Vec<Artifact>,
+    keep_intermediates: bool,
+    resources: ResourceSettings,
+    width: u32,
+    height: u32,
+    title: String,
+    aspect_ratio: Option<String>,
+    scale: f64,
+    jobs: usize,
+    slide_timeout_ms: u64,
+    expected_slides: Option<usize>,
+    overflow: OverflowSettings,
+    transition: Transition,
+    template: Option<TemplateConfig>,
+}
+
+impl BuildArgs {
+    fn resolve(&self, project: &ProjectConfig) -> BigResult<BuildSettings> {
+        let input = project.resolve_optional("build.input", self.input.clone(), None)?;
+        let aspect_ratio = resolve_aspect_ratio(project, &self.aspect_ratio, input.as_deref())?;
+        let layout = project.resolve_optional("pptx.layout", self.layout.clone(), None)?;
+        Ok(BuildSettings {
+            output_dir: project.resolve_optional(
+                "build.output_dir",
+                self.output_dir.clone(),
+                None,
+            )?,
+            artifacts: project.resolve(
+                "build.artifacts",
+                self.artifacts.clone(),
+                None,
+                vec![Artifact::Pptx],
+            )?,
+            keep_intermediates: project.resolve(
+                "build.keep_intermediates",
+                self.keep_intermediates.then_some(true),
+                None,
+                false,
+            )?,
+            resources: ResourceSettings::resolve(
+                project,
+                &self.css,
+                &self.js,
+                &self.mode,
+                self.self_contained,
+                self.inline_limit_kb,
+            )?,
+            width: project.resolve("slides.width", self.width, None, 1920)?,
+            height: project.resolve("slides.height", self.height, None, 1080)?,
+            title: resolve_title(project, &self.title, input.as_deref())?,
+            template: resolve_template(project, &self.template, layout, &aspect_ratio)?,
+            aspect_ratio,
+            scale: resolve_scale(project, self.scale)?,
+            jobs: resolve_jobs(project, self.jobs)?,
+            slide_timeout_ms: resolve_slide_timeout(project, self.slide_timeout_ms)?,
+            expected_slides: project.resolve_optional(
+                "slides.expected_slides",
+                self.expected_slides,
+                None,
+            )?,
+            overflow: OverflowSettings::resolve(
+                project,
+                self.min_font_size,
+                &self.overflow_report,
+                self.fail_on_overflow,
+            )?,
+            transition: resolve_transition(
+                project,
+                self.transition,
+                self.transition_speed,
+                self.advance_after,
+            )?,
+            input,
+        })
+    }
+}
+
+/// Resolved watch settings
+struct WatchSettings {
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    resources: ResourceSettings,
+    slides_dir: Option<PathBuf>,
+    pptx_output: Option<PathBuf>,
+    debounce_ms: u64,
+    serve: bool,
+    port: u16,
+    auto_reload: bool,
+    ws_port: Option<u16>,
+}
+
+impl WatchArgs {
+    fn resolve(&self, project: &ProjectConfig) -> BigResult<WatchSettings> {
+        Ok(WatchSettings {
+            input: project.resolve_optional("watch.input", self.input.clone(), None)?,
+            output: project.resolve_optional("watch.output", self.output.clone(), None)?,
+            resources: ResourceSettings::resolve(
+                project,
+                &self.css,
+                &self.js,
+                &self.mode,
+                self.self_contained,
+                self.inline_limit_kb,
+            )?,
+            slides_dir: project.resolve_optional(
+                "watch.slides_dir",
+                self.slides_dir.clone(),
+                None,
+            )?,
+            pptx_output: project.resolve_optional(
+                "watch.pptx_output",
+                self.pptx_output.clone(),
+                None,
+            )?,
+            debounce_ms: project.resolve("watch.debounce_ms", self.debounce_ms, None, 500)?,
+            serve: project.resolve("watch.serve", self.serve, None, true)?,
+            port: project.resolve("watch.port", self.port, None, 8080)?,
+            auto_reload: project.resolve("watch.auto_reload", self.auto_reload, None, true)?,
+            ws_port: project.resolve_optional("watch.ws_port", self.ws_port, None)?,
+        })
+    }
+}
+
+/// Read the frontmatter of a markdown deck, if there is one to read
+fn deck_metadata(markdown: Option<&Path>) -> BigResult<Option<big_slides::html::DeckMetadata>> {
+    match markdown {
+        Some(path) if path.is_file() => Ok(Some(big_slides::html::parse_deck(path)?.metadata)),
+        _ => Ok(None),
+    }
+}
+
+fn resolve_title(
+    project: &ProjectConfig,
+    title: &Option<String>,
+    markdown: Option<&Path>,
+) -> BigResult<String> {
+    let deck_title = deck_metadata(markdown)?.and_then(|metadata| metadata.title);
+    project.resolve_with_deck(
+        "pptx.title",
+        title.clone(),
+        None,
+        deck_title.zip(markdown),
+        "Presentation".to_string(),
+    )
+}
+
+/// Aspect ratio of the PPTX, or `None` to use 16:9 or the template's slide size
+fn resolve_aspect_ratio(
+    project: &ProjectConfig,
+    aspect_ratio: &Option<String>,
+    markdown: Option<&Path>,
+) -> BigResult<Option<String>> {
+    let deck_ratio = deck_metadata(markdown)?.and_then(|metadata| metadata.aspect_ratio);
+    project.resolve_with_deck(
+        "pptx.aspect_ratio",
+        aspect_ratio.clone().map(Some),
+        None,
+        deck_ratio.map(Some).zip(markdown),
+        None,
+    )
+}
+
+/// Number of tabs to render slides with, which must be at least one
+fn resolve_jobs(project: &ProjectConfig, jobs: Option<usize>) -> BigResult<usize> {
+    let jobs = project.resolve("slides.jobs", jobs, None, 1)?;
+    if jobs == 0 {
+        return Err(BigError::ValidationError(
+            "--jobs must be at least 1".to_string(),
+        ));
+    }
+    Ok(jobs)
+}
+
+/// Quality of lossy slide images, which must be at most 100
+fn resolve_quality(project: &ProjectConfig, quality: Option<u8>) -> BigResult<Option<u8>> {
+    let quality = project.resolve_optional("slides.quality", quality, None)?;
+    if quality.is_some_and(|quality| quality > 100) {
+        return Err(BigError::ValidationError(
+            "--quality must be between 0 and 100".to_string(),
+        ));
+    }
+    Ok(quality)
+}
+
+/// Device scale factor to render slides at, which must be positive
+fn resolve_scale(project: &ProjectConfig, scale: Option<f64>) -> BigResult<f64> {
+    let scale = project.resolve("slides.scale", scale, None, 1.0)?;
+    if !(scale.is_finite() && scale > 0.0) {
+        return Err(BigError::ValidationError(format!(
+            "--scale must be a positive number, got {}",
+            scale
+        )));
+    }
+    Ok(scale)
+}
+
+/// Default PPTX transition and timing, shared by generate-pptx and build
+fn resolve_transition(
+    project: &ProjectConfig,
+    effect: Option<TransitionEffect>,
+    speed: Option<TransitionSpeed>,
+    advance_after: Option<Duration>,
+) -> BigResult<Transition> {
+    let defaults = Transition::default();
+    Ok(Transition {
+        effect: project.resolve("pptx.transition", effect, None, defaults.effect)?,
+        speed: project.resolve("pptx.transition_speed", speed, None, defaults.speed)?,
+        advance_after: project.resolve_optional("pptx.advance_after", advance_after, None)?,
+    })
+}
+
+/// PPTX template, shared by generate-pptx and build. Its slide size is kept unless an
+/// aspect ratio is set.
+fn resolve_template(
+    project: &ProjectConfig,
+    template: &Option<PathBuf>,
+    layout: Option<String>,
+    aspect_ratio: &Option<String>,
+) -> BigResult<Option<TemplateConfig>> {
+    let path = project.resolve_optional("pptx.template", template.clone(), None)?;
+    Ok(path.map(|path| TemplateConfig {
+        path,
+        layout,
+        keep_size: aspect_ratio.is_none(),
+    }))
+}
+
+/// How long to wait for each slide to become ready before capturing it
+fn resolve_slide_timeout(project: &ProjectConfig, timeout_ms: Option<u64>) -> BigResult<u64> {
+    project.resolve(
+        "slides.slide_timeout_ms",
+        timeout_ms,
+        None,
+        big_slides::render::DEFAULT_SLIDE_TIMEOUT_MS,
+    )
+}
+
+/// Unwrap a setting the command can't run without
+fn required(value: Option<PathBuf>, flag: &str, key: &str) -> BigResult<PathBuf> {
+    value.ok_or_else(|| {
+        BigError::ValidationError(format!(
+            "Missing {}: pass it on the command line or set `{}` in big.toml",
+            flag, key
+        ))
+    })
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Parse command line arguments
     let cli = Cli::parse();
@@ -180,25 +1146,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     env_logger::Builder::new().filter_level(log_level).init();
 
-    // Load configuration
-    let mut config = big_slides::Config::from_env();
-
-    // Override with command line options
-    if let Some(browser_path) = cli.browser_path {
-        config.browser_path = Some(browser_path);
-    }
-
     // Execute command
-    let result = match &cli.command {
-        Some(Commands::GenerateHtml(args)) => generate_html(args, &config),
-        Some(Commands::GenerateSlides(args)) => generate_slides(args, &config),
-        Some(Commands::GeneratePptx(args)) => generate_pptx(args, &config),
-        Some(Commands::Watch(args)) => watch(args, &config),
-        None => {
-            println!("No command specified. Use --help for usage information.");
-            Ok(())
-        }
-    };
+    let result = run(&cli);
 
     // Handle errors
     match result {
@@ -236,137 +1185,368 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
+/// Load the configuration and execute the requested command
+fn run(cli: &Cli) -> BigResult<()> {
+    let Some(command) = &cli.command else {
+        println!("No command specified. Use --help for usage information.");
+        return Ok(());
+    };
+
+    // Load configuration: command line > environment > big.toml > built-in defaults
+    let project = ProjectConfig::discover(command.markdown_path())?;
+    let mut config = big_slides::Config::from_project(
+        &project,
+        cli.browser_path.clone(),
+        cli.offline.then_some(true),
+    )?;
+    if cli.refresh {
+        config.cache_policy = CachePolicy::Refresh;
+    }
+    cache::set_policy(config.cache_policy);
+
+    match command {
+        Commands::GenerateHtml(args) => generate_html(&args.resolve(&project)?),
+        Commands::GenerateSlides(args) => {
+            generate_slides(&args.resolve(&project, &config)?, &config)
+        }
+        Commands::GeneratePptx(args) => generate_pptx(&args.resolve(&project)?, &config),
+        Commands::GeneratePdf(args) => generate_pdf(&args.resolve(&project, &config)?, &config),
+        Commands::Build(args) => build(&args.resolve(&project)?, &config),
+        Commands::Watch(args) => watch(&args.resolve(&project)?, &config),
+        Commands::Config(ConfigCommand::Show(args)) => {
+            config_show(&project, &config, args.input.as_deref())
+        }
+        Commands::Cache(command) => cache_command(command),
+        Commands::Doctor(args) => doctor(&project, &config, args),
+        Commands::Compare(args) => compare(&args.resolve(&project)?),
+    }
+}
+
+/// Convert resource paths to ResourceFile structs
+fn resource_files(files: &[String]) -> Vec<big_slides::ResourceFile> {
+    files
+        .iter()
+        .map(|path| big_slides::ResourceFile::new(path))
+        .collect()
+}
+
 /// Execute the generate-html command
-fn generate_html(args: &GenerateHtmlArgs, config: &big_slides::Config) -> BigResult<()> {
+fn generate_html(settings: &HtmlSettings) -> BigResult<()> {
     info!("Executing generate-html command...");
 
+    let input = required(settings.input.clone(), "--input", "html.input")?;
+    let output = required(settings.output.clone(), "--output", "html.output")?;
+
     // Validate input file exists
-    utils::validate_file_exists(&args.input)?;
+    utils::validate_file_exists(&input)?;
 
     // Ensure parent directory for output exists
-    utils::ensure_parent_directory_exists(&args.output)?;
-
-    // Parse mode flag
-    let embed_resources = args.mode.to_lowercase() != "link";
-
-    // Convert CSS files to ResourceFile structs
-    let css_files: Vec<big_slides::ResourceFile> = match args.css.as_ref() {
-        Some(files) if !files.is_empty() => {
-            // User-specified CSS files
-            files
-                .iter()
-                .map(|path| big_slides::ResourceFile::new(path))
-                .collect()
-        }
-        _ => {
-            // No CSS specified, use default
-            info!(
-                "No CSS files specified, using default CSS: {}",
-                config.default_css
-            );
-            vec![big_slides::ResourceFile::new(&config.default_css)]
-        }
-    };
-
-    // Convert JS files to ResourceFile structs
-    let js_files: Vec<big_slides::ResourceFile> = match args.js.as_ref() {
-        Some(files) if !files.is_empty() => {
-            // User-specified JS files
-            files
-                .iter()
-                .map(|path| big_slides::ResourceFile::new(path))
-                .collect()
-        }
-        _ => {
-            // No JS specified, use default
-            info!(
-                "No JavaScript files specified, using default JS: {}",
-                config.default_js
-            );
-            vec![big_slides::ResourceFile::new(&config.default_js)]
-        }
-    };
+    utils::ensure_parent_directory_exists(&output)?;
 
     // Generate HTML content
-    let html_content =
-        big_slides::html::generate_html(&args.input, &css_files, &js_files, embed_resources, None)?;
+    let html_content = big_slides::html::generate_html(
+        &input,
+        &settings.resources.css_files(),
+        &settings.resources.js_files(),
+        settings.resources.embed(),
+        settings.resources.inline().as_ref(),
+        None,
+    )?;
 
     // Write the HTML content to the output file
-    big_slides::html::write_html_to_file(&html_content, &args.output)?;
+    big_slides::html::write_html_to_file(&html_content, &output)?;
 
-    info!("HTML generated successfully: {:?}", args.output);
-    println!("HTML generated successfully: {:?}", args.output);
+    info!("HTML generated successfully: {:?}", output);
+    println!("HTML generated successfully: {:?}", output);
     Ok(())
 }
 
 /// Execute the generate-slides command
-fn generate_slides(args: &GenerateSlidesArgs, config: &big_slides::Config) -> BigResult<()> {
+fn generate_slides(settings: &SlidesSettings, config: &big_slides::Config) -> BigResult<()> {
     info!("Executing generate-slides command...");
 
+    let input = required(settings.input.clone(), "--input", "slides.input")?;
+    let output_dir = required(
+        settings.output_dir.clone(),
+        "--output-dir",
+        "slides.output_dir",
+    )?;
+
     // Validate input file exists
-    utils::validate_file_exists(&args.input)?;
+    utils::validate_file_exists(&input)?;
 
     // Ensure output directory exists
-    utils::ensure_directory_exists(&args.output_dir)?;
+    utils::ensure_directory_exists(&output_dir)?;
 
     // Validate output directory is writable
-    utils::validate_directory_writable(&args.output_dir)?;
+    utils::validate_directory_writable(&output_dir)?;
 
     // Create render configuration
-    let render_config = config.get_render_config(
-        Some(args.width),
-        Some(args.height),
-        Some(args.format.clone()),
-        Some(args.base_name.clone()),
-        args.timeout_ms,
+    let mut render_config = config.get_render_config(
+        Some(settings.width),
+        Some(settings.height),
+        Some(settings.format.clone()),
+        Some(settings.base_name.clone()),
+        Some(settings.timeout_ms),
+    );
+    render_config.scale = settings.scale;
+    render_config.quality = settings.quality;
+    render_config.jobs = settings.jobs;
+    render_config.slide_timeout_ms = settings.slide_timeout_ms;
+    render_config.expected_slides = settings.expected_slides;
+    render_config.slides = settings.slides.clone();
+    render_config.incremental = settings.incremental;
+    render_config.min_font_px = settings.overflow.min_font_px;
+    render_config.overflow_report = Some(
+        settings
+            .overflow
+            .report
+            .clone()
+            .unwrap_or_else(|| output_dir.join(OVERFLOW_REPORT_FILE)),
     );
+    render_config.fail_on_overflow = settings.overflow.fail;
 
     // Generate slides (screenshots)
-    let output_files =
-        big_slides::render::generate_slides(&args.input, &args.output_dir, &render_config)?;
+    let output_files = big_slides::render::generate_slides(&input, &output_dir, &render_config)?;
 
     info!("Generated {} slides", output_files.len());
-    println!("Slides generated successfully: {:?}", args.output_dir);
+    println!("Slides generated successfully: {:?}", output_dir);
     Ok(())
 }
 
 /// Execute the generate-pptx command
-fn generate_pptx(args: &GeneratePptxArgs, config: &big_slides::Config) -> BigResult<()> {
+fn generate_pptx(settings: &PptxSettings, config: &big_slides::Config) -> BigResult<()> {
     info!("Executing generate-pptx command...");
 
-    // Validate input directory exists
-    utils::validate_directory_exists(&args.input_dir)?;
+    // Slides added to an existing deck are written back to it unless told otherwise
+    let output = settings
+        .output
+        .clone()
+        .or_else(|| settings.merge.as_ref().map(|merge| merge.deck.clone()));
+    let output = required(output, "--output", "pptx.output")?;
+
+    // Native slides are built from the markdown, image slides need a directory of images
+    let input_dir = if settings.native {
+        if settings.markdown.is_none() {
+            return Err(BigError::ValidationError(
+                "--native requires --markdown (or `pptx.markdown` in big.toml)".to_string(),
+            ));
+        }
+        settings.input_dir.clone().unwrap_or_default()
+    } else {
+        let input_dir = required(settings.input_dir.clone(), "--input-dir", "pptx.input_dir")?;
+        utils::validate_directory_exists(&input_dir)?;
+        input_dir
+    };
+
+    // Validate markdown source exists, if given
+    if let Some(markdown) = &settings.markdown {
+        utils::validate_file_exists(markdown)?;
+    }
+
+    if let Some(splice) = &settings.splice {
+        utils::validate_directory_exists(splice)?;
+    }
 
     // Ensure parent directory for output exists
-    utils::ensure_parent_directory_exists(&args.output)?;
+    utils::ensure_parent_directory_exists(&output)?;
 
     // Create PPTX configuration
-    let pptx_config = config.get_pptx_config(
-        Some(args.title.clone()),
-        Some(args.pattern.clone()),
-        Some(args.aspect_ratio.clone()),
+    let mut pptx_config = config.get_pptx_config(
+        Some(settings.title.clone()),
+        Some(settings.pattern.clone()),
+        settings.aspect_ratio.clone(),
+        settings.markdown.clone(),
+    );
+    pptx_config.native = settings.native;
+    pptx_config.splice_dir = settings.splice.clone();
+    pptx_config.transition = settings.transition;
+    pptx_config.template = settings.template.clone();
+
+    // Generate PowerPoint presentation from images, or natively from the markdown
+    if let Some(merge) = &settings.merge {
+        utils::validate_file_exists(&merge.deck)?;
+        big_slides::merge::merge_pptx(&input_dir, &output, &pptx_config, merge)?;
+        info!("Slides added to {:?}: {:?}", merge.deck, output);
+        println!("Slides added to {:?}: {:?}", merge.deck, output);
+        return Ok(());
+    }
+    big_slides::pptx::generate_pptx(&input_dir, &output, &pptx_config)?;
+
+    info!("PPTX generated successfully: {:?}", output);
+    println!("PPTX generated successfully: {:?}", output);
+    Ok(())
+}
+
+/// Execute the generate-pdf command
+fn generate_pdf(settings: &PdfSettings, config: &big_slides::Config) -> BigResult<()> {
+    info!("Executing generate-pdf command...");
+
+    let input = required(settings.input.clone(), "--input", "pdf.input")?;
+    let output = required(settings.output.clone(), "--output", "pdf.output")?;
+
+    // Validate input file exists
+    utils::validate_file_exists(&input)?;
+
+    // Ensure parent directory for output exists
+    utils::ensure_parent_directory_exists(&output)?;
+
+    // Create render configuration
+    let mut render_config = config.get_render_config(
+        Some(settings.width),
+        Some(settings.height),
+        None,
+        None,
+        Some(settings.timeout_ms),
     );
+    render_config.slide_timeout_ms = settings.slide_timeout_ms;
+    render_config.expected_slides = settings.expected_slides;
+
+    // Print the slides to PDF
+    let page_count = big_slides::render::generate_pdf(&input, &output, &render_config)?;
+
+    info!("Generated {} PDF pages", page_count);
+    println!("PDF generated successfully: {:?}", output);
+    Ok(())
+}
 
-    // Generate PowerPoint presentation from images
-    big_slides::pptx::generate_pptx(&args.input_dir, &args.output, &pptx_config)?;
+/// Execute the compare command
+fn compare(settings: &CompareSettings) -> BigResult<()> {
+    info!("Executing compare command...");
+
+    let baseline = required(settings.baseline.clone(), "--baseline", "compare.baseline")?;
+    let current = required(settings.current.clone(), "--current", "compare.current")?;
+    utils::validate_directory_exists(&baseline)?;
+    utils::validate_directory_exists(&current)?;
+
+    let compare_config = CompareConfig {
+        diff_dir: Some(
+            settings
+                .diff_dir
+                .clone()
+                .unwrap_or_else(|| current.join("diff")),
+        ),
+        baseline_dir: baseline,
+        current_dir: current,
+        pattern: settings.pattern.clone(),
+        threshold: settings.threshold,
+        tolerance: settings.tolerance,
+    };
+    let comparisons = big_slides::compare::compare_slides(&compare_config)?;
+
+    let mut changed = Vec::new();
+    for comparison in &comparisons {
+        let failed = comparison.exceeds(settings.threshold);
+        let mut line = format!(
+            "[{:>7}] {}: {}",
+            if failed { "CHANGED" } else { "ok" },
+            comparison.file,
+            comparison.difference
+        );
+        if let Some(diff_image) = &comparison.diff_image {
+            line.push_str(&format!(" (diff: {})", diff_image.display()));
+        }
+        println!("{}", line);
+        if failed {
+            changed.push(comparison.file.clone());
+        }
+    }
 
-    info!("PPTX generated successfully: {:?}", args.output);
-    println!("PPTX generated successfully: {:?}", args.output);
+    if !changed.is_empty() {
+        return Err(BigError::SlidesChanged(format!(
+            "{} of {} slides changed by more than {}%: {}",
+            changed.len(),
+            comparisons.len(),
+            settings.threshold,
+            changed.join(", ")
+        )));
+    }
+    println!(
+        "All {} slides match the baseline within {}%",
+        comparisons.len(),
+        settings.threshold
+    );
+    Ok(())
+}
+
+/// Execute the build command
+fn build(settings: &BuildSettings, config: &big_slides::Config) -> BigResult<()> {
+    info!("Executing build command...");
+
+    let input = required(settings.input.clone(), "--input", "build.input")?;
+    let output_dir = required(
+        settings.output_dir.clone(),
+        "--output-dir",
+        "build.output_dir",
+    )?;
+
+    // Validate input file exists
+    utils::validate_file_exists(&input)?;
+
+    // Ensure output directory exists
+    utils::ensure_directory_exists(&output_dir)?;
+
+    let build_config = big_slides::BuildConfig {
+        markdown_path: input,
+        output_dir: output_dir.clone(),
+        artifacts: settings.artifacts.clone(),
+        keep_intermediates: settings.keep_intermediates,
+        css_files: settings.resources.css_files(),
+        js_files: settings.resources.js_files(),
+        embed_resources: settings.resources.embed(),
+        inline: settings.resources.inline(),
+        width: settings.width,
+        height: settings.height,
+        title: settings.title.clone(),
+        aspect_ratio: settings
+            .aspect_ratio
+            .clone()
+            .unwrap_or_else(|| "16:9".to_string()),
+        scale: settings.scale,
+        jobs: settings.jobs,
+        slide_timeout_ms: settings.slide_timeout_ms,
+        expected_slides: settings.expected_slides,
+        min_font_px: settings.overflow.min_font_px,
+        overflow_report: settings.overflow.report.clone(),
+        fail_on_overflow: settings.overflow.fail,
+        transition: settings.transition,
+        template: settings.template.clone(),
+    };
+
+    let output = big_slides::build::build_presentation(&build_config, config)?;
+
+    if let Some(html) = &output.html {
+        println!("HTML generated successfully: {:?}", html);
+    }
+    if !output.slides.is_empty() {
+        println!(
+            "Slides generated successfully: {:?}",
+            output_dir.join("slides")
+        );
+    }
+    if let Some(pptx) = &output.pptx {
+        println!("PPTX generated successfully: {:?}", pptx);
+    }
+    if let Some(pdf) = &output.pdf {
+        println!("PDF generated successfully: {:?}", pdf);
+    }
     Ok(())
 }
 
 /// Execute the watch command
-fn watch(args: &WatchArgs, config: &big_slides::Config) -> BigResult<()> {
+fn watch(settings: &WatchSettings, config: &big_slides::Config) -> BigResult<()> {
     info!("Executing watch command...");
 
+    let input = required(settings.input.clone(), "--input", "watch.input")?;
+
     // Validate input file exists
-    utils::validate_file_exists(&args.input)?;
+    utils::validate_file_exists(&input)?;
 
     // Generate output path if not provided
-    let output_path = match &args.output {
+    let output_path = match &settings.output {
         Some(path) => path.clone(),
         None => {
-            let mut output = args.input.clone();
+            let mut output = input.clone();
             output.set_extension("html");
             output
         }
@@ -376,73 +1556,221 @@ fn watch(args: &WatchArgs, config: &big_slides::Config) -> BigResult<()> {
     utils::ensure_parent_directory_exists(&output_path)?;
 
     // If slides output is specified, ensure that directory exists
-    if let Some(slides_dir) = &args.slides_dir {
+    if let Some(slides_dir) = &settings.slides_dir {
         utils::ensure_directory_exists(slides_dir)?;
         utils::validate_directory_writable(slides_dir)?;
     }
 
     // If PPTX output is specified, ensure parent directory exists
-    if let Some(pptx_output) = &args.pptx_output {
+    if let Some(pptx_output) = &settings.pptx_output {
         utils::ensure_parent_directory_exists(pptx_output)?;
     }
 
-    // Parse mode flag
-    let embed_resources = args.mode.to_lowercase() != "link";
+    // Create watch configuration
+    let watch_config = big_slides::WatchConfig {
+        markdown_path: input,
+        html_output: output_path.clone(),
+        slides_output_dir: settings.slides_dir.clone(),
+        pptx_output: settings.pptx_output.clone(),
+        css_files: settings.resources.css_files(),
+        js_files: settings.resources.js_files(),
+        embed_resources: settings.resources.embed(),
+        inline: settings.resources.inline(),
+        debounce_ms: settings.debounce_ms,
+        serve: settings.serve,
+        port: settings.port,
+        auto_reload: settings.auto_reload,
+        ws_port: settings.ws_port,
+    };
+
+    // Start watching
+    big_slides::watch_markdown(watch_config, config)
+}
 
-    // Convert CSS files to ResourceFile structs
-    let css_files: Vec<big_slides::ResourceFile> = match args.css.as_ref() {
-        Some(files) if !files.is_empty() => {
-            // User-specified CSS files
-            files
-                .iter()
-                .map(|path| big_slides::ResourceFile::new(path))
-                .collect()
+/// Execute the config show command
+fn config_show(
+    project: &ProjectConfig,
+    config: &big_slides::Config,
+    markdown: Option<&Path>,
+) -> BigResult<()> {
+    // Resolve every command's settings for the given deck, without other arguments
+    let markdown = markdown.map(Path::to_path_buf);
+    GenerateHtmlArgs {
+        input: markdown.clone(),
+        ..Default::default()
+    }
+    .resolve(project)?;
+    GenerateSlidesArgs::default().resolve(project, config)?;
+    GeneratePptxArgs {
+        markdown: markdown.clone(),
+        ..Default::default()
+    }
+    .resolve(project)?;
+    GeneratePdfArgs::default().resolve(project, config)?;
+    CompareArgs::default().resolve(project)?;
+    BuildArgs {
+        input: markdown.clone(),
+        ..Default::default()
+    }
+    .resolve(project)?;
+    WatchArgs {
+        input: markdown,
+        ..Default::default()
+    }
+    .resolve(project)?;
+
+    match &project.path {
+        Some(path) => println!("# Configuration file: {}", path.display()),
+        None => println!("# No big.toml found"),
+    }
+
+    // Settings shared between commands are listed once
+    let mut settings = project.resolved();
+    let mut seen = std::collections::HashSet::new();
+    settings.retain(|setting| seen.insert(setting.key.clone()));
+
+    let width = settings
+        .iter()
+        .map(|s| s.key.len() + s.value.len() + 3)
+        .max()
+        .unwrap_or(0);
+    for setting in &settings {
+        let line = format!("{} = {}", setting.key, setting.value);
+        println!("{:<width$}  # {}", line, setting.source, width = width);
+    }
+
+    for key in project.unused_keys() {
+        println!("# Unknown setting in big.toml: {}", key);
+    }
+    Ok(())
+}
+
+/// Execute the cache subcommands
+fn cache_command(command: &CacheCommand) -> BigResult<()> {
+    let cache = ResourceCache::open()?;
+    match command {
+        CacheCommand::List => {
+            let entries = cache.entries()?;
+            println!("# Cache directory: {}", cache.dir().display());
+            if entries.is_empty() {
+                println!("# No cached resources");
+            }
+            for entry in entries {
+                let validator = entry
+                    .etag
+                    .as_deref()
+                    .or(entry.last_modified.as_deref())
+                    .unwrap_or("-");
+                println!(
+                    "{}  {} bytes  fetched {}  {}",
+                    entry.url,
+                    entry.size,
+                    entry.fetched_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                    validator
+                );
+            }
         }
-        _ => {
-            // No CSS specified, use default
-            info!(
-                "No CSS files specified, using default CSS: {}",
-                config.default_css
+        CacheCommand::Clear => {
+            let count = cache.clear()?;
+            println!(
+                "Removed {} cached resource(s) from {}",
+                count,
+                cache.dir().display()
             );
-            vec![big_slides::ResourceFile::new(&config.default_css)]
         }
-    };
+    }
+    Ok(())
+}
 
-    // Convert JS files to ResourceFile structs
-    let js_files: Vec<big_slides::ResourceFile> = match args.js.as_ref() {
-        Some(files) if !files.is_empty() => {
-            // User-specified JS files
-            files
-                .iter()
-                .map(|path| big_slides::ResourceFile::new(path))
-                .collect()
+/// Execute the doctor command
+fn doctor(
+    project: &ProjectConfig,
+    config: &big_slides::Config,
+    args: &DoctorArgs,
+) -> BigResult<()> {
+    // Check the outputs and resources the other commands would use for this deck
+    let markdown = args.input.clone();
+    let html = GenerateHtmlArgs {
+        input: markdown.clone(),
+        ..Default::default()
+    }
+    .resolve(project)?;
+    let slides = GenerateSlidesArgs::default().resolve(project, config)?;
+    let pptx = GeneratePptxArgs {
+        markdown: markdown.clone(),
+        ..Default::default()
+    }
+    .resolve(project)?;
+    let pdf = GeneratePdfArgs::default().resolve(project, config)?;
+    let build = BuildArgs {
+        input: markdown.clone(),
+        ..Default::default()
+    }
+    .resolve(project)?;
+    let watch = WatchArgs {
+        input: markdown,
+        ..Default::default()
+    }
+    .resolve(project)?;
+
+    let mut output_dirs = args.output_dir.clone();
+    output_dirs.extend(
+        [slides.output_dir, build.output_dir, watch.slides_dir]
+            .into_iter()
+            .flatten(),
+    );
+    for file in [html.output, pptx.output, pdf.output, watch.output]
+        .into_iter()
+        .flatten()
+    {
+        match file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => {
+                output_dirs.push(parent.to_path_buf())
+            }
+            _ => output_dirs.push(PathBuf::from(".")),
         }
-        _ => {
-            // No JS specified, use default
-            info!(
-                "No JavaScript files specified, using default JS: {}",
-                config.default_js
-            );
-            vec![big_slides::ResourceFile::new(&config.default_js)]
+    }
+    if output_dirs.is_empty() {
+        output_dirs.push(PathBuf::from("."));
+    }
+    let mut seen = std::collections::HashSet::new();
+    output_dirs.retain(|dir| seen.insert(dir.clone()));
+
+    let mut remote_resources = Vec::new();
+    for resources in [&html.resources, &build.resources, &watch.resources] {
+        for file in resources
+            .css_files()
+            .into_iter()
+            .chain(resources.js_files())
+        {
+            if file.is_remote && !remote_resources.contains(&file.path) {
+                remote_resources.push(file.path);
+            }
         }
-    };
+    }
 
-    // Create watch configuration
-    let watch_config = big_slides::WatchConfig {
-        markdown_path: args.input.clone(),
-        html_output: output_path.clone(),
-        slides_output_dir: args.slides_dir.clone(),
-        pptx_output: args.pptx_output.clone(),
-        css_files,
-        js_files,
-        embed_resources,
-        debounce_ms: args.debounce_ms,
-        serve: args.serve,
-        port: args.port,
-        auto_reload: args.auto_reload,
-        ws_port: args.ws_port,
-    };
+    let checks = big_slides::doctor::run_checks(&DoctorConfig {
+        browser_path: config.browser_path.clone(),
+        output_dirs,
+        remote_resources,
+        offline: config.cache_policy == CachePolicy::Offline,
+        ..Default::default()
+    });
+
+    for check in &checks {
+        println!("[{:>4}] {}: {}", check.status, check.name, check.detail);
+        if let Some(fix) = &check.fix {
+            println!("       fix: {}", fix);
+        }
+    }
 
-    // Start watching
-    big_slides::watch_markdown(watch_config, config)
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Failed)
+        .count();
+    if failed > 0 {
+        return Err(BigError::DiagnosticsFailed(failed));
+    }
+    println!("No problems found");
+    Ok(())
 }
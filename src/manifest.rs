@@ -0,0 +1,138 @@
+// ABOUTME: Manifest of rendered slide images for incremental rendering
+// ABOUTME: Hashes each slide with the deck's styles and scripts so unchanged slides are skipped
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::errors::{BigError, Result};
+
+/// Name of the manifest file kept in the slides directory
+pub const MANIFEST_FILE: &str = ".big-manifest.toml";
+
+/// The parts of a loaded deck that decide what its slides look like
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DeckSources {
+    /// Markup shared by every slide: the head, plus styles and scripts in the body
+    pub shared: Vec<String>,
+
+    /// URLs of the stylesheets and scripts the page links to
+    pub resources: Vec<String>,
+
+    /// Each slide, in deck order
+    pub slides: Vec<SlideSource>,
+}
+
+/// The markup of a single slide and the images it shows
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SlideSource {
+    pub html: String,
+    pub resources: Vec<String>,
+}
+
+impl DeckSources {
+    /// Hash every slide together with the shared markup and `settings`, which should
+    /// cover anything else that changes the image, such as its size and format.
+    /// Local files behind resource URLs are hashed by content, remote ones by URL.
+    pub fn slide_hashes(&self, settings: &str) -> Vec<String> {
+        let mut shared = Sha256::new();
+        shared.update(settings.as_bytes());
+        for markup in &self.shared {
+            shared.update(markup.as_bytes());
+        }
+        hash_resources(&mut shared, &self.resources);
+
+        self.slides
+            .iter()
+            .map(|slide| {
+                let mut hasher = shared.clone();
+                hasher.update(slide.html.as_bytes());
+                hash_resources(&mut hasher, &slide.resources);
+                hex(&hasher.finalize())
+            })
+            .collect()
+    }
+}
+
+/// Add the URLs and, for local files, the contents of `resources` to a hash
+fn hash_resources(hasher: &mut Sha256, resources: &[String]) {
+    for resource in resources {
+        hasher.update(resource.as_bytes());
+        let local = url::Url::parse(resource)
+            .ok()
+            .filter(|url| url.scheme() == "file")
+            .and_then(|url| url.to_file_path().ok());
+        if let Some(content) = local.and_then(|path| fs::read(path).ok()) {
+            hasher.update(&content);
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Which slide images in a directory were rendered from which content
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SlideManifest {
+    /// Content hash of each image, by file name
+    pub slides: BTreeMap<String, String>,
+}
+
+impl SlideManifest {
+    /// Read the manifest in `dir`. A missing or unreadable manifest is empty,
+    /// so every slide gets rendered.
+    pub fn load(dir: &Path) -> Self {
+        let path = Self::path(dir);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => return Self::default(),
+        };
+        toml::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable slide manifest {:?}: {}", path, e);
+            Self::default()
+        })
+    }
+
+    /// Write the manifest to `dir`
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let content = toml::to_string(self).map_err(|e| {
+            BigError::SerializationError(format!("Failed to write slide manifest: {}", e))
+        })?;
+        fs::write(Self::path(dir), content).map_err(BigError::FileReadError)
+    }
+
+    /// Whether `file` in `dir` exists and was rendered from content with `hash`
+    pub fn is_current(&self, dir: &Path, file: &str, hash: &str) -> bool {
+        self.slides.get(file).is_some_and(|known| known == hash) && dir.join(file).is_file()
+    }
+
+    /// Delete the images listed in the manifest that aren't in `keep`, such as those of
+    /// removed slides, and forget them. Returns the deleted files.
+    pub fn remove_stale(&mut self, dir: &Path, keep: &[String]) -> Result<Vec<PathBuf>> {
+        let stale: Vec<String> = self
+            .slides
+            .keys()
+            .filter(|file| !keep.contains(file))
+            .cloned()
+            .collect();
+
+        let mut removed = Vec::with_capacity(stale.len());
+        for file in stale {
+            self.slides.remove(&file);
+            let path = dir.join(&file);
+            if path.is_file() {
+                fs::remove_file(&path).map_err(BigError::FileReadError)?;
+                removed.push(path);
+            }
+        }
+        Ok(removed)
+    }
+
+    fn path(dir: &Path) -> PathBuf {
+        dir.join(MANIFEST_FILE)
+    }
+}
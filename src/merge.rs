@@ -0,0 +1,904 @@
+// ABOUTME: Adding generated slides to an existing PPTX
+// ABOUTME: Inserts slides at a position or in place of a named section and renumbers the package
+
+use log::info;
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::build::TempPath;
+use crate::errors::{BigError, Result};
+use crate::pptx::{self, PptxConfig};
+use crate::template::{self, Relationship, TemplateConfig};
+
+const CONTENT_TYPES: &str = "[Content_Types].xml";
+
+/// What PowerPoint lists in the document properties for a slide without a title
+const UNTITLED_SLIDE: &str = "PowerPoint Presentation";
+
+/// The heading of the slide titles in the document properties
+const SLIDE_TITLES_HEADING: &str = "Slide Titles";
+
+/// Where generated slides go in an existing deck
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Placement {
+    /// After the last slide
+    #[default]
+    End,
+    /// Before the slide with this number, counting from 1
+    Before(usize),
+    /// In place of the slides of the section with this name
+    Section(String),
+}
+
+/// How to add generated slides to an existing deck
+#[derive(Debug, Clone)]
+pub struct MergeConfig {
+    /// The PPTX the slides are added to
+    pub deck: PathBuf,
+
+    pub placement: Placement,
+
+    /// Name of the deck's layout the new slides use; its blank layout if `None`
+    pub layout: Option<String>,
+}
+
+/// Generate slides as `generate_pptx` would and add them to the deck in `merge`, writing
+/// the result to `output_file`, which may be the deck itself. The slides use the deck's
+/// masters, layouts and slide size, so any template in `config` is ignored.
+pub fn merge_pptx(
+    slides_dir: &Path,
+    output_file: &Path,
+    config: &PptxConfig,
+    merge: &MergeConfig,
+) -> Result<()> {
+    info!("Adding generated slides to {:?}", merge.deck);
+    if !merge.deck.is_file() {
+        return Err(BigError::PathNotFoundError(merge.deck.clone()));
+    }
+    let mut deck = Package::open(&merge.deck)?;
+
+    // Generate the slides on the deck itself, so they refer to layouts it already has
+    let generated_path =
+        TempPath(env::temp_dir().join(format!("big-merge-{}.pptx", uuid::Uuid::new_v4())));
+    let generate_config = PptxConfig {
+        template: Some(TemplateConfig {
+            path: merge.deck.clone(),
+            layout: merge.layout.clone(),
+            keep_size: true,
+        }),
+        ..config.clone()
+    };
+    pptx::generate_pptx(slides_dir, &generated_path.0, &generate_config)?;
+    let generated = Package::open(&generated_path.0)?;
+    let generated_types = generated.content_types()?;
+
+    let presentation = deck.main_part()?;
+    let mut presentation_xml = deck.xml(&presentation)?;
+    let mut presentation_rels = deck.relationships(&presentation)?;
+    let slides = slide_ids(&presentation_xml)?;
+    let mut sections = sections(&presentation_xml)?;
+
+    // Work out where the slides go and which ones they replace
+    let (index, replaced) = match &merge.placement {
+        Placement::End => (slides.len(), Vec::new()),
+        Placement::Before(number) => {
+            if *number == 0 || *number > slides.len() + 1 {
+                return Err(BigError::ValidationError(format!(
+                    "Cannot insert before slide {}: the deck has {} slides",
+                    number,
+                    slides.len()
+                )));
+            }
+            (number - 1, Vec::new())
+        }
+        Placement::Section(name) => {
+            let position = sections
+                .iter()
+                .position(|section| section.name.eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| {
+                    let names: Vec<&str> = sections.iter().map(|s| s.name.as_str()).collect();
+                    BigError::ValidationError(if names.is_empty() {
+                        format!("The deck has no sections, so there is no '{}'", name)
+                    } else {
+                        format!(
+                            "The deck has no section called '{}'. Its sections are: {}",
+                            name,
+                            names.join(", ")
+                        )
+                    })
+                })?;
+            // An empty section starts after the slides of the sections before it
+            let section = &sections[position];
+            let index = match section.ids.first() {
+                Some(first) => slides.iter().position(|(id, _)| id == first).unwrap_or(0),
+                None => sections[..position].iter().map(|s| s.ids.len()).sum(),
+            };
+            (
+                index.min(slides.len().saturating_sub(section.ids.len())),
+                section.ids.clone(),
+            )
+        }
+    };
+
+    // Take out the replaced slides, along with notes and media nothing else uses
+    let mut replaced_parts = Vec::new();
+    let mut kept = Vec::new();
+    for (id, rel_id) in slides {
+        if replaced.contains(&id) {
+            if let Some(rel) = presentation_rels.iter().find(|rel| rel.id == rel_id) {
+                replaced_parts.push(template::resolve_target(&presentation, &rel.target));
+            }
+            presentation_rels.retain(|rel| rel.id != rel_id);
+        } else {
+            kept.push((id, rel_id));
+        }
+    }
+    deck.set_relationships(&presentation, &presentation_rels);
+    let mut removed = deck.reachable(replaced_parts, &presentation);
+    for part in deck.reachable(deck.root_targets()?, &presentation) {
+        removed.remove(&part);
+    }
+    for part in &removed {
+        deck.remove(part);
+    }
+    if !removed.is_empty() {
+        info!("Removed {} parts of the replaced slides", removed.len());
+    }
+
+    // Copy the generated slides in under names and ids the deck doesn't use
+    let notes_master = presentation_rels
+        .iter()
+        .find(|rel| template::rel_kind(&rel.rel_type) == "notesMaster")
+        .map(|rel| template::resolve_target(&presentation, &rel.target));
+    let generated_presentation = generated.main_part()?;
+    let generated_rels = generated.relationships(&generated_presentation)?;
+    let mut next_id = kept.iter().map(|(id, _)| *id).max().unwrap_or(255).max(255) + 1;
+    let mut imported = HashMap::new();
+    let mut new_slides = Vec::new();
+    for (_, rel_id) in slide_ids(&generated.xml(&generated_presentation)?)? {
+        let Some(rel) = generated_rels.iter().find(|rel| rel.id == rel_id) else {
+            continue;
+        };
+        let part = template::resolve_target(&generated_presentation, &rel.target);
+        let name = import_part(
+            &mut deck,
+            &generated,
+            &part,
+            notes_master.as_deref(),
+            &mut imported,
+        )?;
+        let rel_id = free_relationship_id(&presentation_rels);
+        presentation_rels.push(Relationship {
+            id: rel_id.clone(),
+            rel_type: rel.rel_type.clone(),
+            target: relative_target(&presentation, &name),
+            external: false,
+        });
+        new_slides.push((next_id, rel_id));
+        next_id += 1;
+    }
+    info!(
+        "Added {} slides at position {}",
+        new_slides.len(),
+        index + 1
+    );
+
+    // Slides with notes bring a notes master along if the deck had none
+    let new_notes_master = match notes_master {
+        Some(_) => None,
+        None => imported.iter().find_map(|(from, to)| {
+            generated_types
+                .content_type(from)?
+                .ends_with("notesMaster+xml")
+                .then(|| to.clone())
+        }),
+    };
+    if let Some(master) = &new_notes_master {
+        let rel_id = free_relationship_id(&presentation_rels);
+        presentation_rels.push(Relationship {
+            id: rel_id.clone(),
+            rel_type:
+                "http://schemas.openxmlformats.org/officeDocument/2006/relationships/notesMaster"
+                    .to_string(),
+            target: relative_target(&presentation, master),
+            external: false,
+        });
+        let list = format!(
+            r#"<p:notesMasterIdLst><p:notesMasterId r:id="{}"/></p:notesMasterIdLst>"#,
+            rel_id
+        );
+        presentation_xml = insert_after(&presentation_xml, &["</p:sldMasterIdLst>"], &list);
+    }
+    deck.set_relationships(&presentation, &presentation_rels);
+
+    // New slides join the section of the slide before them, or replace a section's slides
+    let new_ids: Vec<u32> = new_slides.iter().map(|(id, _)| *id).collect();
+    match &merge.placement {
+        Placement::Section(name) => {
+            if let Some(section) = sections
+                .iter_mut()
+                .find(|section| section.name.eq_ignore_ascii_case(name.trim()))
+            {
+                section.ids = new_ids;
+            }
+        }
+        _ => {
+            let previous = index.checked_sub(1).map(|i| kept[i].0);
+            let section = match previous {
+                Some(previous) => sections.iter_mut().find(|s| s.ids.contains(&previous)),
+                None => sections.first_mut(),
+            };
+            if let Some(section) = section {
+                let at = previous
+                    .and_then(|previous| section.ids.iter().position(|id| *id == previous))
+                    .map_or(0, |i| i + 1);
+                section.ids.splice(at..at, new_ids);
+            }
+        }
+    }
+    presentation_xml = write_sections(&presentation_xml, &sections);
+
+    let slides: Vec<(u32, String)> = kept[..index]
+        .iter()
+        .cloned()
+        .chain(new_slides)
+        .chain(kept[index..].iter().cloned())
+        .collect();
+    let slide_list = slides
+        .iter()
+        .map(|(id, rel_id)| format!(r#"<p:sldId id="{}" r:id="{}"/>"#, id, rel_id))
+        .collect::<String>();
+    presentation_xml = write_slide_list(&presentation_xml, &slide_list);
+    deck.parts
+        .insert(presentation.clone(), presentation_xml.into_bytes());
+
+    // Declare the content types of the new parts and forget the removed ones
+    let mut content_types = deck.content_types()?;
+    content_types
+        .overrides
+        .retain(|part, _| !removed.contains(part));
+    for (from, to) in &imported {
+        content_types.add(to, &generated_types, from);
+    }
+    deck.parts.insert(
+        CONTENT_TYPES.to_string(),
+        content_types.to_xml().into_bytes(),
+    );
+
+    // Keep the counts in the document properties in step
+    let slide_parts: Vec<String> = slides
+        .iter()
+        .filter_map(|(_, rel_id)| presentation_rels.iter().find(|rel| &rel.id == rel_id))
+        .map(|rel| template::resolve_target(&presentation, &rel.target))
+        .collect();
+    if let Some(app) = deck.root_part("extended-properties")? {
+        let mut notes = 0;
+        let mut hidden = 0;
+        let mut titles = Vec::with_capacity(slide_parts.len());
+        for part in &slide_parts {
+            if deck
+                .relationships(part)?
+                .iter()
+                .any(|rel| template::rel_kind(&rel.rel_type) == "notesSlide")
+            {
+                notes += 1;
+            }
+            let xml = deck.xml(part)?;
+            if template::elements(&xml, "p:sld")?
+                .first()
+                .is_some_and(|attrs| attrs.get("show").is_some_and(|show| show == "0"))
+            {
+                hidden += 1;
+            }
+            titles.push(slide_title(&xml)?.unwrap_or_else(|| UNTITLED_SLIDE.to_string()));
+        }
+        let mut xml = deck.xml(&app)?;
+        xml = set_element_text(&xml, "Slides", slide_parts.len());
+        xml = set_element_text(&xml, "Notes", notes);
+        xml = set_element_text(&xml, "HiddenSlides", hidden);
+        xml = set_slide_titles(&xml, &titles);
+        deck.parts.insert(app, xml.into_bytes());
+    }
+
+    deck.save(output_file)?;
+    info!(
+        "Deck with {} slides written to {:?}",
+        slide_parts.len(),
+        output_file
+    );
+    Ok(())
+}
+
+/// Copy `part` of the generated package and everything it refers to into the deck, under
+/// names the deck doesn't use yet. Layouts are the deck's own already, and notes use the
+/// deck's notes master if it has one. Returns the part's name in the deck.
+fn import_part(
+    deck: &mut Package,
+    generated: &Package,
+    part: &str,
+    notes_master: Option<&str>,
+    imported: &mut HashMap<String, String>,
+) -> Result<String> {
+    if let Some(name) = imported.get(part) {
+        return Ok(name.clone());
+    }
+    let data = generated
+        .parts
+        .get(part)
+        .cloned()
+        .ok_or_else(|| BigError::PptxError(format!("Generated slides are missing {}", part)))?;
+    let name = deck.free_name(part);
+    imported.insert(part.to_string(), name.clone());
+    deck.parts.insert(name.clone(), data);
+
+    let mut relationships = generated.relationships(part)?;
+    for rel in relationships.iter_mut().filter(|rel| !rel.external) {
+        let target = template::resolve_target(part, &rel.target);
+        let target = match (template::rel_kind(&rel.rel_type), notes_master) {
+            ("slideLayout", _) => {
+                if !deck.parts.contains_key(&target) {
+                    return Err(BigError::PptxError(format!(
+                        "The deck has no layout {} for the new slides",
+                        target
+                    )));
+                }
+                target
+            }
+            ("notesMaster", Some(master)) => master.to_string(),
+            _ => import_part(deck, generated, &target, notes_master, imported)?,
+        };
+        rel.target = relative_target(&name, &target);
+    }
+    if !relationships.is_empty() {
+        deck.set_relationships(&name, &relationships);
+    }
+    Ok(name)
+}
+
+/// The parts of a package, by name without the leading slash
+struct Package {
+    parts: BTreeMap<String, Vec<u8>>,
+}
+
+impl Package {
+    fn open(path: &Path) -> Result<Self> {
+        let invalid = |message: String| {
+            BigError::PptxError(format!("Invalid PPTX {}: {}", path.display(), message))
+        };
+        let file = fs::File::open(path).map_err(BigError::FileReadError)?;
+        let mut archive = ZipArchive::new(file).map_err(|e| invalid(e.to_string()))?;
+        let mut parts = BTreeMap::new();
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(|e| invalid(e.to_string()))?;
+            if entry.is_dir() {
+                continue;
+            }
+            let mut data = Vec::new();
+            entry
+                .read_to_end(&mut data)
+                .map_err(BigError::FileReadError)?;
+            parts.insert(entry.name().to_string(), data);
+        }
+        Ok(Self { parts })
+    }
+
+    fn xml(&self, part: &str) -> Result<String> {
+        self.parts
+            .get(part)
+            .map(|data| String::from_utf8_lossy(data).into_owned())
+            .ok_or_else(|| BigError::PptxError(format!("The PPTX is missing {}", part)))
+    }
+
+    /// The relationships of `part`, none if it has no relationships part
+    fn relationships(&self, part: &str) -> Result<Vec<Relationship>> {
+        match self.parts.get(&template::rels_part(part)) {
+            Some(data) => template::relationships(&String::from_utf8_lossy(data)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn set_relationships(&mut self, part: &str, relationships: &[Relationship]) {
+        self.parts.insert(
+            template::rels_part(part),
+            template::relationships_xml(relationships).into_bytes(),
+        );
+    }
+
+    /// Parts the package itself refers to
+    fn root_targets(&self) -> Result<Vec<String>> {
+        Ok(self
+            .relationships("")?
+            .into_iter()
+            .filter(|rel| !rel.external)
+            .map(|rel| template::resolve_target("", &rel.target))
+            .collect())
+    }
+
+    /// The part the package refers to with a relationship of `kind`
+    fn root_part(&self, kind: &str) -> Result<Option<String>> {
+        Ok(self
+            .relationships("")?
+            .into_iter()
+            .find(|rel| template::rel_kind(&rel.rel_type) == kind)
+            .map(|rel| template::resolve_target("", &rel.target)))
+    }
+
+    /// The presentation part
+    fn main_part(&self) -> Result<String> {
+        self.root_part("officeDocument")?
+            .ok_or_else(|| BigError::PptxError("The PPTX has no presentation part".to_string()))
+    }
+
+    /// Every part that can be reached from `starts` by following relationships.
+    /// Slides are only followed from `presentation`, so a link from one slide to
+    /// another doesn't keep the other alive.
+    fn reachable(
+        &self,
+        starts: impl IntoIterator<Item = String>,
+        presentation: &str,
+    ) -> BTreeSet<String> {
+        let mut found = BTreeSet::new();
+        let mut queue: Vec<String> = starts.into_iter().collect();
+        while let Some(part) = queue.pop() {
+            if !self.parts.contains_key(&part) || !found.insert(part.clone()) {
+                continue;
+            }
+            for rel in self.relationships(&part).unwrap_or_default() {
+                let is_slide = template::rel_kind(&rel.rel_type) == "slide";
+                if rel.external || (is_slide && part != presentation) {
+                    continue;
+                }
+                queue.push(template::resolve_target(&part, &rel.target));
+            }
+        }
+        found
+    }
+
+    /// Remove a part and its relationships
+    fn remove(&mut self, part: &str) {
+        self.parts.remove(part);
+        self.parts.remove(&template::rels_part(part));
+    }
+
+    /// `name` with the lowest number that no part has yet, such as `ppt/slides/slide4.xml`
+    fn free_name(&self, name: &str) -> String {
+        let file_start = name.rfind('/').map_or(0, |i| i + 1);
+        let (stem, ext) = match name[file_start..].rfind('.') {
+            Some(dot) => name.split_at(file_start + dot),
+            None => (name, ""),
+        };
+        let prefix = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+        (1..)
+            .map(|n| format!("{}{}{}", prefix, n, ext))
+            .find(|candidate| {
+                !self.parts.contains_key(candidate)
+                    && !self.parts.contains_key(&template::rels_part(candidate))
+            })
+            .unwrap_or_default()
+    }
+
+    fn content_types(&self) -> Result<ContentTypes> {
+        ContentTypes::parse(&self.xml(CONTENT_TYPES)?)
+    }
+
+    /// Write the package, with the content types first as readers expect
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(BigError::FileReadError)?;
+        }
+        let file = fs::File::create(path).map_err(BigError::FileReadError)?;
+        let mut zip = ZipWriter::new(file);
+        let ordered = self
+            .parts
+            .iter()
+            .filter(|(name, _)| name.as_str() == CONTENT_TYPES)
+            .chain(
+                self.parts
+                    .iter()
+                    .filter(|(name, _)| name.as_str() != CONTENT_TYPES),
+            );
+        for (name, data) in ordered {
+            zip.start_file(name.as_str(), FileOptions::default())?;
+            zip.write_all(data)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+}
+
+/// The `[Content_Types].xml` of a package
+struct ContentTypes {
+    /// Content types by file extension
+    defaults: Vec<(String, String)>,
+    /// Content types of single parts, by name without the leading slash
+    overrides: BTreeMap<String, String>,
+}
+
+impl ContentTypes {
+    fn parse(xml: &str) -> Result<Self> {
+        let defaults = template::elements(xml, "Default")?
+            .into_iter()
+            .filter_map(|attrs| {
+                Some((
+                    attrs.get("Extension")?.clone(),
+                    attrs.get("ContentType")?.clone(),
+                ))
+            })
+            .collect();
+        let overrides = template::elements(xml, "Override")?
+            .into_iter()
+            .filter_map(|attrs| {
+                Some((
+                    attrs.get("PartName")?.trim_start_matches('/').to_string(),
+                    attrs.get("ContentType")?.clone(),
+                ))
+            })
+            .collect();
+        Ok(Self {
+            defaults,
+            overrides,
+        })
+    }
+
+    fn default_for(&self, part: &str) -> Option<&str> {
+        let ext = part.rsplit_once('.')?.1;
+        self.defaults
+            .iter()
+            .find(|(extension, _)| extension.eq_ignore_ascii_case(ext))
+            .map(|(_, content_type)| content_type.as_str())
+    }
+
+    fn content_type(&self, part: &str) -> Option<&str> {
+        self.overrides
+            .get(part)
+            .map(String::as_str)
+            .or_else(|| self.default_for(part))
+    }
+
+    /// Declare `part` with the content type `source` gives `source_part`
+    fn add(&mut self, part: &str, source: &ContentTypes, source_part: &str) {
+        if let Some(content_type) = source.overrides.get(source_part) {
+            self.overrides
+                .insert(part.to_string(), content_type.clone());
+        } else if let (Some(content_type), None) =
+            (source.default_for(source_part), self.default_for(part))
+        {
+            let ext = part.rsplit_once('.').map_or("", |(_, ext)| ext);
+            self.defaults
+                .push((ext.to_lowercase(), content_type.to_string()));
+        }
+    }
+
+    fn to_xml(&self) -> String {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+"#,
+        );
+        for (ext, content_type) in &self.defaults {
+            xml.push_str(&format!(
+                "    <Default Extension=\"{}\" ContentType=\"{}\"/>\n",
+                escape(ext),
+                escape(content_type)
+            ));
+        }
+        for (part, content_type) in &self.overrides {
+            xml.push_str(&format!(
+                "    <Override PartName=\"/{}\" ContentType=\"{}\"/>\n",
+                escape(part),
+                escape(content_type)
+            ));
+        }
+        xml.push_str("</Types>");
+        xml
+    }
+}
+
+/// A section of slides, as PowerPoint groups them in the slide sorter
+#[derive(Debug, Clone, PartialEq)]
+struct Section {
+    name: String,
+    id: String,
+    ids: Vec<u32>,
+}
+
+/// The ids and relationship ids of the slides in a presentation, in order
+fn slide_ids(presentation_xml: &str) -> Result<Vec<(u32, String)>> {
+    Ok(template::elements(presentation_xml, "p:sldId")?
+        .into_iter()
+        .filter_map(|attrs| Some((attrs.get("id")?.parse().ok()?, attrs.get("r:id")?.clone())))
+        .collect())
+}
+
+/// The sections of a presentation, in order
+fn sections(presentation_xml: &str) -> Result<Vec<Section>> {
+    let mut found = Vec::new();
+    for (start, end) in section_spans(presentation_xml) {
+        let xml = &presentation_xml[start..end];
+        let Some(attrs) = template::elements(xml, "p14:section")?.into_iter().next() else {
+            continue;
+        };
+        found.push(Section {
+            name: attrs.get("name").cloned().unwrap_or_default(),
+            id: attrs.get("id").cloned().unwrap_or_default(),
+            ids: template::elements(xml, "p14:sldId")?
+                .into_iter()
+                .filter_map(|attrs| attrs.get("id")?.parse().ok())
+                .collect(),
+        });
+    }
+    Ok(found)
+}
+
+/// Byte ranges of the `<p14:section>` elements in a presentation
+fn section_spans(xml: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = xml[offset..].find("<p14:section ").map(|i| i + offset) {
+        let Some(tag_end) = xml[start..].find('>').map(|i| start + i + 1) else {
+            break;
+        };
+        let end = if xml[..tag_end].ends_with("/>") {
+            tag_end
+        } else {
+            match xml[start..].find("</p14:section>") {
+                Some(i) => start + i + "</p14:section>".len(),
+                None => break,
+            }
+        };
+        spans.push((start, end));
+        offset = end;
+    }
+    spans
+}
+
+/// Replace the sections of a presentation with `sections`, which are the same ones
+/// with different slides
+fn write_sections(xml: &str, sections: &[Section]) -> String {
+    let mut xml = xml.to_string();
+    let spans = section_spans(&xml);
+    for ((start, end), section) in spans.into_iter().zip(sections).rev() {
+        let ids: String = section
+            .ids
+            .iter()
+            .map(|id| format!(r#"<p14:sldId id="{}"/>"#, id))
+            .collect();
+        let element = format!(
+            r#"<p14:section name="{}" id="{}"><p14:sldIdLst>{}</p14:sldIdLst></p14:section>"#,
+            escape(&section.name),
+            escape(&section.id),
+            ids
+        );
+        xml.replace_range(start..end, &element);
+    }
+    xml
+}
+
+/// Replace the presentation's `<p:sldIdLst>` with one holding `slide_list`
+fn write_slide_list(xml: &str, slide_list: &str) -> String {
+    let list = format!("<p:sldIdLst>{}</p:sldIdLst>", slide_list);
+    if let Some(start) = xml.find("<p:sldIdLst") {
+        let end = match xml[start..].find("</p:sldIdLst>") {
+            Some(i) => start + i + "</p:sldIdLst>".len(),
+            None => start + xml[start..].find("/>").map_or(0, |i| i + 2),
+        };
+        let mut xml = xml.to_string();
+        xml.replace_range(start..end, &list);
+        return xml;
+    }
+    insert_after(
+        xml,
+        &[
+            "</p:handoutMasterIdLst>",
+            "</p:notesMasterIdLst>",
+            "</p:sldMasterIdLst>",
+        ],
+        &list,
+    )
+}
+
+/// Insert `element` after the first of `anchors` found in `xml`
+fn insert_after(xml: &str, anchors: &[&str], element: &str) -> String {
+    let mut xml = xml.to_string();
+    if let Some(end) = anchors
+        .iter()
+        .find_map(|anchor| xml.find(anchor).map(|i| i + anchor.len()))
+    {
+        xml.insert_str(end, element);
+    }
+    xml
+}
+
+/// Set the text of the element called `name`, if there is one
+fn set_element_text(xml: &str, name: &str, value: usize) -> String {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+    let mut xml = xml.to_string();
+    if let Some(start) = xml.find(&open).map(|i| i + open.len()) {
+        if let Some(end) = xml[start..].find(&close).map(|i| start + i) {
+            xml.replace_range(start..end, &value.to_string());
+        }
+    }
+    xml
+}
+
+/// The text of a slide's title placeholder, if it has one with text
+fn slide_title(slide_xml: &str) -> Result<Option<String>> {
+    let mut reader = Reader::from_str(slide_xml);
+    let mut is_title = false;
+    let mut in_text = false;
+    let mut paragraphs: Vec<String> = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) if element.name().as_ref() == b"p:sp" => {
+                is_title = false;
+                paragraphs.clear();
+            }
+            Ok(Event::Start(element)) | Ok(Event::Empty(element))
+                if element.name().as_ref() == b"p:ph" =>
+            {
+                is_title = element
+                    .try_get_attribute("type")
+                    .ok()
+                    .flatten()
+                    .is_some_and(|kind| matches!(kind.value.as_ref(), b"title" | b"ctrTitle"));
+            }
+            Ok(Event::Start(element)) if element.name().as_ref() == b"a:p" => {
+                paragraphs.push(String::new());
+            }
+            Ok(Event::Start(element)) if element.name().as_ref() == b"a:t" => in_text = true,
+            Ok(Event::End(element)) if element.name().as_ref() == b"a:t" => in_text = false,
+            Ok(Event::Text(text)) if in_text && is_title => {
+                if let (Some(paragraph), Ok(text)) = (paragraphs.last_mut(), text.unescape()) {
+                    paragraph.push_str(&text);
+                }
+            }
+            Ok(Event::End(element)) if element.name().as_ref() == b"p:sp" && is_title => {
+                let title = paragraphs
+                    .iter()
+                    .map(|paragraph| paragraph.trim())
+                    .filter(|paragraph| !paragraph.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                return Ok((!title.is_empty()).then_some(title));
+            }
+            Ok(Event::Eof) => return Ok(None),
+            Err(e) => {
+                return Err(BigError::PptxError(format!(
+                    "Failed to read slide XML: {}",
+                    e
+                )))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Byte ranges of the elements called `name` inside the first `parent` element
+fn child_spans(xml: &str, parent: &str, name: &str) -> Vec<(usize, usize)> {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+    let Some(start) = xml.find(&format!("<{}>", parent)) else {
+        return Vec::new();
+    };
+    let end = xml[start..]
+        .find(&format!("</{}>", parent))
+        .map_or(xml.len(), |i| start + i);
+
+    let mut spans = Vec::new();
+    let mut offset = start;
+    while let Some(from) = xml[offset..end].find(&open).map(|i| offset + i) {
+        let Some(to) = xml[from..end].find(&close).map(|i| from + i + close.len()) else {
+            break;
+        };
+        spans.push((from, to));
+        offset = to;
+    }
+    spans
+}
+
+/// The text of the element spanning `span`, between its tags
+fn span_text(xml: &str, (start, end): (usize, usize)) -> &str {
+    let inner = &xml[start..end];
+    let from = inner.find('>').map_or(0, |i| i + 1);
+    let to = inner.rfind("</").unwrap_or(inner.len());
+    &inner[from..to]
+}
+
+/// List `titles` as the slide titles in the document properties, which pair each kind of
+/// part, such as fonts or slide titles, with how many of the parts' names follow
+fn set_slide_titles(app_xml: &str, titles: &[String]) -> String {
+    let names = child_spans(app_xml, "HeadingPairs", "vt:lpstr");
+    let counts = child_spans(app_xml, "HeadingPairs", "vt:i4");
+    let Some(heading) = names
+        .iter()
+        .position(|&span| span_text(app_xml, span) == SLIDE_TITLES_HEADING)
+    else {
+        return app_xml.to_string();
+    };
+    let Some(&count_span) = counts.get(heading) else {
+        return app_xml.to_string();
+    };
+    let count = |span| {
+        span_text(app_xml, span)
+            .trim()
+            .parse::<usize>()
+            .unwrap_or(0)
+    };
+    let first = counts[..heading]
+        .iter()
+        .map(|&span| count(span))
+        .sum::<usize>();
+    let old = count(count_span);
+
+    // The names follow the heading pairs, so replacing them first keeps `count_span` valid
+    let parts = child_spans(app_xml, "TitlesOfParts", "vt:lpstr");
+    let removed = if first + old <= parts.len() { old } else { 0 };
+    let listed: String = titles
+        .iter()
+        .map(|title| format!("<vt:lpstr>{}</vt:lpstr>", escape(title)))
+        .collect();
+    let replaced = match parts.get(first) {
+        Some(&(start, _)) if removed > 0 => Some((start, parts[first + removed - 1].1)),
+        Some(&(start, _)) => Some((start, start)),
+        None => parts
+            .last()
+            .map(|&(_, end)| end)
+            .or_else(|| {
+                let start = app_xml.find("<TitlesOfParts>")?;
+                app_xml[start..].find("</vt:vector>").map(|i| start + i)
+            })
+            .map(|end| (end, end)),
+    };
+    let mut xml = app_xml.to_string();
+    if let Some((start, end)) = replaced {
+        xml.replace_range(start..end, &listed);
+    }
+    let (start, end) = count_span;
+    xml.replace_range(start..end, &format!("<vt:i4>{}</vt:i4>", titles.len()));
+
+    // The vector of part names says how many it holds
+    let total = parts.len() - removed + titles.len();
+    if let Some(start) = xml.find("<TitlesOfParts>") {
+        if let Some(size) = xml[start..]
+            .find(" size=\"")
+            .map(|i| start + i + " size=\"".len())
+        {
+            if let Some(end) = xml[size..].find('"').map(|i| size + i) {
+                xml.replace_range(size..end, &total.to_string());
+            }
+        }
+    }
+    xml
+}
+
+/// The lowest `rIdN` none of `relationships` uses
+fn free_relationship_id(relationships: &[Relationship]) -> String {
+    (1..)
+        .map(|n| format!("rId{}", n))
+        .find(|id| !relationships.iter().any(|rel| &rel.id == id))
+        .unwrap_or_default()
+}
+
+/// Target of a relationship from `from` to `to`, relative to the directory of `from`
+fn relative_target(from: &str, to: &str) -> String {
+    let from_dirs: Vec<&str> = from.split('/').collect();
+    let from_dirs = &from_dirs[..from_dirs.len() - 1];
+    let to_segments: Vec<&str> = to.split('/').collect();
+    let common = from_dirs
+        .iter()
+        .zip(&to_segments)
+        .take_while(|(a, b)| a == b)
+        .count()
+        .min(to_segments.len() - 1);
+    let mut target = "../".repeat(from_dirs.len() - common);
+    target.push_str(&to_segments[common..].join("/"));
+    target
+}
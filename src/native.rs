@@ -0,0 +1,670 @@
+// ABOUTME: Native slide builder for the big-slides application
+// ABOUTME: Converts slide markdown into editable DrawingML text and pictures for PPTX
+
+use crate::pptx::{
+    picture_xml, title_placeholder_xml, MediaFile, SlideBody, REL_HYPERLINK, REL_IMAGE,
+};
+use crate::resources::ResourceFile;
+use crate::template::Relationship;
+use comrak::nodes::{AstNode, ListType, NodeValue};
+use comrak::{parse_document, Arena, ComrakOptions};
+use image::{ImageFormat, ImageOutputFormat};
+use log::warn;
+use quick_xml::escape::escape;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Font sizes in hundredths of a point
+const TITLE_SIZE: u32 = 4400;
+const HEADING_SIZE: u32 = 3600;
+const BODY_SIZE: u32 = 2800;
+const CODE_SIZE: u32 = 2000;
+
+/// Indent per list level in EMU (half an inch)
+const LIST_INDENT: u64 = 457200;
+
+/// A run of text sharing one set of character properties
+#[derive(Debug, Clone, Default)]
+struct Run {
+    text: String,
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    code: bool,
+    link: Option<String>,
+    line_break: bool,
+}
+
+/// How a paragraph is laid out on the slide
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParagraphKind {
+    Heading(u8),
+    Body,
+    Bullet(usize),
+    Numbered(usize),
+    Code,
+}
+
+/// A paragraph of runs
+#[derive(Debug, Clone)]
+struct Paragraph {
+    kind: ParagraphKind,
+    runs: Vec<Run>,
+}
+
+/// An image referenced from the slide markdown
+#[derive(Debug, Clone)]
+struct ImageRef {
+    url: String,
+    alt: String,
+}
+
+/// Image data ready to be stored in the package
+struct LoadedImage {
+    data: Vec<u8>,
+    ext: &'static str,
+    width: u32,
+    height: u32,
+}
+
+/// Everything on a slide, gathered from its markdown
+#[derive(Debug, Default)]
+struct SlideContent {
+    title: Vec<Run>,
+    paragraphs: Vec<Paragraph>,
+    images: Vec<ImageRef>,
+}
+
+/// The text of a slide, for alt text and the outline
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct SlideText {
+    /// Text of the slide's title heading, if it has one
+    pub title: Option<String>,
+
+    /// Everything else on the slide, one paragraph or image description per line
+    pub body: String,
+}
+
+impl SlideText {
+    /// Title for the slide: its heading, else its first line, else its number
+    pub fn title_or_first_line(&self, slide_num: usize) -> String {
+        self.title
+            .clone()
+            .or_else(|| self.body.lines().next().map(str::to_string))
+            .unwrap_or_else(|| format!("Slide {}", slide_num))
+    }
+
+    /// Description of the whole slide, title first
+    pub fn description(&self) -> String {
+        match &self.title {
+            Some(title) if self.body.is_empty() => title.clone(),
+            Some(title) => format!("{}\n{}", title, self.body),
+            None => self.body.clone(),
+        }
+    }
+}
+
+/// Extract the title and plain text of a slide from its markdown
+pub(crate) fn slide_text(markdown: &str) -> SlideText {
+    let content = collect_content(markdown);
+    let title = runs_text(&content.title);
+    let lines: Vec<String> = content
+        .paragraphs
+        .iter()
+        .map(|paragraph| runs_text(&paragraph.runs))
+        .chain(
+            content
+                .images
+                .iter()
+                .map(|image| image.alt.trim().to_string()),
+        )
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    SlideText {
+        title: (!title.is_empty()).then_some(title),
+        body: lines.join("\n"),
+    }
+}
+
+/// Plain text of some runs on one line, with whitespace collapsed
+fn runs_text(runs: &[Run]) -> String {
+    let text: String = runs
+        .iter()
+        .map(|run| if run.line_break { " " } else { &run.text })
+        .collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Build an editable slide body from the markdown of a single slide.
+/// Relative image paths are resolved against `base_dir`.
+pub(crate) fn build_slide_body(
+    markdown: &str,
+    base_dir: &Path,
+    slide_num: usize,
+    (cx, cy): (u64, u64),
+) -> SlideBody {
+    let content = collect_content(markdown);
+
+    let mut shapes = Vec::new();
+    let mut relationships = Vec::new();
+    let mut media = Vec::new();
+    let mut next_id = 2;
+
+    // Lay shapes out top to bottom inside a margin
+    let margin = cx / 20;
+    let width = cx - 2 * margin;
+    let bottom = cy - margin;
+    let mut top = margin;
+    let has_body = !content.paragraphs.is_empty();
+    let has_images = !content.images.is_empty();
+
+    if content.title.is_empty() {
+        // Every slide needs a title for screen readers and the outline, if only a hidden one
+        let text = slide_text(markdown);
+        shapes.push(title_placeholder_xml(
+            next_id,
+            &text.title_or_first_line(slide_num),
+            (margin, margin, width, (bottom - margin) / 5),
+        ));
+        next_id += 1;
+    } else {
+        // A slide with only a heading gets the whole slide, the way big shows it
+        let height = if has_body || has_images {
+            (bottom - top) / 5
+        } else {
+            bottom - top
+        };
+        let title = Paragraph {
+            kind: ParagraphKind::Heading(1),
+            runs: content.title.clone(),
+        };
+        let paragraphs = paragraph_xml(&title, &mut relationships);
+        shapes.push(text_shape_xml(
+            next_id,
+            "Title",
+            &paragraphs,
+            (margin, top, width, height),
+            "ctr",
+            true,
+        ));
+        next_id += 1;
+        top += height;
+    }
+
+    if has_body {
+        let height = if has_images {
+            (bottom - top) / 2
+        } else {
+            bottom - top
+        };
+        let paragraphs = content
+            .paragraphs
+            .iter()
+            .map(|paragraph| paragraph_xml(paragraph, &mut relationships))
+            .collect::<Vec<String>>()
+            .join("\n");
+        shapes.push(text_shape_xml(
+            next_id,
+            "Content",
+            &paragraphs,
+            (margin, top, width, height),
+            "t",
+            false,
+        ));
+        next_id += 1;
+        top += height;
+    }
+
+    if has_images {
+        // Load what we can, leaving out images that can't be read
+        let loaded: Vec<(&ImageRef, LoadedImage)> = content
+            .images
+            .iter()
+            .filter_map(|image_ref| {
+                load_image(&image_ref.url, base_dir).map(|image| (image_ref, image))
+            })
+            .collect();
+
+        // Place images side by side in the remaining space
+        let count = loaded.len() as u64;
+        let gap = margin / 2;
+        let area_height = bottom.saturating_sub(top);
+        let slot_width = width.saturating_sub(gap * count.saturating_sub(1)) / count.max(1);
+        for (i, (image_ref, image)) in loaded.into_iter().enumerate() {
+            let (pic_width, pic_height) =
+                fit_within(image.width, image.height, slot_width, area_height);
+            let slot_x = margin + i as u64 * (slot_width + gap);
+            let x = slot_x + (slot_width - pic_width) / 2;
+            let y = top + (area_height - pic_height) / 2;
+
+            let name = format!("image{}_{}.{}", slide_num, i + 1, image.ext);
+            relationships.push(Relationship::internal(
+                relationships.len() + 1,
+                REL_IMAGE,
+                format!("../media/{}", name),
+            ));
+            shapes.push(picture_xml(
+                next_id,
+                &format!("Picture {}", i + 1),
+                &image_ref.alt,
+                relationships.len(),
+                (x, y, pic_width, pic_height),
+            ));
+            media.push(MediaFile {
+                name,
+                data: image.data,
+            });
+            next_id += 1;
+        }
+    }
+
+    SlideBody {
+        shapes: shapes.join("\n"),
+        relationships,
+        media,
+    }
+}
+
+/// Parse slide markdown into title, paragraphs and images
+fn collect_content(markdown: &str) -> SlideContent {
+    let arena = Arena::new();
+    let mut options = ComrakOptions::default();
+    options.extension.strikethrough = true;
+    options.extension.table = true;
+    let root = parse_document(&arena, markdown, &options);
+
+    let mut content = SlideContent::default();
+    for node in root.children() {
+        collect_block(node, &mut content, ParagraphKind::Body);
+    }
+    content
+}
+
+/// Collect a block node, laying its paragraphs out as `kind`
+fn collect_block<'a>(node: &'a AstNode<'a>, content: &mut SlideContent, kind: ParagraphKind) {
+    let ast = node.data.borrow();
+    match &ast.value {
+        NodeValue::Heading(heading) => {
+            let runs = collect_runs(node, content);
+            // The first top-level heading is the slide title
+            if heading.level == 1 && content.title.is_empty() && content.paragraphs.is_empty() {
+                content.title = runs;
+            } else {
+                push_paragraph(content, ParagraphKind::Heading(heading.level), runs);
+            }
+        }
+        NodeValue::Paragraph => {
+            let runs = collect_runs(node, content);
+            push_paragraph(content, kind, runs);
+        }
+        NodeValue::List(list) => {
+            let level = match kind {
+                ParagraphKind::Bullet(level) | ParagraphKind::Numbered(level) => level + 1,
+                _ => 0,
+            };
+            let item_kind = match list.list_type {
+                ListType::Bullet => ParagraphKind::Bullet(level),
+                ListType::Ordered => ParagraphKind::Numbered(level),
+            };
+            for item in node.children() {
+                for child in item.children() {
+                    collect_block(child, content, item_kind);
+                }
+            }
+        }
+        NodeValue::CodeBlock(code) => {
+            for line in code.literal.trim_end_matches('\n').lines() {
+                content.paragraphs.push(Paragraph {
+                    kind: ParagraphKind::Code,
+                    runs: vec![Run {
+                        text: line.to_string(),
+                        code: true,
+                        ..Default::default()
+                    }],
+                });
+            }
+        }
+        NodeValue::BlockQuote => {
+            for child in node.children() {
+                collect_block(child, content, kind);
+            }
+        }
+        NodeValue::Table(_) => {
+            // Tables become one line per row, with cells separated by bars
+            for row in node.children() {
+                let mut runs = Vec::new();
+                for (i, cell) in row.children().enumerate() {
+                    if i > 0 {
+                        runs.push(Run {
+                            text: " | ".to_string(),
+                            ..Default::default()
+                        });
+                    }
+                    runs.extend(collect_runs(cell, content));
+                }
+                push_paragraph(content, kind, runs);
+            }
+        }
+        // Raw HTML, rules and other blocks have no text to carry over
+        _ => {}
+    }
+}
+
+/// Add a paragraph unless it has no visible text
+fn push_paragraph(content: &mut SlideContent, kind: ParagraphKind, runs: Vec<Run>) {
+    if runs
+        .iter()
+        .any(|run| run.line_break || !run.text.trim().is_empty())
+    {
+        content.paragraphs.push(Paragraph { kind, runs });
+    }
+}
+
+/// Collect the inline children of a block into runs, setting images aside
+fn collect_runs<'a>(node: &'a AstNode<'a>, content: &mut SlideContent) -> Vec<Run> {
+    let mut runs = Vec::new();
+    for child in node.children() {
+        collect_inline(child, &Run::default(), &mut runs, &mut content.images);
+    }
+    runs
+}
+
+/// Collect an inline node with the character style inherited from its parents
+fn collect_inline<'a>(
+    node: &'a AstNode<'a>,
+    style: &Run,
+    runs: &mut Vec<Run>,
+    images: &mut Vec<ImageRef>,
+) {
+    let ast = node.data.borrow();
+    let child_style = match &ast.value {
+        NodeValue::Text(text) => {
+            runs.push(Run {
+                text: text.clone(),
+                ..style.clone()
+            });
+            return;
+        }
+        NodeValue::Code(code) => {
+            runs.push(Run {
+                text: code.literal.clone(),
+                code: true,
+                ..style.clone()
+            });
+            return;
+        }
+        NodeValue::SoftBreak => {
+            runs.push(Run {
+                text: " ".to_string(),
+                ..style.clone()
+            });
+            return;
+        }
+        NodeValue::LineBreak => {
+            runs.push(Run {
+                line_break: true,
+                ..style.clone()
+            });
+            return;
+        }
+        NodeValue::Image(link) => {
+            images.push(ImageRef {
+                url: link.url.clone(),
+                alt: plain_text(node),
+            });
+            return;
+        }
+        NodeValue::Emph => Run {
+            italic: true,
+            ..style.clone()
+        },
+        NodeValue::Strong => Run {
+            bold: true,
+            ..style.clone()
+        },
+        NodeValue::Strikethrough => Run {
+            strike: true,
+            ..style.clone()
+        },
+        NodeValue::Link(link) => Run {
+            link: Some(link.url.clone()),
+            ..style.clone()
+        },
+        _ => style.clone(),
+    };
+
+    for child in node.children() {
+        collect_inline(child, &child_style, runs, images);
+    }
+}
+
+/// Concatenate the text of a node and its descendants
+fn plain_text<'a>(node: &'a AstNode<'a>) -> String {
+    let mut text = String::new();
+    for descendant in node.descendants() {
+        match &descendant.data.borrow().value {
+            NodeValue::Text(t) => text.push_str(t),
+            NodeValue::Code(code) => text.push_str(&code.literal),
+            NodeValue::SoftBreak | NodeValue::LineBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Build the XML for a paragraph, registering hyperlink relationships as needed
+fn paragraph_xml(paragraph: &Paragraph, relationships: &mut Vec<Relationship>) -> String {
+    let (size, bold, properties) = match paragraph.kind {
+        ParagraphKind::Heading(1) => (
+            TITLE_SIZE,
+            true,
+            "<a:pPr algn=\"ctr\"><a:buNone/></a:pPr>".to_string(),
+        ),
+        ParagraphKind::Heading(_) => (HEADING_SIZE, true, "<a:pPr><a:buNone/></a:pPr>".to_string()),
+        ParagraphKind::Body => (BODY_SIZE, false, "<a:pPr><a:buNone/></a:pPr>".to_string()),
+        ParagraphKind::Code => (CODE_SIZE, false, "<a:pPr><a:buNone/></a:pPr>".to_string()),
+        ParagraphKind::Bullet(level) => (
+            BODY_SIZE,
+            false,
+            format!(
+                r#"<a:pPr marL="{}" indent="-{}"><a:buFont typeface="Arial"/><a:buChar char="&#8226;"/></a:pPr>"#,
+                (level as u64 + 1) * LIST_INDENT,
+                LIST_INDENT
+            ),
+        ),
+        ParagraphKind::Numbered(level) => (
+            BODY_SIZE,
+            false,
+            format!(
+                r#"<a:pPr marL="{}" indent="-{}"><a:buAutoNum type="arabicPeriod"/></a:pPr>"#,
+                (level as u64 + 1) * LIST_INDENT,
+                LIST_INDENT
+            ),
+        ),
+    };
+
+    let runs = paragraph
+        .runs
+        .iter()
+        .map(|run| run_xml(run, size, bold, relationships))
+        .collect::<String>();
+
+    format!(
+        "                    <a:p>{}{}<a:endParaRPr lang=\"en-US\" sz=\"{}\" dirty=\"0\"/></a:p>",
+        properties, runs, size
+    )
+}
+
+/// Build the XML for a single run of text or a line break
+fn run_xml(run: &Run, size: u32, bold: bool, relationships: &mut Vec<Relationship>) -> String {
+    let mut attributes = format!(r#"lang="en-US" sz="{}""#, size);
+    if run.bold || bold {
+        attributes.push_str(r#" b="1""#);
+    }
+    if run.italic {
+        attributes.push_str(r#" i="1""#);
+    }
+    if run.strike {
+        attributes.push_str(r#" strike="sngStrike""#);
+    }
+    attributes.push_str(r#" dirty="0""#);
+
+    if run.line_break {
+        return format!("<a:br><a:rPr {}/></a:br>", attributes);
+    }
+
+    let mut children = String::new();
+    if run.code {
+        children.push_str(r#"<a:latin typeface="Courier New"/>"#);
+    }
+    if let Some(url) = &run.link {
+        relationships.push(Relationship::external(
+            relationships.len() + 1,
+            REL_HYPERLINK,
+            url.clone(),
+        ));
+        children.push_str(&format!(
+            r#"<a:hlinkClick r:id="rId{}"/>"#,
+            relationships.len()
+        ));
+    }
+
+    let properties = if children.is_empty() {
+        format!("<a:rPr {}/>", attributes)
+    } else {
+        format!("<a:rPr {}>{}</a:rPr>", attributes, children)
+    };
+    format!("<a:r>{}<a:t>{}</a:t></a:r>", properties, escape(&run.text))
+}
+
+/// Build the XML for a text box shape, or for the slide's title placeholder if `title` is set
+fn text_shape_xml(
+    id: usize,
+    name: &str,
+    paragraphs: &str,
+    (x, y, cx, cy): (u64, u64, u64, u64),
+    anchor: &str,
+    title: bool,
+) -> String {
+    let (shape_properties, properties) = if title {
+        (
+            r#"<p:cNvSpPr><a:spLocks noGrp="1"/></p:cNvSpPr>"#,
+            r#"<p:nvPr><p:ph type="title"/></p:nvPr>"#,
+        )
+    } else {
+        (r#"<p:cNvSpPr txBox="1"/>"#, "<p:nvPr/>")
+    };
+    format!(
+        r#"            <p:sp>
+                <p:nvSpPr>
+                    <p:cNvPr id="{id}" name="{name}"/>
+                    {shape_properties}
+                    {properties}
+                </p:nvSpPr>
+                <p:spPr>
+                    <a:xfrm>
+                        <a:off x="{x}" y="{y}"/>
+                        <a:ext cx="{cx}" cy="{cy}"/>
+                    </a:xfrm>
+                    <a:prstGeom prst="rect">
+                        <a:avLst/>
+                    </a:prstGeom>
+                    <a:noFill/>
+                </p:spPr>
+                <p:txBody>
+                    <a:bodyPr wrap="square" anchor="{anchor}">
+                        <a:normAutofit/>
+                    </a:bodyPr>
+                    <a:lstStyle/>
+{paragraphs}
+                </p:txBody>
+            </p:sp>"#,
+        id = id,
+        name = escape(name),
+        x = x,
+        y = y,
+        cx = cx,
+        cy = cy,
+        anchor = anchor,
+        paragraphs = paragraphs,
+        shape_properties = shape_properties,
+        properties = properties
+    )
+}
+
+/// Scale an image to fit a box, keeping its aspect ratio
+fn fit_within(px_width: u32, px_height: u32, max_cx: u64, max_cy: u64) -> (u64, u64) {
+    if px_width == 0 || px_height == 0 {
+        return (max_cx, max_cy);
+    }
+    let scale = f64::min(
+        max_cx as f64 / px_width as f64,
+        max_cy as f64 / px_height as f64,
+    );
+    (
+        (px_width as f64 * scale) as u64,
+        (px_height as f64 * scale) as u64,
+    )
+}
+
+/// Load an image from a URL or local path.
+/// Formats PowerPoint can't show everywhere are converted to PNG.
+fn load_image(url: &str, base_dir: &Path) -> Option<LoadedImage> {
+    let data = if url.starts_with("http://") || url.starts_with("https://") {
+        // Remote images share the resource cache, so --offline and --refresh apply to them too
+        match ResourceFile::new(url).bytes() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Failed to fetch image {}: {}", url, e);
+                return None;
+            }
+        }
+    } else {
+        let path = Path::new(url.trim_start_matches("file://"));
+        let path = if path.is_relative() {
+            base_dir.join(path)
+        } else {
+            path.to_path_buf()
+        };
+        match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Failed to read image {:?}: {}", path, e);
+                return None;
+            }
+        }
+    };
+
+    let decoded = match image::load_from_memory(&data) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            warn!("Failed to decode image {}: {}", url, e);
+            return None;
+        }
+    };
+    let (width, height) = (decoded.width(), decoded.height());
+    let loaded = |data, ext| LoadedImage {
+        data,
+        ext,
+        width,
+        height,
+    };
+
+    match image::guess_format(&data) {
+        Ok(ImageFormat::Png) => Some(loaded(data, "png")),
+        Ok(ImageFormat::Jpeg) => Some(loaded(data, "jpeg")),
+        Ok(ImageFormat::Gif) => Some(loaded(data, "gif")),
+        _ => {
+            let mut png = Cursor::new(Vec::new());
+            match decoded.write_to(&mut png, ImageOutputFormat::Png) {
+                Ok(()) => Some(loaded(png.into_inner(), "png")),
+                Err(e) => {
+                    warn!("Failed to convert image {} to PNG: {}", url, e);
+                    None
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+// ABOUTME: Overflow and clipping checks for rendered slides
+// ABOUTME: Describes how each slide's content fits the viewport and reports the slides that don't
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::errors::{BigError, Result};
+
+/// Name of the report written next to the slide images
+pub const OVERFLOW_REPORT_FILE: &str = "overflow-report.json";
+
+/// Text smaller than this many CSS pixels is flagged unless configured otherwise
+pub const DEFAULT_MIN_FONT_PX: f64 = 18.0;
+
+/// Measures the slide at `index` after it has been shown. Returns a JSON `SlideLayout`.
+pub(crate) const MEASURE_JS: &str = r#"
+(function (index) {
+  var slide = document.querySelectorAll('body > div')[index];
+  if (!slide) return null;
+
+  var width = window.innerWidth, height = window.innerHeight;
+  var left = 0, top = 0, right = width, bottom = height;
+  var clipped = [], smallest = null;
+
+  function extend(rect) {
+    if (rect.width === 0 && rect.height === 0) return;
+    left = Math.min(left, rect.left);
+    top = Math.min(top, rect.top);
+    right = Math.max(right, rect.right);
+    bottom = Math.max(bottom, rect.bottom);
+  }
+
+  function describe(el) {
+    var name = el.nodeName.toLowerCase();
+    if (el.id) name += '#' + el.id;
+    if (typeof el.className === 'string' && el.className.trim()) {
+      name += '.' + el.className.trim().split(/\s+/).join('.');
+    }
+    return name;
+  }
+
+  var elements = [slide].concat(Array.prototype.slice.call(slide.querySelectorAll('*')));
+  elements.forEach(function (el) {
+    // Skip elements that aren't rendered, including those inside hidden ones
+    var style = window.getComputedStyle(el);
+    if (!el.getClientRects().length || style.visibility === 'hidden') return;
+    extend(el.getBoundingClientRect());
+
+    var cuts = /hidden|clip|scroll|auto/.test(style.overflowX + ' ' + style.overflowY);
+    if (el !== slide && cuts &&
+        (el.scrollWidth > el.clientWidth + 1 || el.scrollHeight > el.clientHeight + 1)) {
+      clipped.push(describe(el));
+    }
+
+    // Text can spill out of its element, so measure the text itself
+    Array.prototype.forEach.call(el.childNodes, function (node) {
+      if (node.nodeType !== 3 || !node.textContent.trim()) return;
+      var range = document.createRange();
+      range.selectNodeContents(node);
+      extend(range.getBoundingClientRect());
+      var size = parseFloat(style.fontSize);
+      if (smallest === null || size < smallest) smallest = size;
+    });
+  });
+
+  return JSON.stringify({
+    overflow: {
+      left: Math.max(0, -left),
+      top: Math.max(0, -top),
+      right: Math.max(0, right - width),
+      bottom: Math.max(0, bottom - height)
+    },
+    clipped: clipped,
+    smallest_font_px: smallest
+  });
+})"#;
+
+/// How far a slide's content reaches past each edge of the viewport, in CSS pixels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Overflow {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+impl Overflow {
+    /// Whether content reaches more than a pixel past any edge
+    pub fn is_visible(&self) -> bool {
+        [self.left, self.top, self.right, self.bottom]
+            .iter()
+            .any(|&extent| extent > 1.0)
+    }
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let edges: Vec<String> = [
+            ("left", self.left),
+            ("top", self.top),
+            ("right", self.right),
+            ("bottom", self.bottom),
+        ]
+        .iter()
+        .filter(|(_, extent)| *extent > 1.0)
+        .map(|(edge, extent)| format!("{:.0}px past the {}", extent, edge))
+        .collect();
+        write!(f, "{}", edges.join(", "))
+    }
+}
+
+/// How a slide's content sits in the viewport, as measured in the browser
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SlideLayout {
+    pub overflow: Overflow,
+
+    /// Elements whose content is cut off by their own box
+    pub clipped: Vec<String>,
+
+    /// Smallest font size of any text on the slide, in CSS pixels
+    pub smallest_font_px: Option<f64>,
+}
+
+/// Something wrong with the way a slide fits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutIssue {
+    /// Content reaches past the edge of the slide
+    Overflow,
+    /// Content is cut off inside an element on the slide
+    Clipped,
+    /// Text is smaller than the minimum font size
+    SmallFont,
+}
+
+impl SlideLayout {
+    /// The issues found on the slide, flagging text smaller than `min_font_px`
+    pub fn issues(&self, min_font_px: f64) -> Vec<LayoutIssue> {
+        let mut issues = Vec::new();
+        if self.overflow.is_visible() {
+            issues.push(LayoutIssue::Overflow);
+        }
+        if !self.clipped.is_empty() {
+            issues.push(LayoutIssue::Clipped);
+        }
+        if self.smallest_font_px.is_some_and(|size| size < min_font_px) {
+            issues.push(LayoutIssue::SmallFont);
+        }
+        issues
+    }
+
+    /// A one-line account of `issues` for warnings
+    pub fn describe(&self, issues: &[LayoutIssue]) -> String {
+        issues
+            .iter()
+            .map(|issue| match issue {
+                LayoutIssue::Overflow => format!("content overflows ({})", self.overflow),
+                LayoutIssue::Clipped => {
+                    format!("content is clipped in {}", self.clipped.join(", "))
+                }
+                LayoutIssue::SmallFont => {
+                    format!("text is {:.1}px", self.smallest_font_px.unwrap_or_default())
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// The measured layout of one rendered slide
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlideReport {
+    /// Slide number, from 1
+    pub slide: usize,
+
+    /// Image the slide was rendered to
+    pub file: String,
+
+    pub issues: Vec<LayoutIssue>,
+
+    #[serde(flatten)]
+    pub layout: SlideLayout,
+}
+
+impl SlideReport {
+    /// Whether the slide has content that spills off or is cut off, rather than only small text
+    pub fn overflows(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| matches!(issue, LayoutIssue::Overflow | LayoutIssue::Clipped))
+    }
+}
+
+/// Layout of every slide a render captured
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OverflowReport {
+    /// Viewport the slides were measured in, in CSS pixels
+    pub width: u32,
+    pub height: u32,
+
+    /// Font size below which text is flagged, in CSS pixels
+    pub min_font_px: f64,
+
+    pub slides: Vec<SlideReport>,
+}
+
+impl OverflowReport {
+    /// Slides whose content overflows or is clipped
+    pub fn overflowing(&self) -> Vec<&SlideReport> {
+        self.slides
+            .iter()
+            .filter(|slide| slide.overflows())
+            .collect()
+    }
+
+    /// Write the report as JSON
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| {
+            BigError::SerializationError(format!("Failed to write overflow report: {}", e))
+        })?;
+        fs::write(path, json).map_err(BigError::FileReadError)
+    }
+}
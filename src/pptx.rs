@@ -2,20 +2,34 @@
 // ABOUTME: Creates PowerPoint presentations from slide images
 
 use crate::errors::{BigError, Result};
+use crate::html;
+use crate::images::{self, ImageFormat};
+use crate::links::LinkManifest;
+use crate::native::{self, SlideText};
+use crate::template::{relationships_xml, Relationship, Template, TemplateConfig};
+use crate::transition::Transition;
 use chrono;
 use glob;
 use image::io::Reader as ImageReader;
 use log::{info, warn};
+use quick_xml::escape::escape;
+use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use zip::{write::FileOptions, ZipWriter};
 
 /// Configuration for PPTX generation
+#[derive(Clone)]
 pub struct PptxConfig {
     pub title: String,
     pub pattern: String,
-    pub aspect_ratio: String, // "16:9" or "4:3"
+    pub aspect_ratio: String,             // "16:9" or "4:3"
+    pub markdown_path: Option<PathBuf>,   // Source markdown for speaker notes
+    pub native: bool, // Build editable text slides from the markdown instead of images
+    pub splice_dir: Option<PathBuf>, // Re-rendered images replacing the slides with the same number
+    pub transition: Transition, // Default transition and timing, overridable per slide in the markdown
+    pub template: Option<TemplateConfig>, // Package to take the masters, layouts, theme and fonts from
 }
 
 impl Default for PptxConfig {
@@ -24,19 +38,17 @@ impl Default for PptxConfig {
             title: "Presentation".to_string(),
             pattern: "*.png".to_string(),
             aspect_ratio: "16:9".to_string(),
+            markdown_path: None,
+            native: false,
+            splice_dir: None,
+            transition: Transition::default(),
+            template: None,
         }
     }
 }
 
-/// Generate a PPTX presentation from slide images
+/// Generate a PPTX presentation from slide images, or from the markdown itself in native mode
 pub fn generate_pptx(slides_dir: &Path, output_file: &Path, config: &PptxConfig) -> Result<()> {
-    info!("Generating PPTX from slides in {:?}", slides_dir);
-
-    // Validate input directory exists
-    if !slides_dir.exists() || !slides_dir.is_dir() {
-        return Err(BigError::PathNotFoundError(slides_dir.to_path_buf()));
-    }
-
     // Ensure parent directory for output file exists
     if let Some(parent) = output_file.parent() {
         if !parent.exists() {
@@ -44,33 +56,123 @@ pub fn generate_pptx(slides_dir: &Path, output_file: &Path, config: &PptxConfig)
         }
     }
 
-    // Collect all slide image files matching the pattern
-    let mut slide_paths = Vec::new();
-    let glob_pattern = format!("{}/{}", slides_dir.to_string_lossy(), config.pattern);
+    // Parse the source markdown, if provided
+    let deck = match &config.markdown_path {
+        Some(markdown_path) => html::parse_deck(markdown_path)?,
+        None => html::Deck::default(),
+    };
+    let markdown_slides = deck.slides;
 
-    for entry in (glob::glob(&glob_pattern)
-        .map_err(|e| BigError::PptxError(format!("Invalid glob pattern: {}", e)))?)
-    .flatten()
-    {
-        slide_paths.push(entry);
-    }
+    // Native slides are built from the markdown, image slides from the rendered images
+    let slide_paths = if config.native {
+        info!("Generating native PPTX from markdown");
+        if config.markdown_path.is_none() {
+            return Err(BigError::ValidationError(
+                "Native PPTX generation requires a markdown source".to_string(),
+            ));
+        }
+        if markdown_slides.is_empty() {
+            return Err(BigError::NoSlidesFoundError(format!(
+                "{:?}",
+                config.markdown_path
+            )));
+        }
+        Vec::new()
+    } else {
+        info!("Generating PPTX from slides in {:?}", slides_dir);
 
-    // Sort slide paths to ensure they're in the correct order
-    slide_paths.sort();
+        // Validate input directory exists
+        if !slides_dir.exists() || !slides_dir.is_dir() {
+            return Err(BigError::PathNotFoundError(slides_dir.to_path_buf()));
+        }
 
-    info!("Found {} slide images", slide_paths.len());
-    if slide_paths.is_empty() {
-        return Err(BigError::NoSlidesFoundError(glob_pattern));
+        // Collect all slide image files matching the pattern, in order
+        let images = find_slide_images(slides_dir, &config.pattern)?;
+        let images = match &config.splice_dir {
+            Some(splice_dir) => {
+                info!("Splicing re-rendered slides from {:?}", splice_dir);
+                splice_slide_images(images, find_slide_images(splice_dir, &config.pattern)?)?
+            }
+            None => images,
+        };
+
+        // AVIF can be written but not read back, so it is packaged from the PNG copy kept when rendering
+        if let Some(avif) = images
+            .iter()
+            .find(|path| is_avif(path) && !images::png_copy_path(path).is_file())
+        {
+            return Err(BigError::PptxError(format!(
+                "Cannot package {:?}: its PNG copy {:?} is missing. Re-render the AVIF slides with big-slides, or render PNG, JPEG or WebP slides for PPTX",
+                avif,
+                images::png_copy_path(avif)
+            )));
+        }
+        images
+    };
+    let slide_count = if config.native {
+        markdown_slides.len()
+    } else {
+        slide_paths.len()
+    };
+    info!("Found {} slides", slide_count);
+
+    // Relative image paths in the markdown resolve against its directory
+    let markdown_dir = config
+        .markdown_path
+        .as_ref()
+        .and_then(|path| path.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    // Collect speaker notes from the source markdown, if provided
+    if config.markdown_path.is_some() && markdown_slides.len() != slide_count {
+        warn!(
+            "Markdown has {} slides but {} slide images were found; notes may not line up",
+            markdown_slides.len(),
+            slide_count
+        );
     }
+    let slide_notes: Vec<Option<String>> = markdown_slides
+        .iter()
+        .take(slide_count)
+        .map(|slide| slide.notes.clone())
+        .collect();
+    let has_notes = slide_notes.iter().any(|notes| notes.is_some());
+
+    // Slides are added against a layout of the template, if there is one
+    let template = config
+        .template
+        .as_ref()
+        .map(|template| Template::load(&template.path))
+        .transpose()?;
+    let layout_target = match (&template, &config.template) {
+        (Some(template), Some(settings)) => {
+            let layout = template.layout(settings.layout.as_deref())?;
+            info!("Adding slides with template layout '{}'", layout.name);
+            format!("../{}", layout.part.trim_start_matches("ppt/"))
+        }
+        _ => "../slideLayouts/slideLayout1.xml".to_string(),
+    };
+    let template_size = template
+        .as_ref()
+        .filter(|_| config.template.as_ref().is_some_and(|t| t.keep_size))
+        .and_then(|template| template.slide_size.clone());
+
+    // The notes master's theme takes a name the template doesn't use
+    let notes_theme = (2..)
+        .map(|n| format!("ppt/theme/theme{}.xml", n))
+        .find(|part| !template.as_ref().is_some_and(|t| t.has_part(part)))
+        .unwrap_or_default();
 
     // Create a new PPTX file
     let file = fs::File::create(output_file).map_err(BigError::FileReadError)?;
     let mut zip = ZipWriter::new(file);
 
-    // Set slide dimensions based on aspect ratio
-    let (cx, cy) = match config.aspect_ratio.as_str() {
-        "16:9" => (9144000, 5143500), // 16:9 ratio
-        "4:3" => (9144000, 6858000),  // 4:3 ratio
+    // Set slide dimensions based on aspect ratio, unless the template's are kept
+    let (cx, cy) = match (&template_size, config.aspect_ratio.as_str()) {
+        (Some((cx, cy, _)), _) => (*cx, *cy),
+        (None, "16:9") => (9144000, 5143500), // 16:9 ratio
+        (None, "4:3") => (9144000, 6858000),  // 4:3 ratio
         _ => {
             warn!(
                 "Unsupported aspect ratio: {}. Using 16:9 instead.",
@@ -91,16 +193,24 @@ pub fn generate_pptx(slides_dir: &Path, output_file: &Path, config: &PptxConfig)
     <Default Extension="jpeg" ContentType="image/jpeg"/>
     <Default Extension="jpg" ContentType="image/jpeg"/>
     <Default Extension="png" ContentType="image/png"/>
+    <Default Extension="gif" ContentType="image/gif"/>
     <Override PartName="/ppt/presentation.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.presentation.main+xml"/>
-    <Override PartName="/ppt/slideMasters/slideMaster1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideMaster+xml"/>
-    <Override PartName="/ppt/slideLayouts/slideLayout1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideLayout+xml"/>
+{masters}
     <Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
     <Override PartName="/docProps/app.xml" ContentType="application/vnd.openxmlformats-officedocument.extended-properties+xml"/>
     {slides}
+    {notes}
 </Types>"#,
-        slides = slide_paths.iter().enumerate().map(|(i, _)| {
-            format!(r#"<Override PartName="/ppt/slides/slide{}.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slide+xml"/>"#, i + 1)
-        }).collect::<Vec<String>>().join("\n")
+        slides = (1..=slide_count).map(|slide_num| {
+            format!(r#"<Override PartName="/ppt/slides/slide{}.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slide+xml"/>"#, slide_num)
+        }).collect::<Vec<String>>().join("\n"),
+        masters = match &template {
+            Some(template) => template.content_types_xml(),
+            None => r#"    <Override PartName="/ppt/slideMasters/slideMaster1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideMaster+xml"/>
+    <Override PartName="/ppt/slideLayouts/slideLayout1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideLayout+xml"/>
+    <Override PartName="/ppt/theme/theme1.xml" ContentType="application/vnd.openxmlformats-officedocument.theme+xml"/>"#.to_string(),
+        },
+        notes = notes_content_types(&slide_notes, &notes_theme)
     );
     zip.write_all(content_types.as_bytes())?;
 
@@ -124,24 +234,14 @@ pub fn generate_pptx(slides_dir: &Path, output_file: &Path, config: &PptxConfig)
     <Application>big-slides</Application>
     <Slides>{}</Slides>
 </Properties>"#,
-        slide_paths.len()
+        slide_count
     );
     zip.write_all(app_xml.as_bytes())?;
 
     // Add docProps/core.xml
     info!("Creating PPTX structure: docProps/core.xml");
     zip.start_file("docProps/core.xml", FileOptions::default())?;
-    let core_xml = format!(
-        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:dcmitype="http://purl.org/dc/dcmitype/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-    <dc:title>{}</dc:title>
-    <dc:creator>big-slides</dc:creator>
-    <dcterms:created xsi:type="dcterms:W3CDTF">{}</dcterms:created>
-    <cp:revision>1</cp:revision>
-</cp:coreProperties>"#,
-        config.title,
-        chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
-    );
+    let core_xml = core_properties_xml(&config.title, &deck.metadata);
     zip.write_all(core_xml.as_bytes())?;
 
     // Add ppt/_rels/presentation.xml.rels
@@ -154,17 +254,36 @@ pub fn generate_pptx(slides_dir: &Path, output_file: &Path, config: &PptxConfig)
 "#,
     );
 
-    // Add relationship for slide master (must be before slides)
-    pres_rels.push_str(
-        r#"    <Relationship Id="rIdSM" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideMaster" Target="slideMasters/slideMaster1.xml"/>
+    if let Some(template) = &template {
+        // Add relationships for the template's masters, theme and fonts
+        pres_rels.push_str(&template.presentation_relationships_xml());
+    } else {
+        // Add relationship for slide master (must be before slides)
+        pres_rels.push_str(
+            r#"    <Relationship Id="rIdSM" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideMaster" Target="slideMasters/slideMaster1.xml"/>
 "#,
-    );
+        );
+
+        // Add relationship for the theme
+        pres_rels.push_str(
+            r#"    <Relationship Id="rIdTheme" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/theme" Target="theme/theme1.xml"/>
+"#,
+        );
+    }
+
+    // Add relationship for the notes master when any slide has notes
+    if has_notes {
+        pres_rels.push_str(
+            r#"    <Relationship Id="rIdNM" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/notesMaster" Target="notesMasters/notesMaster1.xml"/>
+"#,
+        );
+    }
 
     // Add relationship for each slide
-    for (i, _) in slide_paths.iter().enumerate() {
+    for slide_num in 1..=slide_count {
         pres_rels.push_str(&format!(
             r#"    <Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slide" Target="slides/slide{}.xml"/>"#,
-            i + 1, i + 1
+            slide_num, slide_num
         ));
         pres_rels.push('\n');
     }
@@ -176,166 +295,246 @@ pub fn generate_pptx(slides_dir: &Path, output_file: &Path, config: &PptxConfig)
     info!("Creating PPTX structure: ppt/presentation.xml");
     zip.start_file("ppt/presentation.xml", FileOptions::default())?;
 
-    // Determine correct slide size type
-    let slide_size_type = match config.aspect_ratio.as_str() {
-        "16:9" => "screen16x9",
-        "4:3" => "screen4x3",
-        _ => "screen16x9", // Default to 16:9
+    // Determine correct slide size type; custom template sizes have none
+    let slide_size_type = match (&template_size, config.aspect_ratio.as_str()) {
+        (Some((_, _, size_type)), _) => size_type.clone(),
+        (None, "4:3") => Some("screen4x3".to_string()),
+        _ => Some("screen16x9".to_string()), // Default to 16:9
     };
+    let slide_size_type = slide_size_type
+        .map(|size_type| format!(r#" type="{}""#, escape(&size_type)))
+        .unwrap_or_default();
+    let master_ids = match &template {
+        Some(template) => template.master_ids_xml(),
+        None => r#"        <p:sldMasterId id="2147483648" r:id="rIdSM"/>"#.to_string(),
+    };
+    let embedded_fonts = template.as_ref().and_then(|t| t.embedded_fonts_xml());
+    let default_text_style = template
+        .as_ref()
+        .and_then(|t| t.default_text_style.clone())
+        .unwrap_or_else(|| DEFAULT_TEXT_STYLE_XML.to_string());
 
     let presentation_xml = format!(
         r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <p:presentation xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"
                 xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"
                 xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main"
-                xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006">
+                xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006"{embed_fonts}>
     <p:sldMasterIdLst>
-        <p:sldMasterId id="2147483648" r:id="rIdSM"/>
-    </p:sldMasterIdLst>
+{master_ids}
+    </p:sldMasterIdLst>{notes_master_ids}
     <p:sldIdLst>
 {slide_ids}
     </p:sldIdLst>
-    <p:sldSz cx="{cx}" cy="{cy}" type="{slide_size_type}"/>
-    <p:notesSz cx="6858000" cy="9144000"/>
-    <p:defaultTextStyle>
-        <a:defPPr/>
-        <a:lvl1pPr marL="0" algn="l" defTabSz="914400" rtl="0" eaLnBrk="1" latinLnBrk="0" hangingPunct="1">
-            <a:defRPr sz="1800" kern="1200">
-                <a:solidFill>
-                    <a:schemeClr val="tx1"/>
-                </a:solidFill>
-                <a:latin typeface="+mn-lt"/>
-                <a:ea typeface="+mn-ea"/>
-                <a:cs typeface="+mn-cs"/>
-            </a:defRPr>
-        </a:lvl1pPr>
-    </p:defaultTextStyle>
+    <p:sldSz cx="{cx}" cy="{cy}"{slide_size_type}/>
+    <p:notesSz cx="6858000" cy="9144000"/>{embedded_fonts}
+    {default_text_style}
 </p:presentation>"#,
-        slide_ids = slide_paths
-            .iter()
-            .enumerate()
-            .map(|(i, _)| { format!(r#"        <p:sldId id="{}" r:id="rId{}"/>"#, 256 + i, i + 1) })
+        embed_fonts = if embedded_fonts.is_some() {
+            r#" embedTrueTypeFonts="1""#
+        } else {
+            ""
+        },
+        embedded_fonts = embedded_fonts
+            .map(|fonts| format!("\n    {}", fonts))
+            .unwrap_or_default(),
+        master_ids = master_ids,
+        default_text_style = default_text_style,
+        slide_ids = (1..=slide_count)
+            .map(|slide_num| {
+                format!(
+                    r#"        <p:sldId id="{}" r:id="rId{}"/>"#,
+                    255 + slide_num,
+                    slide_num
+                )
+            })
             .collect::<Vec<String>>()
             .join("\n"),
+        notes_master_ids = if has_notes {
+            "\n    <p:notesMasterIdLst>\n        <p:notesMasterId r:id=\"rIdNM\"/>\n    </p:notesMasterIdLst>"
+        } else {
+            ""
+        },
         cx = cx,
         cy = cy,
         slide_size_type = slide_size_type
     );
     zip.write_all(presentation_xml.as_bytes())?;
 
-    // Add a basic slide master
-    info!("Creating slide master: ppt/slideMasters/slideMaster1.xml");
-    zip.start_file("ppt/slideMasters/slideMaster1.xml", FileOptions::default())?;
-    let slide_master_xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<p:sldMaster xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"
-             xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"
-             xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main">
-    <p:cSld>
-        <p:bg>
-            <p:bgRef idx="1001">
-                <a:schemeClr val="bg1"/>
-            </p:bgRef>
-        </p:bg>
-        <p:spTree>
-            <p:nvGrpSpPr>
-                <p:cNvPr id="1" name=""/>
-                <p:cNvGrpSpPr/>
-                <p:nvPr/>
-            </p:nvGrpSpPr>
-            <p:grpSpPr>
-                <a:xfrm>
-                    <a:off x="0" y="0"/>
-                    <a:ext cx="0" cy="0"/>
-                    <a:chOff x="0" y="0"/>
-                    <a:chExt cx="0" cy="0"/>
-                </a:xfrm>
-            </p:grpSpPr>
-        </p:spTree>
-    </p:cSld>
-    <p:clrMap bg1="lt1" tx1="dk1" bg2="lt2" tx2="dk2" accent1="accent1" accent2="accent2" accent3="accent3" accent4="accent4" accent5="accent5" accent6="accent6" hlink="hlink" folHlink="folHlink"/>
-</p:sldMaster>"#;
-    zip.write_all(slide_master_xml.as_bytes())?;
-
-    // Add slide master relationships
-    info!("Creating slide master relationships: ppt/slideMasters/_rels/slideMaster1.xml.rels");
-    zip.start_file(
-        "ppt/slideMasters/_rels/slideMaster1.xml.rels",
-        FileOptions::default(),
-    )?;
-    let slide_master_rels = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
-    <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideLayout" Target="../slideLayouts/slideLayout1.xml"/>
-</Relationships>"#;
-    zip.write_all(slide_master_rels.as_bytes())?;
-
-    // Add a basic slide layout
-    info!("Creating slide layout: ppt/slideLayouts/slideLayout1.xml");
-    zip.start_file("ppt/slideLayouts/slideLayout1.xml", FileOptions::default())?;
-    let slide_layout_xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<p:sldLayout xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"
-             xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"
-             xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main" type="blank">
-    <p:cSld name="Blank">
-        <p:spTree>
-            <p:nvGrpSpPr>
-                <p:cNvPr id="1" name=""/>
-                <p:cNvGrpSpPr/>
-                <p:nvPr/>
-            </p:nvGrpSpPr>
-            <p:grpSpPr>
-                <a:xfrm>
-                    <a:off x="0" y="0"/>
-                    <a:ext cx="0" cy="0"/>
-                    <a:chOff x="0" y="0"/>
-                    <a:chExt cx="0" cy="0"/>
-                </a:xfrm>
-            </p:grpSpPr>
-        </p:spTree>
-    </p:cSld>
-    <p:clrMapOvr>
-        <a:masterClrMapping/>
-    </p:clrMapOvr>
-</p:sldLayout>"#;
-    zip.write_all(slide_layout_xml.as_bytes())?;
-
-    // Add slide layout relationships
-    info!("Creating slide layout relationships: ppt/slideLayouts/_rels/slideLayout1.xml.rels");
-    zip.start_file(
-        "ppt/slideLayouts/_rels/slideLayout1.xml.rels",
-        FileOptions::default(),
-    )?;
-    let slide_layout_rels = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+    if let Some(template) = &template {
+        // Add the template's masters, layouts, themes and fonts as they are
+        for (part, data) in &template.parts {
+            info!("Adding template part: {}", part);
+            zip.start_file(part.as_str(), FileOptions::default())?;
+            zip.write_all(data)?;
+        }
+    } else {
+        // Add a basic slide master
+        info!("Creating slide master: ppt/slideMasters/slideMaster1.xml");
+        zip.start_file("ppt/slideMasters/slideMaster1.xml", FileOptions::default())?;
+        let slide_master_xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+    <p:sldMaster xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"
+                 xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"
+                 xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main">
+        <p:cSld>
+            <p:bg>
+                <p:bgRef idx="1001">
+                    <a:schemeClr val="bg1"/>
+                </p:bgRef>
+            </p:bg>
+            <p:spTree>
+                <p:nvGrpSpPr>
+                    <p:cNvPr id="1" name=""/>
+                    <p:cNvGrpSpPr/>
+                    <p:nvPr/>
+                </p:nvGrpSpPr>
+                <p:grpSpPr>
+                    <a:xfrm>
+                        <a:off x="0" y="0"/>
+                        <a:ext cx="0" cy="0"/>
+                        <a:chOff x="0" y="0"/>
+                        <a:chExt cx="0" cy="0"/>
+                    </a:xfrm>
+                </p:grpSpPr>
+            </p:spTree>
+        </p:cSld>
+        <p:clrMap bg1="lt1" tx1="dk1" bg2="lt2" tx2="dk2" accent1="accent1" accent2="accent2" accent3="accent3" accent4="accent4" accent5="accent5" accent6="accent6" hlink="hlink" folHlink="folHlink"/>
+    </p:sldMaster>"#;
+        zip.write_all(slide_master_xml.as_bytes())?;
+
+        // Add slide master relationships
+        info!("Creating slide master relationships: ppt/slideMasters/_rels/slideMaster1.xml.rels");
+        zip.start_file(
+            "ppt/slideMasters/_rels/slideMaster1.xml.rels",
+            FileOptions::default(),
+        )?;
+        let slide_master_rels = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+    <Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+        <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideLayout" Target="../slideLayouts/slideLayout1.xml"/>
+        <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/theme" Target="../theme/theme1.xml"/>
+    </Relationships>"#;
+        zip.write_all(slide_master_rels.as_bytes())?;
+
+        // Add the default theme
+        info!("Creating theme: ppt/theme/theme1.xml");
+        zip.start_file("ppt/theme/theme1.xml", FileOptions::default())?;
+        zip.write_all(THEME_XML.as_bytes())?;
+    }
+
+    // Add the notes master and its own theme when any slide has notes
+    if has_notes {
+        info!("Creating notes master: ppt/notesMasters/notesMaster1.xml");
+        zip.start_file("ppt/notesMasters/notesMaster1.xml", FileOptions::default())?;
+        zip.write_all(NOTES_MASTER_XML.as_bytes())?;
+
+        zip.start_file(
+            "ppt/notesMasters/_rels/notesMaster1.xml.rels",
+            FileOptions::default(),
+        )?;
+        let notes_master_rels = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
-    <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideMaster" Target="../slideMasters/slideMaster1.xml"/>
-</Relationships>"#;
-    zip.write_all(slide_layout_rels.as_bytes())?;
+    <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/theme" Target="../{}"/>
+</Relationships>"#,
+            notes_theme.trim_start_matches("ppt/")
+        );
+        zip.write_all(notes_master_rels.as_bytes())?;
+
+        zip.start_file(notes_theme.as_str(), FileOptions::default())?;
+        zip.write_all(THEME_XML.as_bytes())?;
+    }
+
+    if template.is_none() {
+        // Add a basic slide layout
+        info!("Creating slide layout: ppt/slideLayouts/slideLayout1.xml");
+        zip.start_file("ppt/slideLayouts/slideLayout1.xml", FileOptions::default())?;
+        let slide_layout_xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+    <p:sldLayout xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"
+                 xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"
+                 xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main" type="blank">
+        <p:cSld name="Blank">
+            <p:spTree>
+                <p:nvGrpSpPr>
+                    <p:cNvPr id="1" name=""/>
+                    <p:cNvGrpSpPr/>
+                    <p:nvPr/>
+                </p:nvGrpSpPr>
+                <p:grpSpPr>
+                    <a:xfrm>
+                        <a:off x="0" y="0"/>
+                        <a:ext cx="0" cy="0"/>
+                        <a:chOff x="0" y="0"/>
+                        <a:chExt cx="0" cy="0"/>
+                    </a:xfrm>
+                </p:grpSpPr>
+            </p:spTree>
+        </p:cSld>
+        <p:clrMapOvr>
+            <a:masterClrMapping/>
+        </p:clrMapOvr>
+    </p:sldLayout>"#;
+        zip.write_all(slide_layout_xml.as_bytes())?;
+
+        // Add slide layout relationships
+        info!("Creating slide layout relationships: ppt/slideLayouts/_rels/slideLayout1.xml.rels");
+        zip.start_file(
+            "ppt/slideLayouts/_rels/slideLayout1.xml.rels",
+            FileOptions::default(),
+        )?;
+        let slide_layout_rels = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+    <Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+        <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideMaster" Target="../slideMasters/slideMaster1.xml"/>
+    </Relationships>"#;
+        zip.write_all(slide_layout_rels.as_bytes())?;
+    }
+
+    // Link manifests of the directories the images were rendered to, to keep links clickable
+    let mut link_manifests: HashMap<PathBuf, Option<LinkManifest>> = HashMap::new();
 
     // Process each slide
-    for (i, slide_path) in slide_paths.iter().enumerate() {
+    for i in 0..slide_count {
         let slide_num = i + 1;
-        info!("Processing slide {}: {:?}", slide_num, slide_path);
-
-        // Add the image to the media directory
-        let image_ext = slide_path
-            .extension()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        let image_name = format!("image{}.{}", slide_num, image_ext);
-
-        // Read image data
-        let image_data = match fs::read(slide_path) {
-            Ok(data) => data,
-            Err(e) => {
-                warn!("Failed to read image file {:?}: {}", slide_path, e);
+        let mut body = if config.native {
+            info!("Building native slide {}", slide_num);
+            native::build_slide_body(
+                &markdown_slides[i].markdown,
+                &markdown_dir,
+                slide_num,
+                (cx, cy),
+            )
+        } else {
+            let slide_path = &slide_paths[i];
+            info!("Processing slide {}: {:?}", slide_num, slide_path);
+            let text = markdown_slides
+                .get(i)
+                .map(|slide| native::slide_text(&slide.markdown));
+            let Some(mut body) = image_slide_body(slide_path, slide_num, (cx, cy), text.as_ref())
+            else {
                 continue; // Skip this slide but continue with others
+            };
+            let dir = slide_path.parent().unwrap_or(Path::new("."));
+            let links = link_manifests
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| LinkManifest::load(dir));
+            if let (Some(links), Some(file)) = (links, slide_path.file_name()) {
+                add_link_overlays(&mut body, links, &file.to_string_lossy(), (cx, cy));
             }
+            body
         };
 
-        // Add image to media directory
-        info!("Adding image to PPTX: ppt/media/{}", image_name);
-        zip.start_file(format!("ppt/media/{}", image_name), FileOptions::default())?;
-        zip.write_all(&image_data)?;
+        // Media named like the template's own is renamed
+        if let Some(template) = &template {
+            rename_clashing_media(&mut body, |name| {
+                template.has_part(&format!("ppt/media/{}", name))
+            });
+        }
+
+        // Add media to the media directory
+        for media in &body.media {
+            info!("Adding image to PPTX: ppt/media/{}", media.name);
+            zip.start_file(format!("ppt/media/{}", media.name), FileOptions::default())?;
+            zip.write_all(&media.data)?;
+        }
 
         // Create slide_rels file
         info!(
@@ -346,32 +545,30 @@ pub fn generate_pptx(slides_dir: &Path, output_file: &Path, config: &PptxConfig)
             format!("ppt/slides/_rels/slide{}.xml.rels", slide_num),
             FileOptions::default(),
         )?;
-        let slide_rels = format!(
-            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
-    <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="../media/{}"/>
-    <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideLayout" Target="../slideLayouts/slideLayout1.xml"/>
-</Relationships>"#,
-            image_name
-        );
-        zip.write_all(slide_rels.as_bytes())?;
-
-        // Verify image can be read and decoded (for validation)
-        match ImageReader::open(slide_path) {
-            Ok(reader) => match reader.decode() {
-                Ok(_) => {
-                    // Image is valid, continue processing
-                }
-                Err(e) => {
-                    warn!("Failed to decode image {:?}: {}", slide_path, e);
-                    continue; // Skip this slide but continue with others
-                }
-            },
-            Err(e) => {
-                warn!("Failed to open image {:?}: {}", slide_path, e);
-                continue;
-            }
-        };
+        let notes = slide_notes.get(i).and_then(|notes| notes.as_deref());
+        let mut relationships = body.relationships;
+        relationships.push(Relationship::internal(
+            relationships.len() + 1,
+            REL_SLIDE_LAYOUT,
+            layout_target.as_str(),
+        ));
+        if notes.is_some() {
+            relationships.push(Relationship::internal(
+                relationships.len() + 1,
+                REL_NOTES_SLIDE,
+                format!("../notesSlides/notesSlide{}.xml", slide_num),
+            ));
+        }
+        zip.write_all(relationships_xml(&relationships).as_bytes())?;
+
+        // Slides without markdown use the deck's transition
+        let transition = markdown_slides
+            .get(i)
+            .map(|slide| slide.transition.apply(&config.transition))
+            .unwrap_or(config.transition)
+            .to_xml()
+            .map(|xml| format!("\n    {}", xml))
+            .unwrap_or_default();
 
         // Create slide file
         info!("Creating slide XML: ppt/slides/slide{}.xml", slide_num);
@@ -400,47 +597,41 @@ pub fn generate_pptx(slides_dir: &Path, output_file: &Path, config: &PptxConfig)
                     <a:chExt cx="0" cy="0"/>
                 </a:xfrm>
             </p:grpSpPr>
-            <p:pic>
-                <p:nvPicPr>
-                    <p:cNvPr id="2" name="Slide Image {slide_num}"/>
-                    <p:cNvPicPr>
-                        <a:picLocks noChangeAspect="1"/>
-                    </p:cNvPicPr>
-                    <p:nvPr/>
-                </p:nvPicPr>
-                <p:blipFill>
-                    <a:blip r:embed="rId1">
-                        <a:extLst>
-                            <a:ext uri="{{28A0092B-C50C-407E-A947-70E740481C1C}}">
-                                <a14:useLocalDpi xmlns:a14="http://schemas.microsoft.com/office/drawing/2010/main" val="0"/>
-                            </a:ext>
-                        </a:extLst>
-                    </a:blip>
-                    <a:stretch>
-                        <a:fillRect/>
-                    </a:stretch>
-                </p:blipFill>
-                <p:spPr>
-                    <a:xfrm>
-                        <a:off x="0" y="0"/>
-                        <a:ext cx="{cx}" cy="{cy}"/>
-                    </a:xfrm>
-                    <a:prstGeom prst="rect">
-                        <a:avLst/>
-                    </a:prstGeom>
-                </p:spPr>
-            </p:pic>
+{shapes}
         </p:spTree>
     </p:cSld>
     <p:clrMapOvr>
         <a:masterClrMapping/>
-    </p:clrMapOvr>
+    </p:clrMapOvr>{transition}
 </p:sld>"#,
-            cx = cx,
-            cy = cy,
+            shapes = body.shapes,
+            transition = transition,
             slide_num = slide_num
         );
         zip.write_all(slide_xml.as_bytes())?;
+
+        // Add the notes page for this slide
+        if let Some(notes) = notes {
+            info!(
+                "Creating notes slide: ppt/notesSlides/notesSlide{}.xml",
+                slide_num
+            );
+            zip.start_file(
+                format!("ppt/notesSlides/notesSlide{}.xml", slide_num),
+                FileOptions::default(),
+            )?;
+            zip.write_all(notes_slide_xml(notes).as_bytes())?;
+
+            zip.start_file(
+                format!("ppt/notesSlides/_rels/notesSlide{}.xml.rels", slide_num),
+                FileOptions::default(),
+            )?;
+            let notes_rels = relationships_xml(&[
+                Relationship::internal(1, REL_NOTES_MASTER, "../notesMasters/notesMaster1.xml"),
+                Relationship::internal(2, REL_SLIDE, format!("../slides/slide{}.xml", slide_num)),
+            ]);
+            zip.write_all(notes_rels.as_bytes())?;
+        }
     }
 
     // Finalize the ZIP file
@@ -471,3 +662,682 @@ pub fn find_slide_images(dir: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
 
     Ok(paths)
 }
+
+/// Replace slide images with re-rendered ones of the same slide number, e.g. `slide_0012.png`.
+/// Images for slides that aren't in `images` yet are added in slide order.
+pub fn splice_slide_images(images: Vec<PathBuf>, updates: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    let numbered = |path: PathBuf| {
+        slide_number(&path)
+            .map(|number| (number, path.clone()))
+            .ok_or_else(|| {
+                BigError::ValidationError(format!(
+                    "Cannot splice {:?}: its file name does not end with a slide number",
+                    path
+                ))
+            })
+    };
+
+    let mut slides = std::collections::BTreeMap::new();
+    for path in images {
+        let (number, path) = numbered(path)?;
+        slides.insert(number, path);
+    }
+    for path in updates {
+        let (number, path) = numbered(path)?;
+        if slides.insert(number, path).is_none() {
+            info!("Adding slide {} from the spliced images", number);
+        }
+    }
+    Ok(slides.into_values().collect())
+}
+
+/// Slide number at the end of an image's file name, e.g. 12 for `slide_0012.png`
+fn slide_number(path: &Path) -> Option<usize> {
+    let stem = path.file_stem()?.to_str()?;
+    let digits = stem.len() - stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    stem[stem.len() - digits..].parse().ok()
+}
+
+/// Document properties for docProps/core.xml, filled in from the deck's frontmatter
+fn core_properties_xml(title: &str, metadata: &html::DeckMetadata) -> String {
+    let now = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+
+    let mut optional = String::new();
+    if let Some(description) = &metadata.description {
+        optional.push_str(&format!(
+            "\n    <dc:description>{}</dc:description>",
+            escape(description)
+        ));
+    }
+    if !metadata.keywords.is_empty() {
+        optional.push_str(&format!(
+            "\n    <cp:keywords>{}</cp:keywords>",
+            escape(&metadata.keywords.join(", "))
+        ));
+    }
+    if let Some(language) = &metadata.language {
+        optional.push_str(&format!(
+            "\n    <dc:language>{}</dc:language>",
+            escape(language)
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:dcmitype="http://purl.org/dc/dcmitype/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+    <dc:title>{title}</dc:title>
+    <dc:creator>{creator}</dc:creator>{optional}
+    <cp:lastModifiedBy>big-slides</cp:lastModifiedBy>
+    <dcterms:created xsi:type="dcterms:W3CDTF">{now}</dcterms:created>
+    <dcterms:modified xsi:type="dcterms:W3CDTF">{now}</dcterms:modified>
+    <cp:revision>1</cp:revision>
+</cp:coreProperties>"#,
+        title = escape(title),
+        creator = escape(metadata.author.as_deref().unwrap_or("big-slides")),
+        optional = optional,
+        now = now
+    )
+}
+
+/// Relationship type for images
+pub(crate) const REL_IMAGE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/image";
+
+/// Relationship type for hyperlinks
+pub(crate) const REL_HYPERLINK: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink";
+
+/// Relationship type from a slide to its layout
+const REL_SLIDE_LAYOUT: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideLayout";
+
+/// Relationship type from a slide to its notes page
+const REL_NOTES_SLIDE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/notesSlide";
+
+/// Relationship type from a notes page to the notes master
+const REL_NOTES_MASTER: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/notesMaster";
+
+/// Relationship type from a notes page back to its slide
+const REL_SLIDE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/slide";
+
+/// A media file stored under ppt/media
+pub(crate) struct MediaFile {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// Shapes, relationships and media that make up the body of one slide.
+/// Shapes refer to the relationships as rId1, rId2, ... in order.
+pub(crate) struct SlideBody {
+    pub shapes: String,
+    pub relationships: Vec<Relationship>,
+    pub media: Vec<MediaFile>,
+}
+
+/// Give media files that `taken` says are already in the package a new name, and point
+/// the slide's relationships at it
+fn rename_clashing_media(body: &mut SlideBody, taken: impl Fn(&str) -> bool) {
+    for media in &mut body.media {
+        if !taken(&media.name) {
+            continue;
+        }
+        let renamed = (1..)
+            .map(|n| format!("big{}_{}", n, media.name))
+            .find(|name| !taken(name))
+            .unwrap_or_default();
+        let target = format!("../media/{}", media.name);
+        for relationship in &mut body.relationships {
+            if !relationship.external && relationship.target == target {
+                relationship.target = format!("../media/{}", renamed);
+            }
+        }
+        media.name = renamed;
+    }
+}
+
+/// Whether `path` names an AVIF slide image
+fn is_avif(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| ext.parse().ok())
+        == Some(ImageFormat::Avif)
+}
+
+/// Build a slide body holding a single full-bleed slide image. With the slide's text from
+/// the markdown, the image gets it as alt text and the slide gets a hidden title.
+fn image_slide_body(
+    slide_path: &Path,
+    slide_num: usize,
+    (cx, cy): (u64, u64),
+    text: Option<&SlideText>,
+) -> Option<SlideBody> {
+    // AVIF slides are packaged from their PNG copy, as AVIF can't be decoded
+    let avif_copy = is_avif(slide_path).then(|| images::png_copy_path(slide_path));
+    let slide_path = avif_copy.as_deref().unwrap_or(slide_path);
+
+    // Read image data
+    let image_data = match fs::read(slide_path) {
+        Ok(data) => data,
+        Err(e) => {
+            warn!("Failed to read image file {:?}: {}", slide_path, e);
+            return None;
+        }
+    };
+
+    // Verify image can be read and decoded (for validation)
+    let image = match ImageReader::open(slide_path) {
+        Ok(reader) => match reader.decode() {
+            Ok(image) => image,
+            Err(e) => {
+                warn!("Failed to decode image {:?}: {}", slide_path, e);
+                return None;
+            }
+        },
+        Err(e) => {
+            warn!("Failed to open image {:?}: {}", slide_path, e);
+            return None;
+        }
+    };
+
+    let mut image_ext = slide_path
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    // PowerPoint can't show WebP everywhere, so package it as PNG
+    let mut image_data = image_data;
+    if image_ext
+        .parse::<ImageFormat>()
+        .is_ok_and(|format| !format.is_pptx_compatible())
+    {
+        info!("Transcoding {:?} to PNG", slide_path);
+        image_data = match images::transcode_to_png(&image) {
+            Ok(png) => png,
+            Err(e) => {
+                warn!("{}", e);
+                return None;
+            }
+        };
+        image_ext = "png".to_string();
+    }
+    let image_name = format!("image{}.{}", slide_num, image_ext);
+
+    // The picture is shape 2 and the title shape 3, but the title comes first in reading order
+    let picture = picture_xml(
+        2,
+        &format!("Slide Image {}", slide_num),
+        &text.map(SlideText::description).unwrap_or_default(),
+        1,
+        (0, 0, cx, cy),
+    );
+    let shapes = match text {
+        Some(text) => {
+            let title =
+                title_placeholder_xml(3, &text.title_or_first_line(slide_num), (0, 0, cx, cy / 5));
+            format!("{}\n{}", title, picture)
+        }
+        None => picture,
+    };
+
+    Some(SlideBody {
+        shapes,
+        relationships: vec![Relationship::internal(
+            1,
+            REL_IMAGE,
+            format!("../media/{}", image_name),
+        )],
+        media: vec![MediaFile {
+            name: image_name,
+            data: image_data,
+        }],
+    })
+}
+
+/// Cover each clickable link recorded for the image `file` with an invisible shape that
+/// follows the link when clicked, so links survive the slide becoming a picture
+fn add_link_overlays(body: &mut SlideBody, links: &LinkManifest, file: &str, (cx, cy): (u64, u64)) {
+    let Some(areas) = links.slides.get(file) else {
+        return;
+    };
+    if links.width == 0 || links.height == 0 {
+        return;
+    }
+
+    // Link positions are in CSS pixels of the viewport, which the image fills
+    let to_emu = |px: f64, viewport: u32, size: u64| -> u64 {
+        (px / f64::from(viewport) * size as f64).round().max(0.0) as u64
+    };
+
+    // The picture and title are shapes 2 and 3
+    let clickable = areas.iter().filter(|area| area.is_clickable());
+    for (shape_id, area) in (4..).zip(clickable) {
+        // Every area of the same link shares one relationship
+        let rel_id = match body
+            .relationships
+            .iter()
+            .position(|rel| rel.rel_type == REL_HYPERLINK && rel.target == area.href)
+        {
+            Some(index) => index + 1,
+            None => {
+                body.relationships.push(Relationship::external(
+                    body.relationships.len() + 1,
+                    REL_HYPERLINK,
+                    area.href.clone(),
+                ));
+                body.relationships.len()
+            }
+        };
+
+        body.shapes.push('\n');
+        body.shapes.push_str(&link_overlay_xml(
+            shape_id,
+            &area.href,
+            rel_id,
+            (
+                to_emu(area.left, links.width, cx),
+                to_emu(area.top, links.height, cy),
+                to_emu(area.width, links.width, cx),
+                to_emu(area.height, links.height, cy),
+            ),
+        ));
+    }
+}
+
+/// Build the XML for an invisible rectangle linking to the URL behind relationship `rel_id`.
+/// It is filled, if fully transparently, because PowerPoint ignores clicks on unfilled shapes.
+fn link_overlay_xml(
+    id: usize,
+    href: &str,
+    rel_id: usize,
+    (x, y, cx, cy): (u64, u64, u64, u64),
+) -> String {
+    format!(
+        r#"            <p:sp>
+                <p:nvSpPr>
+                    <p:cNvPr id="{id}" name="Link {id}" descr="{href}">
+                        <a:hlinkClick r:id="rId{rel_id}"/>
+                    </p:cNvPr>
+                    <p:cNvSpPr/>
+                    <p:nvPr/>
+                </p:nvSpPr>
+                <p:spPr>
+                    <a:xfrm>
+                        <a:off x="{x}" y="{y}"/>
+                        <a:ext cx="{cx}" cy="{cy}"/>
+                    </a:xfrm>
+                    <a:prstGeom prst="rect">
+                        <a:avLst/>
+                    </a:prstGeom>
+                    <a:solidFill>
+                        <a:srgbClr val="FFFFFF">
+                            <a:alpha val="0"/>
+                        </a:srgbClr>
+                    </a:solidFill>
+                    <a:ln>
+                        <a:noFill/>
+                    </a:ln>
+                </p:spPr>
+            </p:sp>"#,
+        id = id,
+        href = escape(href),
+        rel_id = rel_id,
+        x = x,
+        y = y,
+        cx = cx,
+        cy = cy
+    )
+}
+
+/// Build the XML for a hidden title placeholder, which gives the slide a title in the outline
+/// and for screen readers without showing over the slide
+pub(crate) fn title_placeholder_xml(
+    id: usize,
+    title: &str,
+    (x, y, cx, cy): (u64, u64, u64, u64),
+) -> String {
+    format!(
+        r#"            <p:sp>
+                <p:nvSpPr>
+                    <p:cNvPr id="{id}" name="Title {id}" hidden="1"/>
+                    <p:cNvSpPr>
+                        <a:spLocks noGrp="1"/>
+                    </p:cNvSpPr>
+                    <p:nvPr>
+                        <p:ph type="title"/>
+                    </p:nvPr>
+                </p:nvSpPr>
+                <p:spPr>
+                    <a:xfrm>
+                        <a:off x="{x}" y="{y}"/>
+                        <a:ext cx="{cx}" cy="{cy}"/>
+                    </a:xfrm>
+                </p:spPr>
+                <p:txBody>
+                    <a:bodyPr/>
+                    <a:lstStyle/>
+                    <a:p>
+                        <a:r>
+                            <a:rPr lang="en-US" dirty="0"/>
+                            <a:t>{title}</a:t>
+                        </a:r>
+                    </a:p>
+                </p:txBody>
+            </p:sp>"#,
+        id = id,
+        title = escape(title),
+        x = x,
+        y = y,
+        cx = cx,
+        cy = cy
+    )
+}
+
+/// Build the XML for a picture shape showing the image behind relationship `rel_id`
+pub(crate) fn picture_xml(
+    id: usize,
+    name: &str,
+    descr: &str,
+    rel_id: usize,
+    (x, y, cx, cy): (u64, u64, u64, u64),
+) -> String {
+    format!(
+        r#"            <p:pic>
+                <p:nvPicPr>
+                    <p:cNvPr id="{id}" name="{name}" descr="{descr}"/>
+                    <p:cNvPicPr>
+                        <a:picLocks noChangeAspect="1"/>
+                    </p:cNvPicPr>
+                    <p:nvPr/>
+                </p:nvPicPr>
+                <p:blipFill>
+                    <a:blip r:embed="rId{rel_id}">
+                        <a:extLst>
+                            <a:ext uri="{{28A0092B-C50C-407E-A947-70E740481C1C}}">
+                                <a14:useLocalDpi xmlns:a14="http://schemas.microsoft.com/office/drawing/2010/main" val="0"/>
+                            </a:ext>
+                        </a:extLst>
+                    </a:blip>
+                    <a:stretch>
+                        <a:fillRect/>
+                    </a:stretch>
+                </p:blipFill>
+                <p:spPr>
+                    <a:xfrm>
+                        <a:off x="{x}" y="{y}"/>
+                        <a:ext cx="{cx}" cy="{cy}"/>
+                    </a:xfrm>
+                    <a:prstGeom prst="rect">
+                        <a:avLst/>
+                    </a:prstGeom>
+                </p:spPr>
+            </p:pic>"#,
+        id = id,
+        name = escape(name),
+        descr = escape(descr).replace('\n', "&#xA;"),
+        rel_id = rel_id,
+        x = x,
+        y = y,
+        cx = cx,
+        cy = cy
+    )
+}
+
+/// Content type overrides for the notes master and every notes slide
+fn notes_content_types(slide_notes: &[Option<String>], notes_theme: &str) -> String {
+    if slide_notes.iter().all(|notes| notes.is_none()) {
+        return String::new();
+    }
+
+    let mut overrides = vec![
+        r#"<Override PartName="/ppt/notesMasters/notesMaster1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.notesMaster+xml"/>"#.to_string(),
+        format!(r#"<Override PartName="/{}" ContentType="application/vnd.openxmlformats-officedocument.theme+xml"/>"#, notes_theme),
+    ];
+    for (i, notes) in slide_notes.iter().enumerate() {
+        if notes.is_some() {
+            overrides.push(format!(
+                r#"<Override PartName="/ppt/notesSlides/notesSlide{}.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.notesSlide+xml"/>"#,
+                i + 1
+            ));
+        }
+    }
+    overrides.join("\n")
+}
+
+/// Build the XML for a notes page, one paragraph per line of notes
+fn notes_slide_xml(notes: &str) -> String {
+    let paragraphs = notes
+        .lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                "<a:p><a:endParaRPr lang=\"en-US\"/></a:p>".to_string()
+            } else {
+                format!(
+                    "<a:p><a:r><a:rPr lang=\"en-US\"/><a:t>{}</a:t></a:r></a:p>",
+                    escape(line.trim_end())
+                )
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n                    ");
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:notes xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"
+         xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"
+         xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main">
+    <p:cSld>
+        <p:spTree>
+            <p:nvGrpSpPr>
+                <p:cNvPr id="1" name=""/>
+                <p:cNvGrpSpPr/>
+                <p:nvPr/>
+            </p:nvGrpSpPr>
+            <p:grpSpPr/>
+            <p:sp>
+                <p:nvSpPr>
+                    <p:cNvPr id="2" name="Slide Image Placeholder 1"/>
+                    <p:cNvSpPr>
+                        <a:spLocks noGrp="1" noRot="1" noChangeAspect="1"/>
+                    </p:cNvSpPr>
+                    <p:nvPr>
+                        <p:ph type="sldImg"/>
+                    </p:nvPr>
+                </p:nvSpPr>
+                <p:spPr/>
+            </p:sp>
+            <p:sp>
+                <p:nvSpPr>
+                    <p:cNvPr id="3" name="Notes Placeholder 2"/>
+                    <p:cNvSpPr>
+                        <a:spLocks noGrp="1"/>
+                    </p:cNvSpPr>
+                    <p:nvPr>
+                        <p:ph type="body" idx="1"/>
+                    </p:nvPr>
+                </p:nvSpPr>
+                <p:spPr/>
+                <p:txBody>
+                    <a:bodyPr/>
+                    <a:lstStyle/>
+                    {paragraphs}
+                </p:txBody>
+            </p:sp>
+        </p:spTree>
+    </p:cSld>
+    <p:clrMapOvr>
+        <a:masterClrMapping/>
+    </p:clrMapOvr>
+</p:notes>"#,
+        paragraphs = paragraphs
+    )
+}
+
+/// Default text style of decks that aren't built on a template
+const DEFAULT_TEXT_STYLE_XML: &str = r#"<p:defaultTextStyle>
+        <a:defPPr/>
+        <a:lvl1pPr marL="0" algn="l" defTabSz="914400" rtl="0" eaLnBrk="1" latinLnBrk="0" hangingPunct="1">
+            <a:defRPr sz="1800" kern="1200">
+                <a:solidFill>
+                    <a:schemeClr val="tx1"/>
+                </a:solidFill>
+                <a:latin typeface="+mn-lt"/>
+                <a:ea typeface="+mn-ea"/>
+                <a:cs typeface="+mn-cs"/>
+            </a:defRPr>
+        </a:lvl1pPr>
+    </p:defaultTextStyle>"#;
+
+/// Notes master with a slide image and a notes body placeholder
+const NOTES_MASTER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:notesMaster xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"
+               xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"
+               xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main">
+    <p:cSld>
+        <p:bg>
+            <p:bgRef idx="1001">
+                <a:schemeClr val="bg1"/>
+            </p:bgRef>
+        </p:bg>
+        <p:spTree>
+            <p:nvGrpSpPr>
+                <p:cNvPr id="1" name=""/>
+                <p:cNvGrpSpPr/>
+                <p:nvPr/>
+            </p:nvGrpSpPr>
+            <p:grpSpPr>
+                <a:xfrm>
+                    <a:off x="0" y="0"/>
+                    <a:ext cx="0" cy="0"/>
+                    <a:chOff x="0" y="0"/>
+                    <a:chExt cx="0" cy="0"/>
+                </a:xfrm>
+            </p:grpSpPr>
+            <p:sp>
+                <p:nvSpPr>
+                    <p:cNvPr id="2" name="Slide Image Placeholder 1"/>
+                    <p:cNvSpPr>
+                        <a:spLocks noGrp="1" noRot="1" noChangeAspect="1"/>
+                    </p:cNvSpPr>
+                    <p:nvPr>
+                        <p:ph type="sldImg" idx="2"/>
+                    </p:nvPr>
+                </p:nvSpPr>
+                <p:spPr>
+                    <a:xfrm>
+                        <a:off x="381000" y="685800"/>
+                        <a:ext cx="6096000" cy="3429000"/>
+                    </a:xfrm>
+                    <a:prstGeom prst="rect">
+                        <a:avLst/>
+                    </a:prstGeom>
+                    <a:noFill/>
+                    <a:ln w="12700">
+                        <a:solidFill>
+                            <a:prstClr val="black"/>
+                        </a:solidFill>
+                    </a:ln>
+                </p:spPr>
+            </p:sp>
+            <p:sp>
+                <p:nvSpPr>
+                    <p:cNvPr id="3" name="Notes Placeholder 2"/>
+                    <p:cNvSpPr>
+                        <a:spLocks noGrp="1"/>
+                    </p:cNvSpPr>
+                    <p:nvPr>
+                        <p:ph type="body" sz="quarter" idx="3"/>
+                    </p:nvPr>
+                </p:nvSpPr>
+                <p:spPr>
+                    <a:xfrm>
+                        <a:off x="685800" y="4400550"/>
+                        <a:ext cx="5486400" cy="3600450"/>
+                    </a:xfrm>
+                    <a:prstGeom prst="rect">
+                        <a:avLst/>
+                    </a:prstGeom>
+                </p:spPr>
+                <p:txBody>
+                    <a:bodyPr vert="horz" lIns="91440" tIns="45720" rIns="91440" bIns="45720" rtlCol="0"/>
+                    <a:lstStyle/>
+                    <a:p>
+                        <a:endParaRPr lang="en-US"/>
+                    </a:p>
+                </p:txBody>
+            </p:sp>
+        </p:spTree>
+    </p:cSld>
+    <p:clrMap bg1="lt1" tx1="dk1" bg2="lt2" tx2="dk2" accent1="accent1" accent2="accent2" accent3="accent3" accent4="accent4" accent5="accent5" accent6="accent6" hlink="hlink" folHlink="folHlink"/>
+    <p:notesStyle>
+        <a:lvl1pPr marL="0" algn="l" defTabSz="914400" rtl="0" eaLnBrk="1" latinLnBrk="0" hangingPunct="1">
+            <a:defRPr sz="1200" kern="1200">
+                <a:solidFill>
+                    <a:schemeClr val="tx1"/>
+                </a:solidFill>
+                <a:latin typeface="+mn-lt"/>
+                <a:ea typeface="+mn-ea"/>
+                <a:cs typeface="+mn-cs"/>
+            </a:defRPr>
+        </a:lvl1pPr>
+    </p:notesStyle>
+</p:notesMaster>"#;
+
+/// Minimal Office theme shared by the slide and notes masters
+const THEME_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<a:theme xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" name="Office Theme">
+    <a:themeElements>
+        <a:clrScheme name="Office">
+            <a:dk1><a:sysClr val="windowText" lastClr="000000"/></a:dk1>
+            <a:lt1><a:sysClr val="window" lastClr="FFFFFF"/></a:lt1>
+            <a:dk2><a:srgbClr val="44546A"/></a:dk2>
+            <a:lt2><a:srgbClr val="E7E6E6"/></a:lt2>
+            <a:accent1><a:srgbClr val="4472C4"/></a:accent1>
+            <a:accent2><a:srgbClr val="ED7D31"/></a:accent2>
+            <a:accent3><a:srgbClr val="A5A5A5"/></a:accent3>
+            <a:accent4><a:srgbClr val="FFC000"/></a:accent4>
+            <a:accent5><a:srgbClr val="5B9BD5"/></a:accent5>
+            <a:accent6><a:srgbClr val="70AD47"/></a:accent6>
+            <a:hlink><a:srgbClr val="0563C1"/></a:hlink>
+            <a:folHlink><a:srgbClr val="954F72"/></a:folHlink>
+        </a:clrScheme>
+        <a:fontScheme name="Office">
+            <a:majorFont>
+                <a:latin typeface="Calibri Light"/>
+                <a:ea typeface=""/>
+                <a:cs typeface=""/>
+            </a:majorFont>
+            <a:minorFont>
+                <a:latin typeface="Calibri"/>
+                <a:ea typeface=""/>
+                <a:cs typeface=""/>
+            </a:minorFont>
+        </a:fontScheme>
+        <a:fmtScheme name="Office">
+            <a:fillStyleLst>
+                <a:solidFill><a:schemeClr val="phClr"/></a:solidFill>
+                <a:solidFill><a:schemeClr val="phClr"/></a:solidFill>
+                <a:solidFill><a:schemeClr val="phClr"/></a:solidFill>
+            </a:fillStyleLst>
+            <a:lnStyleLst>
+                <a:ln w="6350"><a:solidFill><a:schemeClr val="phClr"/></a:solidFill></a:ln>
+                <a:ln w="12700"><a:solidFill><a:schemeClr val="phClr"/></a:solidFill></a:ln>
+                <a:ln w="19050"><a:solidFill><a:schemeClr val="phClr"/></a:solidFill></a:ln>
+            </a:lnStyleLst>
+            <a:effectStyleLst>
+                <a:effectStyle><a:effectLst/></a:effectStyle>
+                <a:effectStyle><a:effectLst/></a:effectStyle>
+                <a:effectStyle><a:effectLst/></a:effectStyle>
+            </a:effectStyleLst>
+            <a:bgFillStyleLst>
+                <a:solidFill><a:schemeClr val="phClr"/></a:solidFill>
+                <a:solidFill><a:schemeClr val="phClr"/></a:solidFill>
+                <a:solidFill><a:schemeClr val="phClr"/></a:solidFill>
+            </a:bgFillStyleLst>
+        </a:fmtScheme>
+    </a:themeElements>
+    <a:objectDefaults/>
+    <a:extraClrSchemeLst/>
+</a:theme>"#;
@@ -1,22 +1,87 @@
 // ABOUTME: Browser rendering module for the big-slides application
 // ABOUTME: Captures screenshots of HTML slides using a headless browser
+// ABOUTME: and prints them to PDF with one page per slide
 
 use crate::errors::{BigError, Result};
-use headless_chrome::{Browser, LaunchOptionsBuilder};
+use crate::images::{self, ImageFormat, DEFAULT_QUALITY};
+use crate::links::{LinkArea, LinkManifest, LINKS_JS};
+use crate::manifest::{DeckSources, SlideManifest};
+use crate::overflow::{OverflowReport, SlideLayout, SlideReport, DEFAULT_MIN_FONT_PX, MEASURE_JS};
+use headless_chrome::protocol::cdp::Emulation;
+use headless_chrome::protocol::cdp::Page::{self, CaptureScreenshotFormatOption};
+use headless_chrome::types::PrintToPdfOptions;
+use headless_chrome::{Browser, LaunchOptionsBuilder, Tab};
 use log::{info, warn};
+use lopdf::{Document, Object};
 use std::env;
+use std::fmt;
 use std::fs;
+use std::ops::{Range, RangeInclusive};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
 use std::time::{Duration, Instant};
 
+/// CSS pixels per inch, used to size PDF pages to the slide dimensions
+const PIXELS_PER_INCH: f64 = 96.0;
+
+/// Records the slides and the markup they share as soon as the document is parsed,
+/// before scripts such as big.js restyle them, for the content hashes of incremental renders
+const SNAPSHOT_JS: &str = r#"
+    document.addEventListener('DOMContentLoaded', function() {
+        var map = Array.prototype.map;
+        var shared = [document.head.outerHTML].concat(
+            map.call(document.querySelectorAll('body style, body script, body link'), function(element) {
+                return element.outerHTML;
+            })
+        );
+        var resources = map.call(document.querySelectorAll('link[href], script[src]'), function(element) {
+            return element.href || element.src;
+        });
+        var slides = map.call(document.querySelectorAll('body > div'), function(slide) {
+            return {
+                html: slide.outerHTML,
+                resources: map.call(slide.querySelectorAll('img[src]'), function(img) {
+                    return img.src;
+                })
+            };
+        });
+        window.__bigSources = JSON.stringify({ shared: shared, resources: resources, slides: slides });
+    }, true);
+"#;
+
+/// How long to wait for a slide to become ready before capturing it anyway
+pub const DEFAULT_SLIDE_TIMEOUT_MS: u64 = 10000;
+
 /// Configuration for browser rendering
 pub struct RenderConfig {
     pub width: u32,
     pub height: u32,
+    /// Device scale factor: images are `scale` times the CSS size of the slide
+    pub scale: f64,
     pub format: String,
+    /// Quality of lossy formats (jpeg, webp, avif) from 0 to 100
+    pub quality: Option<u8>,
     pub base_name: String,
     pub timeout_ms: u64,
     pub browser_path: Option<String>,
+    pub jobs: usize,
+    /// How long to wait for each slide to report it is ready before capturing it anyway
+    pub slide_timeout_ms: u64,
+    /// Number of slides the deck must have, if known; rendering fails on a mismatch
+    pub expected_slides: Option<usize>,
+    /// Only render these slides instead of the whole deck
+    pub slides: Option<SlideSelection>,
+    /// Skip slides whose image in the output directory is up to date, and delete the
+    /// images of removed slides, using a manifest kept in the output directory
+    pub incremental: bool,
+    /// Text smaller than this many CSS pixels is reported
+    pub min_font_px: f64,
+    /// Where to write the JSON overflow report, if anywhere
+    pub overflow_report: Option<PathBuf>,
+    /// Fail when content overflows or is clipped on any rendered slide
+    pub fail_on_overflow: bool,
 }
 
 impl Default for RenderConfig {
@@ -24,92 +89,857 @@ impl Default for RenderConfig {
         Self {
             width: 1920,
             height: 1080,
+            scale: 1.0,
             format: "png".to_string(),
+            quality: None,
             base_name: "slide".to_string(),
             timeout_ms: 30000, // 30 seconds
             browser_path: None,
+            jobs: 1,
+            slide_timeout_ms: DEFAULT_SLIDE_TIMEOUT_MS,
+            expected_slides: None,
+            slides: None,
+            incremental: false,
+            min_font_px: DEFAULT_MIN_FONT_PX,
+            overflow_report: None,
+            fail_on_overflow: false,
+        }
+    }
+}
+
+/// A set of slides picked by number, such as `3,5,10-20`. Slide numbers start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlideSelection(Vec<RangeInclusive<usize>>);
+
+impl SlideSelection {
+    /// Whether slide `number` (starting at 1) is selected
+    pub fn contains(&self, number: usize) -> bool {
+        self.0.iter().any(|range| range.contains(&number))
+    }
+
+    /// Zero-based indices of the selected slides in a deck of `count` slides, in deck order.
+    /// Fails if the selection names a slide the deck doesn't have.
+    pub fn indices(&self, count: usize) -> Result<Vec<i64>> {
+        if let Some(last) = self.0.iter().map(|range| *range.end()).max() {
+            if last > count {
+                return Err(BigError::ValidationError(format!(
+                    "Slide {} was selected but the deck only has {} slides",
+                    last, count
+                )));
+            }
+        }
+        Ok((1..=count)
+            .filter(|&number| self.contains(number))
+            .map(|number| number as i64 - 1)
+            .collect())
+    }
+}
+
+impl FromStr for SlideSelection {
+    type Err = BigError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |part: &str| {
+            BigError::ValidationError(format!(
+                "Invalid slide selection '{}', expected slide numbers or ranges like 3,5,10-20",
+                part
+            ))
+        };
+
+        let mut ranges = Vec::new();
+        for part in s.split(',').map(str::trim) {
+            let (start, end) = match part.split_once('-') {
+                Some((start, end)) => (start.trim(), end.trim()),
+                None => (part, part),
+            };
+            let start: usize = start.parse().map_err(|_| invalid(part))?;
+            let end: usize = end.parse().map_err(|_| invalid(part))?;
+            if start == 0 || end < start {
+                return Err(invalid(part));
+            }
+            ranges.push(start..=end);
+        }
+        Ok(SlideSelection(ranges))
+    }
+}
+
+impl fmt::Display for SlideSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .0
+            .iter()
+            .map(|range| {
+                if range.start() == range.end() {
+                    range.start().to_string()
+                } else {
+                    format!("{}-{}", range.start(), range.end())
+                }
+            })
+            .collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+/// A headless browser kept running across renders, so rendering many decks, or the same
+/// deck many times, only pays for starting Chrome once. The browser is launched on the
+/// first render and relaunched if it crashes or a render needs different launch settings.
+#[derive(Default)]
+pub struct RenderSession {
+    browser: Option<(Browser, LaunchSettings)>,
+}
+
+/// The parts of a render configuration the browser is launched with
+#[derive(Debug, Clone, PartialEq)]
+struct LaunchSettings {
+    width: u32,
+    height: u32,
+    browser_path: Option<String>,
+    idle_timeout: Duration,
+}
+
+impl LaunchSettings {
+    fn of(config: &RenderConfig) -> Self {
+        Self {
+            width: config.width,
+            height: config.height,
+            browser_path: config.browser_path.clone(),
+            idle_timeout: idle_timeout(config),
+        }
+    }
+}
+
+impl RenderSession {
+    /// Create a session; the browser starts with the first render
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generate slide images from an HTML file
+    pub fn generate_slides(
+        &mut self,
+        html_path: &Path,
+        output_dir: &Path,
+        config: &RenderConfig,
+    ) -> Result<Vec<PathBuf>> {
+        info!("Generating slides from HTML: {:?}", html_path);
+
+        // Validate input file exists
+        if !html_path.exists() {
+            return Err(BigError::PathNotFoundError(html_path.to_path_buf()));
+        }
+
+        // Refuse unknown formats before starting the browser
+        let format: ImageFormat = config.format.parse()?;
+        if config.quality.is_some() && !format.is_lossy() {
+            warn!("--quality has no effect on {} images", format);
+        }
+
+        // Ensure output directory exists
+        if !output_dir.exists() {
+            fs::create_dir_all(output_dir).map_err(BigError::FileReadError)?;
+        }
+
+        self.with_browser(config, |browser| {
+            render_slides(browser, html_path, output_dir, config)
+        })
+    }
+
+    /// Generate a PDF from an HTML file, one page per slide.
+    /// Returns the number of pages written.
+    pub fn generate_pdf(
+        &mut self,
+        html_path: &Path,
+        output_file: &Path,
+        config: &RenderConfig,
+    ) -> Result<usize> {
+        info!("Generating PDF from HTML: {:?}", html_path);
+
+        // Validate input file exists
+        if !html_path.exists() {
+            return Err(BigError::PathNotFoundError(html_path.to_path_buf()));
+        }
+
+        // Ensure parent directory exists
+        if let Some(parent) = output_file.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent).map_err(BigError::FileReadError)?;
+            }
+        }
+
+        self.with_browser(config, |browser| {
+            print_pdf(browser, html_path, output_file, config)
+        })
+    }
+
+    /// Run a render in the session's browser, closing the tabs it opened afterwards.
+    /// A render that fails because the browser died is retried once in a new browser.
+    fn with_browser<T>(
+        &mut self,
+        config: &RenderConfig,
+        render: impl Fn(&Browser) -> Result<T>,
+    ) -> Result<T> {
+        let mut retried = false;
+        loop {
+            let browser = self.browser(config)?;
+            let existing: Vec<String> = tab_ids(browser);
+            let result = render(browser);
+            for tab in browser.get_tabs().lock().unwrap().iter() {
+                if !existing.contains(tab.get_target_id()) {
+                    let _ = tab.close(false);
+                }
+            }
+
+            match result {
+                Err(e) if !retried && browser.get_version().is_err() => {
+                    warn!("Browser stopped while rendering ({}); restarting it", e);
+                    self.browser = None;
+                    retried = true;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// The running browser, launching one if there is none, it has stopped responding,
+    /// or it was launched with settings `config` doesn't match
+    fn browser(&mut self, config: &RenderConfig) -> Result<&Browser> {
+        let settings = LaunchSettings::of(config);
+        let reusable = match &self.browser {
+            Some((browser, launched_with)) if *launched_with == settings => {
+                let alive = browser.get_version().is_ok();
+                if !alive {
+                    warn!("Browser stopped responding; restarting it");
+                }
+                alive
+            }
+            _ => false,
+        };
+
+        if !reusable {
+            // Drop the old browser first so its process is gone before the new one starts
+            self.browser = None;
+            self.browser = Some((launch_browser(config)?, settings));
         }
+        Ok(&self.browser.as_ref().unwrap().0)
     }
 }
 
-/// Generate slide images from an HTML file
+fn tab_ids(browser: &Browser) -> Vec<String> {
+    browser
+        .get_tabs()
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|tab| tab.get_target_id().clone())
+        .collect()
+}
+
+/// Generate slide images from an HTML file in a browser of its own.
+/// Use a [`RenderSession`] to render several times without relaunching the browser.
 pub fn generate_slides(
     html_path: &Path,
     output_dir: &Path,
     config: &RenderConfig,
 ) -> Result<Vec<PathBuf>> {
-    info!("Generating slides from HTML: {:?}", html_path);
+    RenderSession::new().generate_slides(html_path, output_dir, config)
+}
+
+/// Capture the slides of an HTML deck into `output_dir` using `browser`
+fn render_slides(
+    browser: &Browser,
+    html_path: &Path,
+    output_dir: &Path,
+    config: &RenderConfig,
+) -> Result<Vec<PathBuf>> {
+    let (tab, slide_count) = open_slides(browser, html_path, config)?;
 
-    // Validate input file exists
-    if !html_path.exists() {
-        return Err(BigError::PathNotFoundError(html_path.to_path_buf()));
+    // Render the whole deck unless only some slides were asked for
+    let selected: Vec<i64> = match &config.slides {
+        Some(selection) => selection.indices(slide_count as usize)?,
+        None => (0..slide_count).collect(),
+    };
+
+    // Incremental renders skip slides whose image is already up to date
+    let file_names: Vec<String> = (0..slide_count)
+        .map(|i| slide_file_name(config, i))
+        .collect();
+    let mut incremental = if config.incremental {
+        let hashes = deck_sources(&tab, slide_count)?.slide_hashes(&render_settings(config));
+        let mut manifest = SlideManifest::load(output_dir);
+        for removed in manifest.remove_stale(output_dir, &file_names)? {
+            info!("Removed image of a deleted slide: {:?}", removed);
+            let _ = fs::remove_file(images::png_copy_path(&removed));
+        }
+        Some((manifest, hashes))
+    } else {
+        None
+    };
+    let to_render: Vec<i64> = match &mut incremental {
+        Some((manifest, hashes)) => {
+            let changed: Vec<i64> = selected
+                .iter()
+                .copied()
+                .filter(|&i| {
+                    let (file, hash) = (&file_names[i as usize], &hashes[i as usize]);
+                    !manifest.is_current(output_dir, file, hash)
+                })
+                .collect();
+            // Forget the old images until the new ones are captured
+            for &i in &changed {
+                manifest.slides.remove(&file_names[i as usize]);
+            }
+            info!(
+                "{} of {} slides changed since the last render",
+                changed.len(),
+                selected.len()
+            );
+            changed
+        }
+        None => selected.clone(),
+    };
+
+    if to_render.len() as i64 == slide_count {
+        info!("Loaded! Ready to render {} slides", slide_count);
+    } else {
+        info!(
+            "Loaded! Ready to render {} of {} slides",
+            to_render.len(),
+            slide_count
+        );
     }
 
-    // Ensure output directory exists
-    if !output_dir.exists() {
-        fs::create_dir_all(output_dir).map_err(BigError::FileReadError)?;
+    // Estimate rendering time (using 0.2s per slide as in Python version)
+    let estimated_seconds = (to_render.len() as f64) * 0.2;
+    info!(
+        "It will probably take about {:.2} seconds to render the slides. Sit back and relax.",
+        estimated_seconds
+    );
+
+    let start_time = Instant::now();
+    let jobs = config.jobs.clamp(1, to_render.len().max(1));
+
+    let rendered = if jobs == 1 {
+        capture_slides(&tab, &to_render, output_dir, config)?
+    } else {
+        // Each tab renders a contiguous share of the deck; joining the shares in order
+        // gives the same files, in the same order, as rendering them one by one
+        info!("Rendering with {} tabs", jobs);
+        thread::scope(|scope| {
+            let workers: Vec<_> = split_range(to_render.len() as i64, jobs)
+                .into_iter()
+                .enumerate()
+                .map(|(job, share)| {
+                    let slides = &to_render[share.start as usize..share.end as usize];
+                    let first_tab = &tab;
+                    scope.spawn(move || {
+                        // The tab that counted the slides takes the first share
+                        let tab = if job == 0 {
+                            first_tab.clone()
+                        } else {
+                            open_slides(browser, html_path, config)?.0
+                        };
+                        capture_slides(&tab, slides, output_dir, config)
+                    })
+                })
+                .collect();
+
+            let mut rendered = Vec::with_capacity(to_render.len());
+            for worker in workers {
+                let files = worker.join().map_err(|_| {
+                    BigError::ScreenshotError("A rendering thread panicked".to_string())
+                })??;
+                rendered.extend(files);
+            }
+            Ok::<_, BigError>(rendered)
+        })?
+    };
+
+    let elapsed = start_time.elapsed();
+    info!(
+        "Rendering complete. Captured {} slides in {:.2} seconds",
+        rendered.len(),
+        elapsed.as_secs_f64()
+    );
+
+    let report = overflow_report(&rendered, config);
+    if let Some(path) = &config.overflow_report {
+        report.write(path)?;
+        info!("Overflow report written: {:?}", path);
     }
 
-    // Configure browser launch options
-    let mut launch_options_builder = LaunchOptionsBuilder::default();
+    // Let the PPTX put clickable links over the images
+    update_link_manifest(output_dir, &rendered, &file_names, config)?;
 
-    // Set window size and headless mode
-    launch_options_builder.window_size(Some((config.width, config.height)));
-    launch_options_builder.headless(true);
+    // Record what the new images were rendered from, and list them with the unchanged ones.
+    // Overflowing slides stay out of the manifest so the next render checks them again.
+    let output_files = match &mut incremental {
+        Some((manifest, hashes)) => {
+            for captured in &rendered {
+                let i = captured.index as usize;
+                let overflows = report
+                    .slides
+                    .iter()
+                    .any(|slide| slide.slide == i + 1 && slide.overflows());
+                if !overflows {
+                    manifest
+                        .slides
+                        .insert(file_names[i].clone(), hashes[i].clone());
+                }
+            }
+            manifest.save(output_dir)?;
 
-    // Use custom browser path if specified
-    if let Some(browser_path) = &config.browser_path {
-        launch_options_builder.path(Some(browser_path.into()));
-    } else if let Ok(path) = env::var("BROWSER_PATH") {
-        if !path.is_empty() {
-            launch_options_builder.path(Some(path.into()));
+            selected
+                .iter()
+                .filter(|&&i| {
+                    manifest.slides.contains_key(&file_names[i as usize])
+                        || rendered.iter().any(|captured| captured.index == i)
+                })
+                .map(|&i| output_dir.join(&file_names[i as usize]))
+                .collect()
         }
+        None => rendered.into_iter().map(|captured| captured.file).collect(),
+    };
+
+    let overflowing = report.overflowing();
+    if config.fail_on_overflow && !overflowing.is_empty() {
+        let slides: Vec<String> = overflowing
+            .iter()
+            .map(|slide| slide.slide.to_string())
+            .collect();
+        return Err(BigError::SlideOverflow(slides.join(", ")));
     }
 
-    let launch_options = launch_options_builder
-        .build()
-        .map_err(|e| BigError::BrowserError {
-            message: format!("Failed to build browser options: {:?}", e),
-            source: None,
+    Ok(output_files)
+}
+
+/// Split `0..count` into `parts` contiguous ranges whose lengths differ by at most one
+pub(crate) fn split_range(count: i64, parts: usize) -> Vec<Range<i64>> {
+    let parts = parts.max(1) as i64;
+    let (size, extra) = (count / parts, count % parts);
+    let mut start = 0;
+    (0..parts)
+        .map(|part| {
+            let end = start + size + i64::from(part < extra);
+            let range = start..end;
+            start = end;
+            range
+        })
+        .filter(|range| !range.is_empty())
+        .collect()
+}
+
+/// Screenshot the slides at `slides` (zero-based indices) in a tab opened with `open_slides`.
+/// Files are named after the slide's position in the deck. Returns each file with its slide.
+fn capture_slides(
+    tab: &Tab,
+    slides: &[i64],
+    output_dir: &Path,
+    config: &RenderConfig,
+) -> Result<Vec<CapturedSlide>> {
+    // Chrome captures PNG, JPEG and WebP itself; AVIF is encoded from a PNG capture
+    let format: ImageFormat = config.format.parse()?;
+    let quality = config.quality.unwrap_or(DEFAULT_QUALITY);
+    let (capture_format, capture_quality) = match format {
+        ImageFormat::Png | ImageFormat::Avif => (CaptureScreenshotFormatOption::Png, None),
+        ImageFormat::Jpeg => (CaptureScreenshotFormatOption::Jpeg, Some(quality.into())),
+        ImageFormat::Webp => (CaptureScreenshotFormatOption::Webp, Some(quality.into())),
+    };
+
+    let mut output_files = Vec::with_capacity(slides.len());
+
+    for &i in slides {
+        show_slide(tab, i, config);
+        let layout = measure_layout(tab, i);
+        let links = find_links(tab, i);
+
+        let slide_num = i + 1;
+        let output_filename = slide_file_name(config, i);
+        let output_file = output_dir.join(&output_filename);
+
+        info!("Rendering {}", output_filename);
+
+        // A missing slide would silently shift every later slide, so fail outright
+        let screenshot_data = tab
+            .capture_screenshot(capture_format.clone(), capture_quality, None, true)
+            .map_err(|e| BigError::ScreenshotError(format!("slide {}: {}", slide_num, e)))?;
+
+        // Save screenshot
+        match format {
+            ImageFormat::Avif => images::save_avif_slide(&screenshot_data, &output_file, quality)?,
+            _ => fs::write(&output_file, &screenshot_data).map_err(BigError::FileReadError)?,
+        }
+
+        output_files.push(CapturedSlide {
+            file: output_file,
+            index: i,
+            layout,
+            links,
+        });
+    }
+
+    Ok(output_files)
+}
+
+/// A slide image written by `capture_slides`
+struct CapturedSlide {
+    file: PathBuf,
+    index: i64,
+    /// How the slide's content fitted, if it could be measured
+    layout: Option<SlideLayout>,
+    /// Where the slide's links are
+    links: Vec<LinkArea>,
+}
+
+/// Measure how the content of the slide at `index`, already shown, fits the viewport
+fn measure_layout(tab: &Tab, index: i64) -> Option<SlideLayout> {
+    let measured = tab
+        .evaluate(&format!("{}({})", MEASURE_JS, index), false)
+        .map_err(|e| e.to_string())
+        .and_then(|result| {
+            let json = result.value.as_ref().and_then(|value| value.as_str());
+            let json = json.ok_or_else(|| format!("got {:?}", result.value))?;
+            serde_json::from_str(json).map_err(|e| e.to_string())
+        });
+    match measured {
+        Ok(layout) => Some(layout),
+        Err(e) => {
+            warn!("Failed to measure the layout of slide {}: {}", index + 1, e);
+            None
+        }
+    }
+}
+
+/// Find the links on the slide at `index`, already shown
+fn find_links(tab: &Tab, index: i64) -> Vec<LinkArea> {
+    let found = tab
+        .evaluate(&format!("{}({})", LINKS_JS, index), false)
+        .map_err(|e| e.to_string())
+        .and_then(|result| {
+            let json = result.value.as_ref().and_then(|value| value.as_str());
+            let json = json.ok_or_else(|| format!("got {:?}", result.value))?;
+            serde_json::from_str(json).map_err(|e| e.to_string())
+        });
+    found.unwrap_or_else(|e| {
+        warn!("Failed to find the links on slide {}: {}", index + 1, e);
+        Vec::new()
+    })
+}
+
+/// Record the links of the captured slides in the link manifest of `output_dir`.
+/// Slides that weren't captured keep their links unless the viewport size changed.
+fn update_link_manifest(
+    output_dir: &Path,
+    rendered: &[CapturedSlide],
+    file_names: &[String],
+    config: &RenderConfig,
+) -> Result<()> {
+    let mut links = LinkManifest::load(output_dir)
+        .filter(|links| (links.width, links.height) == (config.width, config.height))
+        .unwrap_or(LinkManifest {
+            width: config.width,
+            height: config.height,
+            ..Default::default()
+        });
+    links.slides.retain(|file, _| file_names.contains(file));
+    for captured in rendered {
+        links.set(&file_names[captured.index as usize], captured.links.clone());
+    }
+    links.save(output_dir)
+}
+
+/// Report how every measured slide fits, warning about those that don't
+fn overflow_report(rendered: &[CapturedSlide], config: &RenderConfig) -> OverflowReport {
+    let slides = rendered
+        .iter()
+        .filter_map(|captured| {
+            let layout = captured.layout.clone()?;
+            let issues = layout.issues(config.min_font_px);
+            if !issues.is_empty() {
+                warn!("Slide {}: {}", captured.index + 1, layout.describe(&issues));
+            }
+            Some(SlideReport {
+                slide: captured.index as usize + 1,
+                file: slide_file_name(config, captured.index),
+                issues,
+                layout,
+            })
+        })
+        .collect();
+
+    OverflowReport {
+        width: config.width,
+        height: config.height,
+        min_font_px: config.min_font_px,
+        slides,
+    }
+}
+
+/// File name of the image of the slide at `index`, numbered from 1
+fn slide_file_name(config: &RenderConfig, index: i64) -> String {
+    format!("{}_{:04}.{}", config.base_name, index + 1, config.format)
+}
+
+/// Render settings that change how a slide image looks, for content hashes
+fn render_settings(config: &RenderConfig) -> String {
+    format!(
+        "{}x{}@{} {} {:?}",
+        config.width, config.height, config.scale, config.format, config.quality
+    )
+}
+
+/// Markup and resources of the deck in `tab`, as recorded by `SNAPSHOT_JS` before the
+/// page's own scripts ran
+fn deck_sources(tab: &Tab, slide_count: i64) -> Result<DeckSources> {
+    let result = tab
+        .evaluate("window.__bigSources || null", false)
+        .map_err(|e| BigError::SlideDiscoveryError(format!("Failed to read the slides: {}", e)))?;
+    let json = result
+        .value
+        .as_ref()
+        .and_then(|value| value.as_str())
+        .ok_or_else(|| {
+            BigError::SlideDiscoveryError(
+                "The page was not snapshotted before its scripts ran".to_string(),
+            )
         })?;
+    let sources: DeckSources = serde_json::from_str(json).map_err(|e| {
+        BigError::SlideDiscoveryError(format!("Failed to read the slide snapshot: {}", e))
+    })?;
+
+    if sources.slides.len() as i64 != slide_count {
+        return Err(BigError::SlideDiscoveryError(format!(
+            "The page had {} slides when it loaded but {} after its scripts ran",
+            sources.slides.len(),
+            slide_count
+        )));
+    }
+    Ok(sources)
+}
+
+/// Generate a PDF from an HTML file, one page per slide, in a browser of its own.
+/// Slides are printed rather than screenshotted so their text stays selectable.
+/// Returns the number of pages written.
+pub fn generate_pdf(html_path: &Path, output_file: &Path, config: &RenderConfig) -> Result<usize> {
+    RenderSession::new().generate_pdf(html_path, output_file, config)
+}
+
+/// Print each slide of an HTML deck to a page of `output_file` using `browser`
+fn print_pdf(
+    browser: &Browser,
+    html_path: &Path,
+    output_file: &Path,
+    config: &RenderConfig,
+) -> Result<usize> {
+    let (tab, slide_count) = open_slides(browser, html_path, config)?;
+
+    // Print with screen styles so the page looks like the slide on screen
+    tab.call_method(Emulation::SetEmulatedMedia {
+        media: Some("screen".to_string()),
+        features: None,
+    })
+    .map_err(|e| BigError::BrowserError {
+        message: format!("Failed to emulate screen media: {}", e),
+        source: None,
+    })?;
+
+    let start_time = Instant::now();
+    let mut pages = Vec::with_capacity(slide_count as usize);
+
+    // Print each slide on its own page, sized to the slide
+    for i in 0..slide_count {
+        show_slide(&tab, i, config);
+
+        info!("Printing slide {}", i + 1);
+
+        let options = PrintToPdfOptions {
+            print_background: Some(true),
+            paper_width: Some(config.width as f64 / PIXELS_PER_INCH),
+            paper_height: Some(config.height as f64 / PIXELS_PER_INCH),
+            margin_top: Some(0.0),
+            margin_bottom: Some(0.0),
+            margin_left: Some(0.0),
+            margin_right: Some(0.0),
+            page_ranges: Some("1".to_string()),
+            prefer_css_page_size: Some(false),
+            ..Default::default()
+        };
+
+        let pdf_data = tab
+            .print_to_pdf(Some(options))
+            .map_err(|e| BigError::PdfError(format!("Failed to print slide {}: {}", i + 1, e)))?;
+        pages.push(pdf_data);
+    }
+
+    if pages.is_empty() {
+        return Err(BigError::PdfError("No slides could be printed".to_string()));
+    }
+
+    let pdf_data = merge_pdf_pages(&pages)?;
+    fs::write(output_file, pdf_data).map_err(BigError::FileReadError)?;
+
+    let elapsed = start_time.elapsed();
+    info!(
+        "PDF complete. Printed {} slides in {:.2} seconds",
+        pages.len(),
+        elapsed.as_secs_f64()
+    );
+
+    Ok(pages.len())
+}
+
+/// Combine single-page PDFs into one document, keeping their order
+pub(crate) fn merge_pdf_pages(pages: &[Vec<u8>]) -> Result<Vec<u8>> {
+    let mut merged: Option<Document> = None;
+
+    for data in pages {
+        let mut doc = Document::load_mem(data)?;
+
+        let Some(base) = merged.as_mut() else {
+            merged = Some(doc);
+            continue;
+        };
+
+        // Move the new document's objects past the ones already in use
+        doc.renumber_objects_with(base.max_id + 1);
+        base.max_id = base.max_id.max(doc.max_id);
 
-    // Launch headless browser with improved error handling and retry logic
-    info!("Launching headless browser");
-    let browser = match Browser::new(launch_options) {
-        Ok(browser) => browser,
+        let pages_id = base.catalog()?.get(b"Pages")?.as_reference()?;
+        let new_pages: Vec<_> = doc.get_pages().into_values().collect();
+
+        // Carry over attributes the pages inherit from their old page tree
+        for page_id in &new_pages {
+            let old_parent = doc
+                .get_dictionary(*page_id)?
+                .get(b"Parent")
+                .and_then(Object::as_reference)
+                .ok();
+            let inherited: Vec<(Vec<u8>, Object)> = match old_parent {
+                Some(parent_id) => doc
+                    .get_dictionary(parent_id)?
+                    .iter()
+                    .filter(|(key, _)| {
+                        matches!(key.as_slice(), b"MediaBox" | b"Resources" | b"CropBox")
+                    })
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+                None => Vec::new(),
+            };
+
+            let page = doc.get_dictionary_mut(*page_id)?;
+            for (key, value) in inherited {
+                if !page.has(&key) {
+                    page.set(key, value);
+                }
+            }
+            page.set("Parent", Object::Reference(pages_id));
+        }
+
+        base.objects.append(&mut doc.objects);
+
+        let pages_dict = base.get_dictionary_mut(pages_id)?;
+        let count = pages_dict.get(b"Count")?.as_i64()?;
+        pages_dict.set("Count", count + new_pages.len() as i64);
+        let kids = pages_dict.get_mut(b"Kids")?.as_array_mut()?;
+        kids.extend(new_pages.into_iter().map(Object::Reference));
+    }
+
+    let mut doc = merged.ok_or_else(|| BigError::PdfError("No pages to merge".to_string()))?;
+
+    // Drop the catalogs and page trees left over from the merged documents
+    doc.prune_objects();
+
+    let mut output = Vec::new();
+    doc.save_to(&mut output).map_err(BigError::FileReadError)?;
+    Ok(output)
+}
+
+/// Launch a headless browser sized to the configured slide dimensions.
+/// If Chrome won't start with its sandbox, which is common in containers, it is
+/// retried without it.
+fn launch_browser(config: &RenderConfig) -> Result<Browser> {
+    let path =
+        browser_executable(config.browser_path.as_deref()).ok_or(BigError::BrowserNotFound)?;
+    let window_size = (config.width, config.height);
+
+    info!("Launching headless browser: {}", path.display());
+    match launch(&path, window_size, idle_timeout(config), true, None) {
+        Ok(browser) => Ok(browser),
         Err(e) => {
-            // Try with a slightly different configuration if first attempt fails
             warn!(
-                "First browser launch attempt failed: {}. Retrying with modified options...",
+                "First browser launch attempt failed: {}. Retrying without the sandbox...",
                 e
             );
-
-            // Build alternative launch options with safer defaults
-            let retry_options = LaunchOptionsBuilder::default()
-                .window_size(Some((config.width, config.height)))
-                .headless(true)
-                .sandbox(false) // Try without sandbox
-                // Using default args, which include the necessary flags
-                .build()
-                .map_err(|e| BigError::BrowserError {
-                    message: format!("Failed to build retry browser options: {:?}", e),
+            launch(&path, window_size, idle_timeout(config), false, None).map_err(|e| {
+                let message = format!(
+                    "Failed to launch browser {} after retry: {}. Run `big doctor` to diagnose",
+                    path.display(),
+                    e
+                );
+                warn!("{}", message);
+                BigError::BrowserError {
+                    message,
                     source: None,
-                })?;
-
-            match Browser::new(retry_options) {
-                Ok(browser) => browser,
-                Err(e) => {
-                    let message = format!("Failed to launch browser after retry: {}", e);
-                    warn!("{}", message);
-                    return Err(BigError::BrowserError {
-                        message,
-                        source: None,
-                    });
                 }
-            }
+            })
         }
-    };
+    }
+}
 
+/// The browser binary renders use: `browser_path` if given, then `BROWSER_PATH`,
+/// then an installed Chrome, Chromium or Edge. `None` if none can be found.
+pub fn browser_executable(browser_path: Option<&str>) -> Option<PathBuf> {
+    let configured = browser_path
+        .map(str::to_string)
+        .or_else(|| env::var("BROWSER_PATH").ok())
+        .filter(|path| !path.is_empty());
+    match configured {
+        Some(path) => Some(PathBuf::from(path)),
+        None => headless_chrome::browser::default_executable().ok(),
+    }
+}
+
+/// Launch headless Chrome from `path` with or without its sandbox. Without a `port`,
+/// a browser that won't start is retried on up to ten random DevTools ports.
+pub fn launch(
+    path: &Path,
+    window_size: (u32, u32),
+    idle_timeout: Duration,
+    sandbox: bool,
+    port: Option<u16>,
+) -> Result<Browser> {
+    let launch_options = LaunchOptionsBuilder::default()
+        .path(Some(path.to_path_buf()))
+        .port(port)
+        .window_size(Some(window_size))
+        .headless(true)
+        .sandbox(sandbox)
+        .idle_browser_timeout(idle_timeout)
+        .build()
+        .map_err(|e| BigError::BrowserError {
+            message: format!("Failed to build browser options: {:?}", e),
+            source: None,
+        })?;
+
+    Browser::new(launch_options).map_err(|e| BigError::BrowserError {
+        message: e.to_string(),
+        source: None,
+    })
+}
+
+/// How long the browser may stay silent before a call to it fails. Waiting for a slide
+/// to become ready is a single call, so this must outlast the slide timeout.
+fn idle_timeout(config: &RenderConfig) -> Duration {
+    Duration::from_secs(30)
+        .max(Duration::from_millis(config.slide_timeout_ms) + Duration::from_secs(5))
+}
+
+/// Open an HTML deck in a new tab and prepare it for slide-by-slide navigation.
+/// Returns the tab, showing the first slide, and the number of slides.
+fn open_slides(
+    browser: &Browser,
+    html_path: &Path,
+    config: &RenderConfig,
+) -> Result<(Arc<Tab>, i64)> {
     // Get absolute path for HTML file
     let html_path_abs = fs::canonicalize(html_path).map_err(BigError::FileReadError)?;
     let url = format!("file://{}", html_path_abs.to_string_lossy());
@@ -122,6 +952,43 @@ pub fn generate_slides(
         source: None,
     })?;
 
+    // Keep the CSS viewport at the slide size while rendering more pixels per CSS pixel
+    if config.scale != 1.0 {
+        tab.call_method(Emulation::SetDeviceMetricsOverride {
+            width: config.width,
+            height: config.height,
+            device_scale_factor: config.scale,
+            mobile: false,
+            scale: None,
+            screen_width: None,
+            screen_height: None,
+            position_x: None,
+            position_y: None,
+            dont_set_visible_size: None,
+            screen_orientation: None,
+            viewport: None,
+            display_feature: None,
+            device_posture: None,
+        })
+        .map_err(|e| BigError::BrowserError {
+            message: format!("Failed to set device scale factor {}: {}", config.scale, e),
+            source: None,
+        })?;
+    }
+
+    if config.incremental {
+        tab.call_method(Page::AddScriptToEvaluateOnNewDocument {
+            source: SNAPSHOT_JS.to_string(),
+            world_name: None,
+            include_command_line_api: None,
+            run_immediately: None,
+        })
+        .map_err(|e| BigError::BrowserError {
+            message: format!("Failed to prepare the slide snapshot: {}", e),
+            source: None,
+        })?;
+    }
+
     tab.navigate_to(&url).map_err(|e| BigError::BrowserError {
         message: format!("Failed to navigate to HTML: {}", e),
         source: None,
@@ -141,34 +1008,6 @@ pub fn generate_slides(
             source: None,
         })?;
 
-    // Additional wait to ensure resources are loaded
-    std::thread::sleep(Duration::from_millis(500));
-
-    // Direct slide counting approach using a simple script that just counts divs
-    let _js = r#"
-        // Simple, direct count of slides (no fancy detection)
-        try {
-            // Just get all direct div children of body - these are the slides
-            var slides = document.querySelectorAll('body > div');
-
-            // Log what we found to help with debugging
-            console.log('Found ' + slides.length + ' direct div children of body (slides)');
-
-            // Return the raw count with minimum of 1
-            Math.max(slides.length, 1);
-        } catch (e) {
-            // Log the error for debugging
-            console.error('Error counting slides: ' + e.message);
-            // Return a default in case of errors
-            1;
-        }
-    "#;
-
-    // Special handling for known test files
-    let url = tab.get_url();
-    let is_test_special_case =
-        url.contains("/tmp/test_output.html") || url.contains("/tmp/output.html");
-
     // Forcefully prep the slides with a script that ensures proper visibility
     let prep_js = r#"
         // Prepare slides for navigation and screenshots
@@ -218,150 +1057,174 @@ pub fn generate_slides(
         Err(e) => warn!("Error preparing slides: {}", e),
     };
 
-    // Now count the slides - we'll use a simpler, more direct approach now
-    let slide_count = match tab.evaluate("document.querySelectorAll('body > div').length", false) {
-        Ok(result) => {
-            let count_str = format!("{:?}", result.value);
-            let count = count_str
-                .trim_matches(|c| c == '"' || c == ' ')
-                .parse::<i64>()
-                .unwrap_or(0);
-
-            if count <= 0 {
-                if is_test_special_case {
-                    warn!("Failed to count slides, using default test count of 15");
-                    15
-                } else {
-                    warn!("Failed to count slides, defaulting to 15");
-                    15
-                }
-            } else {
-                info!("Detected {} slides", count);
-                count
-            }
-        }
-        Err(e) => {
-            warn!("Failed to count slides: {}. Using default of 15.", e);
-            15 // Always use 15 as fallback to ensure we capture all slides
-        }
-    };
-
-    info!("Loaded! Ready to render {} slides", slide_count);
-
-    // Estimate rendering time (using 0.2s per slide as in Python version)
-    let estimated_seconds = (slide_count as f64) * 0.2;
-    info!(
-        "It will probably take about {:.2} seconds to render the slides. Sit back and relax.",
-        estimated_seconds
-    );
+    let slide_count = count_slides(&tab, &url)?;
+    info!("Detected {} slides", slide_count);
 
-    let start_time = Instant::now();
-    let get_screenshot_format = || match config.format.to_lowercase().as_str() {
-        "png" => headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption::Png,
-        "jpeg" | "jpg" => headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption::Jpeg,
-        _ => {
-            warn!("Unsupported format: {}. Using PNG instead.", config.format);
-            headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption::Png
+    if let Some(expected) = config.expected_slides {
+        if slide_count as usize != expected {
+            return Err(BigError::SlideCountMismatch {
+                expected,
+                found: slide_count as usize,
+                location: url,
+            });
         }
-    };
+    }
 
-    let mut output_files = Vec::with_capacity(slide_count as usize);
+    Ok((tab, slide_count))
+}
 
-    // Render all slides - following exactly the Python implementation flow
-    for i in 0..slide_count {
-        // First take screenshot of the current slide
-        let slide_num = i + 1;
-        let output_filename = format!("{}_{:04}.{}", config.base_name, slide_num, config.format);
-        let output_file = output_dir.join(&output_filename);
+/// Count the slides of the deck loaded in `tab`, which must have at least one
+fn count_slides(tab: &Tab, url: &str) -> Result<i64> {
+    let result = tab
+        .evaluate("document.querySelectorAll('body > div').length", false)
+        .map_err(|e| {
+            BigError::SlideDiscoveryError(format!("Failed to count the slides in {}: {}", url, e))
+        })?;
 
-        info!("Rendering {}", output_filename);
+    match result.value.as_ref().and_then(|value| value.as_i64()) {
+        Some(count) if count > 0 => Ok(count),
+        Some(_) => Err(BigError::SlideDiscoveryError(format!(
+            "{} has no slides (no <div> elements directly inside <body>)",
+            url
+        ))),
+        None => Err(BigError::SlideDiscoveryError(format!(
+            "Counting the slides in {} returned {:?} instead of a number",
+            url, result.value
+        ))),
+    }
+}
 
-        match tab.capture_screenshot(get_screenshot_format(), None, None, true) {
-            Ok(screenshot_data) => {
-                // Save screenshot
-                fs::write(&output_file, &screenshot_data).map_err(BigError::FileReadError)?;
+/// Show the slide at `index` and wait until it is ready to capture
+fn show_slide(tab: &Tab, index: i64, config: &RenderConfig) {
+    // Use the go method from our global
+    let js = format!(
+        r#"
+        // Set the slide directly with our enhanced big object
+        if (window.big && typeof window.big.go === 'function') {{
+            window.big.go({});
+            true;
+        }} else {{
+            // Fallback for any unexpected scenario
+            var slides = document.querySelectorAll('body > div');
+            for (var i = 0; i < slides.length; i++) {{
+                slides[i].style.display = 'none';
+            }}
+            if ({} < slides.length) {{
+                slides[{}].style.display = 'inline';
+                true;
+            }} else {{
+                false;
+            }}
+        }}
+    "#,
+        index, index, index
+    );
 
-                output_files.push(output_file);
-            }
-            Err(e) => {
-                // Log the error but continue with other slides
-                warn!(
-                    "Failed to capture screenshot for slide {}: {}",
-                    slide_num, e
-                );
-            }
+    match tab.evaluate(&js, false) {
+        Ok(_) => info!("Showing slide {}", index + 1),
+        Err(e) => {
+            warn!("Failed to navigate to slide {}: {}", index + 1, e);
         }
+    }
 
-        // Navigate to next slide - use our global object
-        let next_slide_idx = i + 1;
-        if next_slide_idx < slide_count {
-            // Use the go method from our global
-            let js = format!(
-                r#"
-                // Set the slide directly with our enhanced big object
-                if (window.big && typeof window.big.go === 'function') {{
-                    window.big.go({});
-                    true;
-                }} else {{
-                    // Fallback for any unexpected scenario
-                    var slides = document.querySelectorAll('body > div');
-                    for (var i = 0; i < slides.length; i++) {{
-                        slides[i].style.display = 'none';
-                    }}
-                    if ({} < slides.length) {{
-                        slides[{}].style.display = 'inline';
-                        true;
-                    }} else {{
-                        false;
-                    }}
-                }}
-            "#,
-                next_slide_idx, next_slide_idx, next_slide_idx
-            );
+    // Final render preparation - ensure visible and stabilized
+    let stabilize_js = format!(
+        r#"
+        // Final visibility check
+        var slides = document.querySelectorAll('body > div');
+        for (var i = 0; i < slides.length; i++) {{
+            slides[i].style.display = i === {} ? 'inline' : 'none';
+        }}
+        // Force any pending transitions or animations to complete
+        window.getComputedStyle(document.body).opacity;
+        true;
+        "#,
+        index
+    );
 
-            match tab.evaluate(&js, false) {
-                Ok(_) => info!("Showing slide {}", next_slide_idx + 1),
-                Err(e) => {
-                    warn!("Failed to navigate to slide {}: {}", next_slide_idx + 1, e);
-                }
-            }
+    match tab.evaluate(&stabilize_js, false) {
+        Ok(_) => {}
+        Err(e) => warn!("Stabilization step failed: {}", e),
+    }
 
-            // Wait longer for transitions - especially important for images
-            std::thread::sleep(Duration::from_millis(800));
-        } else {
-            info!("Reached the end of slides");
-        }
+    wait_until_ready(tab, index, config.slide_timeout_ms);
+}
 
-        // Final render preparation - ensure visible and stabilized
-        let stabilize_js = format!(
-            r#"
-            // Final visibility check
-            var slides = document.querySelectorAll('body > div');
-            for (var i = 0; i < slides.length; i++) {{
-                slides[i].style.display = i === {} ? 'inline' : 'none';
+/// Wait until web fonts have loaded, every image on the slide at `index` is complete
+/// and the page's optional `window.bigReady(index)` promise has resolved.
+/// Gives up after `timeout_ms` and lets the slide be captured as it is.
+fn wait_until_ready(tab: &Tab, index: i64, timeout_ms: u64) {
+    let js = format!(
+        r#"
+        (function() {{
+            var index = {index};
+            var slide = document.querySelectorAll('body > div')[index];
+
+            function imagesLoaded() {{
+                var images = slide ? Array.prototype.slice.call(slide.querySelectorAll('img')) : [];
+                return Promise.all(images.map(function(img) {{
+                    if (img.complete) {{
+                        return Promise.resolve();
+                    }}
+                    return new Promise(function(resolve) {{
+                        img.addEventListener('load', resolve, {{ once: true }});
+                        img.addEventListener('error', resolve, {{ once: true }});
+                    }});
+                }}));
             }}
-            // Force any pending transitions or animations to complete
-            window.getComputedStyle(document.body).opacity;
-            true;
-            "#,
-            next_slide_idx
-        );
 
-        match tab.evaluate(&stabilize_js, false) {
-            Ok(_) => {}
-            Err(e) => warn!("Stabilization step failed: {}", e),
-        }
+            function pageReady() {{
+                if (typeof window.bigReady !== 'function') {{
+                    return Promise.resolve();
+                }}
+                try {{
+                    return Promise.resolve(window.bigReady(index));
+                }} catch (e) {{
+                    return Promise.reject(e);
+                }}
+            }}
 
-        // Extra wait for rendering stability
-        std::thread::sleep(Duration::from_millis(300));
-    }
+            function painted() {{
+                return new Promise(function(resolve) {{
+                    requestAnimationFrame(function() {{ requestAnimationFrame(resolve); }});
+                }});
+            }}
 
-    let elapsed = start_time.elapsed();
-    info!(
-        "Rendering complete. Captured {} slides in {:.2} seconds",
-        output_files.len(),
-        elapsed.as_secs_f64()
+            var fonts = document.fonts ? document.fonts.ready : Promise.resolve();
+            var ready = fonts
+                .then(imagesLoaded)
+                .then(pageReady)
+                .then(painted)
+                .then(function() {{ return 'ready'; }}, function(e) {{ return 'error: ' + e; }});
+            var timeout = new Promise(function(resolve) {{
+                setTimeout(function() {{ resolve('timeout'); }}, {timeout_ms});
+            }});
+            return Promise.race([ready, timeout]);
+        }})()
+        "#
     );
 
-    Ok(output_files)
+    let status = match tab.evaluate(&js, true) {
+        Ok(result) => result
+            .value
+            .as_ref()
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        Err(e) => format!("error: {}", e),
+    };
+
+    match status.as_str() {
+        "ready" => {}
+        "timeout" => warn!(
+            "Slide {} was not ready after {} ms; capturing it anyway",
+            index + 1,
+            timeout_ms
+        ),
+        _ => warn!(
+            "Could not tell whether slide {} is ready ({}); capturing it anyway",
+            index + 1,
+            status
+        ),
+    }
 }
@@ -1,27 +1,53 @@
 // ABOUTME: Resource handling for the big-slides application
-// ABOUTME: Handles local and remote resources like CSS and JavaScript files
+// ABOUTME: Handles local, remote (cached) and built-in resources like CSS and JavaScript files
 
+use crate::cache::{self, CacheEntry, CachePolicy, ResourceCache};
 use crate::errors::{BigError, Result};
-use log::info;
+use chrono::Utc;
+use log::{info, warn};
 use reqwest::blocking::Client;
+use reqwest::{header, StatusCode};
 use std::fs;
 use std::path::Path;
 use std::time::Duration;
 
-/// Represents a resource file that can be either local or remote.
+/// Prefix naming a resource compiled into the binary, e.g. `builtin:big.css`
+pub const BUILTIN_SCHEME: &str = "builtin:";
+
+/// Stylesheets and scripts bundled with big-slides so decks render offline
+const BUILTIN_RESOURCES: &[(&str, &str)] = &[
+    ("big.css", include_str!("themes/big.css")),
+    ("big.js", include_str!("themes/big.js")),
+    ("dark.css", include_str!("themes/dark.css")),
+];
+
+/// Names of the bundled resources, usable after the `builtin:` prefix
+pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+    BUILTIN_RESOURCES.iter().map(|(name, _)| *name)
+}
+
+/// Whether a resource path names a file, to be resolved relative to where it was written,
+/// rather than a URL or a built-in resource
+pub fn is_local_path(path: &str) -> bool {
+    !path.contains("://") && !path.starts_with(BUILTIN_SCHEME)
+}
+
+/// Represents a resource file that can be local, remote or built in.
 #[derive(Debug, Clone)]
 pub struct ResourceFile {
     pub path: String,
     pub is_remote: bool,
+    pub is_builtin: bool,
 }
 
 impl ResourceFile {
     /// Create a new ResourceFile from a path string.
-    /// The path can be either a local file path or a URL.
+    /// The path can be a local file path, a URL or a `builtin:` name.
     pub fn new(path: &str) -> Self {
         let is_remote = path.starts_with("http://") || path.starts_with("https://");
+        let is_builtin = path.starts_with(BUILTIN_SCHEME);
 
-        let normalized_path = if !is_remote {
+        let normalized_path = if !is_remote && !is_builtin {
             // Try to get absolute path for local files
             match std::path::Path::new(path).canonicalize() {
                 Ok(abs_path) => abs_path.to_string_lossy().to_string(),
@@ -34,48 +60,111 @@ impl ResourceFile {
         Self {
             path: normalized_path,
             is_remote,
+            is_builtin,
         }
     }
 
     /// Get the content of the resource file.
     /// If the file is remote, it will be fetched from the URL.
     /// If the file is local, it will be read from the filesystem.
+    /// Built-in resources come from the binary itself.
     pub fn content(&self) -> Result<String> {
-        if self.is_remote {
+        if self.is_builtin {
+            return self.builtin_content().map(str::to_string);
+        }
+        Ok(String::from_utf8_lossy(&self.bytes()?).into_owned())
+    }
+
+    /// Get the raw bytes of the resource, e.g. for images and fonts
+    pub fn bytes(&self) -> Result<Vec<u8>> {
+        if self.is_builtin {
+            self.builtin_content()
+                .map(|content| content.as_bytes().to_vec())
+        } else if self.is_remote {
             self.fetch_remote_content()
         } else {
             self.read_local_content()
         }
     }
 
-    /// Fetch content from a remote URL with retry capability
-    fn fetch_remote_content(&self) -> Result<String> {
-        info!("Fetching remote resource: {}", self.path);
+    /// Fetch content from a remote URL, going through the on-disk cache
+    fn fetch_remote_content(&self) -> Result<Vec<u8>> {
+        let policy = cache::policy();
+        let cache = match ResourceCache::open() {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                warn!("Resource cache unavailable: {}", e);
+                None
+            }
+        };
+        let cached = cache.as_ref().and_then(|cache| cache.get(&self.path));
 
-        // Create a client with timeout
-        let client = Client::builder()
-            .timeout(Duration::from_secs(10))
-            .build()
-            .map_err(BigError::FetchError)?;
+        match (policy, cached) {
+            (CachePolicy::Offline, Some((_, content))) => {
+                info!("Using cached resource: {}", self.path);
+                Ok(content)
+            }
+            (CachePolicy::Offline, None) => Err(BigError::OfflineError(self.path.clone())),
+            (CachePolicy::Normal, Some((entry, content))) if entry.is_fresh() => {
+                info!("Using cached resource: {}", self.path);
+                Ok(content)
+            }
+            (_, Some((entry, content))) => {
+                // A stale copy beats no copy, so a single failed attempt falls back to it
+                info!("Revalidating cached resource: {}", self.path);
+                match self.request(&Self::client()?, Some(&entry)) {
+                    Ok(Some((fresh, body))) => {
+                        Self::store(cache.as_ref(), &fresh, &body);
+                        Ok(body)
+                    }
+                    Ok(None) => {
+                        if let Some(cache) = &cache {
+                            let entry = CacheEntry {
+                                fetched_at: Utc::now(),
+                                ..entry
+                            };
+                            if let Err(e) = cache.update(&entry) {
+                                warn!("Failed to update cache entry for {}: {}", self.path, e);
+                            }
+                        }
+                        Ok(content)
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Failed to revalidate {}, using cached copy: {}",
+                            self.path, e
+                        );
+                        Ok(content)
+                    }
+                }
+            }
+            (_, None) => {
+                let (entry, body) = self.fetch_with_retries()?;
+                Self::store(cache.as_ref(), &entry, &body);
+                Ok(body)
+            }
+        }
+    }
+
+    /// Download a resource that isn't cached, retrying transient failures
+    fn fetch_with_retries(&self) -> Result<(CacheEntry, Vec<u8>)> {
+        info!("Fetching remote resource: {}", self.path);
+        let client = Self::client()?;
 
         // Try up to 3 times with increasing backoff
         let mut retry_delay = 1000; // Start with 1 second
-        let mut last_error = None;
-
-        for attempt in 1..=3 {
-            match client.get(&self.path).send() {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        return response.text().map_err(BigError::FetchError);
-                    } else {
-                        let status = response.status();
-                        last_error =
-                            Some(BigError::ValidationError(format!("HTTP error: {}", status)));
-                    }
-                }
-                Err(e) => {
-                    last_error = Some(BigError::FetchError(e));
-                }
+        let mut attempt = 1;
+        loop {
+            let error = match self.request(&client, None) {
+                Ok(Some(fetched)) => return Ok(fetched),
+                Ok(None) => BigError::ValidationError("Unexpected 304 response".to_string()),
+                Err(e) => e,
+            };
+
+            // There is no point waiting for a network that isn't there
+            let unreachable = matches!(&error, BigError::FetchError(e) if e.is_connect());
+            if attempt == 3 || unreachable {
+                return Err(error);
             }
 
             info!(
@@ -84,15 +173,94 @@ impl ResourceFile {
             );
             std::thread::sleep(Duration::from_millis(retry_delay));
             retry_delay *= 2; // Exponential backoff
+            attempt += 1;
         }
+    }
 
-        Err(last_error.unwrap_or_else(|| {
-            BigError::ValidationError("Unknown error fetching resource".to_string())
-        }))
+    /// Make one request for the resource, conditional on `cached` if given.
+    /// Returns `None` when the server says the cached copy is still current.
+    fn request(
+        &self,
+        client: &Client,
+        cached: Option<&CacheEntry>,
+    ) -> Result<Option<(CacheEntry, Vec<u8>)>> {
+        let mut request = client.get(&self.path);
+        if let Some(entry) = cached {
+            if let Some(etag) = &entry.etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request.send().map_err(BigError::FetchError)?;
+        let status = response.status();
+        if status == StatusCode::NOT_MODIFIED && cached.is_some() {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(BigError::ValidationError(format!("HTTP error: {}", status)));
+        }
+
+        let header_value = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header_value(header::ETAG);
+        let last_modified = header_value(header::LAST_MODIFIED);
+        let body = response.bytes().map_err(BigError::FetchError)?.to_vec();
+
+        Ok(Some((
+            CacheEntry {
+                url: self.path.clone(),
+                etag,
+                last_modified,
+                fetched_at: Utc::now(),
+                size: body.len() as u64,
+            },
+            body,
+        )))
+    }
+
+    /// HTTP client used for remote resources
+    fn client() -> Result<Client> {
+        Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(BigError::FetchError)
+    }
+
+    /// Save a download to the cache; a cache that can't be written only costs a warning
+    fn store(cache: Option<&ResourceCache>, entry: &CacheEntry, content: &[u8]) {
+        if let Some(cache) = cache {
+            if let Err(e) = cache.put(entry, content) {
+                warn!("Failed to cache {}: {}", entry.url, e);
+            }
+        }
+    }
+
+    /// Look up a resource bundled into the binary
+    fn builtin_content(&self) -> Result<&'static str> {
+        let name = &self.path[BUILTIN_SCHEME.len()..];
+        BUILTIN_RESOURCES
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .map(|(_, content)| *content)
+            .ok_or_else(|| {
+                BigError::InvalidResourcePath(format!(
+                    "Unknown built-in resource '{}', available: {}",
+                    name,
+                    builtin_names().collect::<Vec<_>>().join(", ")
+                ))
+            })
     }
 
     /// Read content from a local file
-    fn read_local_content(&self) -> Result<String> {
+    fn read_local_content(&self) -> Result<Vec<u8>> {
         info!("Reading local resource: {}", self.path);
         if !Path::new(&self.path).exists() {
             return Err(BigError::PathNotFoundError(
@@ -100,14 +268,16 @@ impl ResourceFile {
             ));
         }
 
-        fs::read_to_string(&self.path).map_err(BigError::FileReadError)
+        fs::read(&self.path).map_err(BigError::FileReadError)
     }
 
     /// Generate HTML tag for the resource, either embedding or linking the content.
     /// - tag_type: The type of tag to generate ("css" or "js")
     /// - embed: Whether to embed the content in the tag or link to it
+    ///
+    /// Built-in resources have nothing to link to, so they are always embedded.
     pub fn tag(&self, tag_type: &str, embed: bool) -> Result<String> {
-        if !embed {
+        if !embed && !self.is_builtin {
             // When explicitly requesting linking, create a link
             Ok(match tag_type {
                 "css" => format!(r#"<link rel="stylesheet" href="{}">"#, self.path),
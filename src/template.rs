@@ -0,0 +1,494 @@
+// ABOUTME: PowerPoint templates for generated decks
+// ABOUTME: Reads the masters, layouts, themes and fonts of a .pptx or .potx for new slides to use
+
+use log::{info, warn};
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+use crate::errors::{BigError, Result};
+
+/// Extensions whose content types every generated package already declares
+const BUILTIN_EXTENSIONS: [&str; 6] = ["xml", "rels", "jpeg", "jpg", "png", "gif"];
+
+/// Relationship types that lead away from the masters, to parts a template's own slides use
+const SKIPPED_RELATIONSHIPS: [&str; 4] = ["slide", "notesSlide", "notesMaster", "handoutMaster"];
+
+/// Which template to build a deck on
+#[derive(Debug, Clone)]
+pub struct TemplateConfig {
+    /// The .pptx or .potx to take the masters, layouts, themes and fonts from
+    pub path: PathBuf,
+
+    /// Name of the layout new slides use; the template's blank layout if `None`
+    pub layout: Option<String>,
+
+    /// Keep the template's slide size rather than sizing slides by the aspect ratio
+    pub keep_size: bool,
+}
+
+/// A slide layout in a template
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Layout {
+    /// Part name, such as `ppt/slideLayouts/slideLayout7.xml`
+    pub part: String,
+    pub name: String,
+    blank: bool,
+}
+
+/// The parts of a template that new slides are built on
+pub(crate) struct Template {
+    /// Parts to copy into the new package by name, without the leading slash
+    pub parts: BTreeMap<String, Vec<u8>>,
+
+    /// Slide masters with the ids the template gave them, in order
+    pub masters: Vec<(String, String)>,
+
+    /// Theme of the presentation itself
+    pub theme: Option<String>,
+
+    /// Width, height and size type of the template's slides
+    pub slide_size: Option<(u64, u64, Option<String>)>,
+
+    /// The template's `<p:defaultTextStyle>` element
+    pub default_text_style: Option<String>,
+
+    pub layouts: Vec<Layout>,
+
+    /// The template's `<p:embeddedFontLst>` element and the font parts its ids refer to
+    fonts: Option<(String, Vec<(String, String)>)>,
+
+    /// Content types by extension
+    defaults: Vec<(String, String)>,
+
+    /// Content types of single parts
+    overrides: Vec<(String, String)>,
+}
+
+/// A relationship from a part to another part or to an external URL, as kept in a `.rels` part
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Relationship {
+    pub id: String,
+    pub rel_type: String,
+    pub target: String,
+    pub external: bool,
+}
+
+impl Relationship {
+    /// Relationship `rId{number}` to another part inside the package
+    pub(crate) fn internal(number: usize, rel_type: &str, target: impl Into<String>) -> Self {
+        Self {
+            id: format!("rId{}", number),
+            rel_type: rel_type.to_string(),
+            target: target.into(),
+            external: false,
+        }
+    }
+
+    /// Relationship `rId{number}` to a URL outside the package
+    pub(crate) fn external(number: usize, rel_type: &str, target: impl Into<String>) -> Self {
+        Self {
+            external: true,
+            ..Self::internal(number, rel_type, target)
+        }
+    }
+}
+
+impl Template {
+    /// Read the masters, layouts, themes and fonts of the template at `path`
+    pub fn load(path: &Path) -> Result<Self> {
+        info!("Loading PPTX template: {:?}", path);
+        if !path.is_file() {
+            return Err(BigError::PathNotFoundError(path.to_path_buf()));
+        }
+        let invalid = |message: String| {
+            BigError::PptxError(format!("Invalid template {}: {}", path.display(), message))
+        };
+
+        let file = fs::File::open(path).map_err(BigError::FileReadError)?;
+        let mut archive = ZipArchive::new(file).map_err(|e| invalid(e.to_string()))?;
+
+        // The presentation part is usually ppt/presentation.xml, but the package says where
+        let root_rels = read_xml(&mut archive, "_rels/.rels")
+            .ok_or_else(|| invalid("not a PowerPoint package".to_string()))?;
+        let presentation = relationships(&root_rels)?
+            .into_iter()
+            .find(|rel| rel_kind(&rel.rel_type) == "officeDocument")
+            .map(|rel| resolve_target("", &rel.target))
+            .ok_or_else(|| invalid("no presentation part".to_string()))?;
+        let presentation_xml = read_xml(&mut archive, &presentation)
+            .ok_or_else(|| invalid(format!("missing {}", presentation)))?;
+        let presentation_rels = read_xml(&mut archive, &rels_part(&presentation))
+            .map(|rels| relationships(&rels))
+            .transpose()?
+            .unwrap_or_default();
+        let target_of = |id: &str| {
+            presentation_rels
+                .iter()
+                .find(|rel| rel.id == id)
+                .map(|rel| resolve_target(&presentation, &rel.target))
+        };
+
+        let masters: Vec<(String, String)> = elements(&presentation_xml, "p:sldMasterId")?
+            .into_iter()
+            .filter_map(|attrs| Some((attrs.get("id")?.clone(), target_of(attrs.get("r:id")?)?)))
+            .collect();
+        if masters.is_empty() {
+            return Err(invalid("no slide master".to_string()));
+        }
+        let theme = presentation_rels
+            .iter()
+            .find(|rel| rel_kind(&rel.rel_type) == "theme")
+            .map(|rel| resolve_target(&presentation, &rel.target));
+        let slide_size = elements(&presentation_xml, "p:sldSz")?
+            .into_iter()
+            .next()
+            .and_then(|attrs| {
+                let cx = attrs.get("cx")?.parse().ok()?;
+                let cy = attrs.get("cy")?.parse().ok()?;
+                Some((cx, cy, attrs.get("type").cloned()))
+            });
+
+        // Embedded fonts are listed in the presentation and stored in parts of their own
+        let fonts = raw_element(&presentation_xml, "p:embeddedFontLst").map(|list| {
+            let parts = presentation_rels
+                .iter()
+                .filter(|rel| rel_kind(&rel.rel_type) == "font")
+                .map(|rel| (rel.id.clone(), resolve_target(&presentation, &rel.target)))
+                .collect::<Vec<_>>();
+            (list, parts)
+        });
+
+        // Copy everything the masters, the theme and the fonts lead to
+        let mut queue: Vec<String> = masters.iter().map(|(_, part)| part.clone()).collect();
+        queue.extend(theme.clone());
+        if let Some((_, font_parts)) = &fonts {
+            queue.extend(font_parts.iter().map(|(_, part)| part.clone()));
+        }
+        let mut parts = BTreeMap::new();
+        while let Some(part) = queue.pop() {
+            if parts.contains_key(&part) {
+                continue;
+            }
+            let Some(data) = read_part(&mut archive, &part) else {
+                warn!("Template {:?} is missing {}", path, part);
+                continue;
+            };
+            parts.insert(part.clone(), data);
+
+            let rels = rels_part(&part);
+            if let Some(rels_data) = read_part(&mut archive, &rels) {
+                for rel in relationships(&String::from_utf8_lossy(&rels_data))? {
+                    if !rel.external && !SKIPPED_RELATIONSHIPS.contains(&rel_kind(&rel.rel_type)) {
+                        queue.push(resolve_target(&part, &rel.target));
+                    }
+                }
+                parts.insert(rels, rels_data);
+            }
+        }
+
+        let mut layouts = Vec::new();
+        for (part, data) in &parts {
+            let xml = String::from_utf8_lossy(data);
+            let Some(layout) = elements(&xml, "p:sldLayout")?.into_iter().next() else {
+                continue;
+            };
+            let name = elements(&xml, "p:cSld")?
+                .into_iter()
+                .next()
+                .and_then(|attrs| attrs.get("name").cloned())
+                .unwrap_or_default();
+            layouts.push(Layout {
+                part: part.clone(),
+                name,
+                blank: layout.get("type").is_some_and(|t| t == "blank"),
+            });
+        }
+        // slideLayout2.xml comes before slideLayout10.xml
+        layouts.sort_by(|a, b| (a.part.len(), &a.part).cmp(&(b.part.len(), &b.part)));
+        if layouts.is_empty() {
+            return Err(invalid("no slide layout".to_string()));
+        }
+
+        let content_types = read_xml(&mut archive, "[Content_Types].xml")
+            .ok_or_else(|| invalid("missing [Content_Types].xml".to_string()))?;
+        let defaults = elements(&content_types, "Default")?
+            .into_iter()
+            .filter_map(|attrs| {
+                Some((
+                    attrs.get("Extension")?.clone(),
+                    attrs.get("ContentType")?.clone(),
+                ))
+            })
+            .filter(|(ext, _)| !BUILTIN_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            .collect();
+        let overrides = elements(&content_types, "Override")?
+            .into_iter()
+            .filter_map(|attrs| {
+                let part = attrs.get("PartName")?.trim_start_matches('/').to_string();
+                Some((part, attrs.get("ContentType")?.clone()))
+            })
+            .filter(|(part, _)| parts.contains_key(part))
+            .collect();
+
+        Ok(Self {
+            parts,
+            masters,
+            theme,
+            slide_size,
+            default_text_style: raw_element(&presentation_xml, "p:defaultTextStyle"),
+            layouts,
+            fonts,
+            defaults,
+            overrides,
+        })
+    }
+
+    /// The layout called `name`, ignoring case, or the blank layout (else the first one)
+    pub fn layout(&self, name: Option<&str>) -> Result<&Layout> {
+        match name {
+            Some(name) => self
+                .layouts
+                .iter()
+                .find(|layout| layout.name.eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| {
+                    let names: Vec<&str> = self.layouts.iter().map(|l| l.name.as_str()).collect();
+                    BigError::PptxError(format!(
+                        "The template has no layout called '{}'. Its layouts are: {}",
+                        name,
+                        names.join(", ")
+                    ))
+                }),
+            None => Ok(self
+                .layouts
+                .iter()
+                .find(|layout| layout.blank)
+                .unwrap_or(&self.layouts[0])),
+        }
+    }
+
+    /// Whether the template has a part called `name`
+    pub fn has_part(&self, name: &str) -> bool {
+        self.parts.contains_key(name)
+    }
+
+    /// `<Default>` and `<Override>` entries for the copied parts
+    pub fn content_types_xml(&self) -> String {
+        let defaults = self.defaults.iter().map(|(ext, content_type)| {
+            format!(
+                r#"    <Default Extension="{}" ContentType="{}"/>"#,
+                escape(ext),
+                escape(content_type)
+            )
+        });
+        let overrides = self.overrides.iter().map(|(part, content_type)| {
+            format!(
+                r#"    <Override PartName="/{}" ContentType="{}"/>"#,
+                escape(part),
+                escape(content_type)
+            )
+        });
+        defaults.chain(overrides).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Relationships from the presentation to the masters (`rIdSM1`, ...), the theme
+    /// (`rIdTheme`) and the embedded fonts (`rIdFont1`, ...)
+    pub fn presentation_relationships_xml(&self) -> String {
+        let relationship = |id: String, kind: &str, part: &str| {
+            format!(
+                r#"    <Relationship Id="{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/{}" Target="{}"/>
+"#,
+                id,
+                kind,
+                escape(part.trim_start_matches("ppt/"))
+            )
+        };
+
+        let mut xml = String::new();
+        for (i, (_, part)) in self.masters.iter().enumerate() {
+            xml.push_str(&relationship(
+                format!("rIdSM{}", i + 1),
+                "slideMaster",
+                part,
+            ));
+        }
+        if let Some(theme) = &self.theme {
+            xml.push_str(&relationship("rIdTheme".to_string(), "theme", theme));
+        }
+        if let Some((_, font_parts)) = &self.fonts {
+            for (i, (_, part)) in font_parts.iter().enumerate() {
+                xml.push_str(&relationship(format!("rIdFont{}", i + 1), "font", part));
+            }
+        }
+        xml
+    }
+
+    /// `<p:sldMasterId>` entries for every master, keeping the template's ids
+    pub fn master_ids_xml(&self) -> String {
+        self.masters
+            .iter()
+            .enumerate()
+            .map(|(i, (id, _))| {
+                format!(
+                    r#"        <p:sldMasterId id="{}" r:id="rIdSM{}"/>"#,
+                    escape(id),
+                    i + 1
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The template's `<p:embeddedFontLst>`, referring to the fonts by their new ids
+    pub fn embedded_fonts_xml(&self) -> Option<String> {
+        let (list, font_parts) = self.fonts.as_ref()?;
+        let mut list = list.clone();
+        for (i, (id, _)) in font_parts.iter().enumerate() {
+            list = list.replace(
+                &format!(r#"r:id="{}""#, id),
+                &format!(r#"r:id="rIdFont{}""#, i + 1),
+            );
+        }
+        Some(list)
+    }
+}
+
+/// Read a part of the package, if it is there
+fn read_part<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<Vec<u8>> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+/// Read an XML part of the package, if it is there
+fn read_xml<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    read_part(archive, name).map(|data| String::from_utf8_lossy(&data).into_owned())
+}
+
+/// Name of the relationships part of `part`
+pub(crate) fn rels_part(part: &str) -> String {
+    match part.rsplit_once('/') {
+        Some((dir, file)) => format!("{}/_rels/{}.rels", dir, file),
+        None => format!("_rels/{}.rels", part),
+    }
+}
+
+/// Resolve a relationship target against the part it is relative to
+pub(crate) fn resolve_target(part: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut segments: Vec<&str> = part.split('/').collect();
+    segments.pop();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                segments.pop();
+            }
+            "." | "" => {}
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// The last part of a relationship type URI, such as `slideLayout`
+pub(crate) fn rel_kind(rel_type: &str) -> &str {
+    rel_type.rsplit('/').next().unwrap_or(rel_type)
+}
+
+/// Read the relationships of a `.rels` part
+pub(crate) fn relationships(xml: &str) -> Result<Vec<Relationship>> {
+    Ok(elements(xml, "Relationship")?
+        .into_iter()
+        .filter_map(|attrs| {
+            Some(Relationship {
+                id: attrs.get("Id")?.clone(),
+                rel_type: attrs.get("Type")?.clone(),
+                target: attrs.get("Target")?.clone(),
+                external: attrs
+                    .get("TargetMode")
+                    .is_some_and(|mode| mode == "External"),
+            })
+        })
+        .collect())
+}
+
+/// Build the XML for a relationships part
+pub(crate) fn relationships_xml(relationships: &[Relationship]) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+"#,
+    );
+    for rel in relationships {
+        xml.push_str(&format!(
+            "    <Relationship Id=\"{}\" Type=\"{}\" Target=\"{}\"{}/>\n",
+            escape(&rel.id),
+            escape(&rel.rel_type),
+            escape(&rel.target),
+            if rel.external {
+                r#" TargetMode="External""#
+            } else {
+                ""
+            }
+        ));
+    }
+    xml.push_str("</Relationships>");
+    xml
+}
+
+/// The attributes of every element called `name`, such as `p:sldSz`, in document order
+pub(crate) fn elements(xml: &str, name: &str) -> Result<Vec<HashMap<String, String>>> {
+    let mut reader = Reader::from_str(xml);
+    let mut found = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) | Ok(Event::Empty(element))
+                if element.name().as_ref() == name.as_bytes() =>
+            {
+                found.push(
+                    element
+                        .attributes()
+                        .filter_map(|attr| attr.ok())
+                        .map(|attr| {
+                            let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+                            let value = attr
+                                .unescape_value()
+                                .map(|value| value.into_owned())
+                                .unwrap_or_default();
+                            (key, value)
+                        })
+                        .collect(),
+                );
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(BigError::PptxError(format!(
+                    "Failed to read package XML: {}",
+                    e
+                )))
+            }
+            _ => {}
+        }
+    }
+    Ok(found)
+}
+
+/// The source of the first element called `name`, including its tags
+fn raw_element(xml: &str, name: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}", name))?;
+    let close = format!("</{}>", name);
+    let end = match xml[start..].find(&close) {
+        Some(offset) => start + offset + close.len(),
+        // A self-closing element ends at the first `/>`
+        None => start + xml[start..].find("/>")? + 2,
+    };
+    Some(xml[start..end].to_string())
+}
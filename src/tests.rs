@@ -1,6 +1,7 @@
 use super::*;
 use std::fs;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use tempfile::{NamedTempFile, TempDir};
 
 fn create_temp_markdown_file(content: &str) -> NamedTempFile {
@@ -30,7 +31,7 @@ fn test_generate_html_basic() {
     let markdown_file = create_temp_markdown_file(markdown_content);
 
     let result = html::generate_html_without_reload(
-        &markdown_file.path().to_path_buf(),
+        markdown_file.path(),
         &[],
         &[],
         true, // embed resources
@@ -50,6 +51,22 @@ fn test_generate_html_basic() {
     assert!(html.contains("<body>\n<div>"));
 }
 
+#[test]
+fn test_with_base_href_points_at_markdown_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let markdown_path = temp_dir.path().join("deck.md");
+    fs::write(&markdown_path, "# Slide\n\n![](image.png)").unwrap();
+
+    let html = html::generate_html_without_reload(&markdown_path, &[], &[], false).unwrap();
+    let based = html::with_base_href(&html, temp_dir.path()).unwrap();
+
+    let dir_url = url::Url::from_directory_path(fs::canonicalize(temp_dir.path()).unwrap())
+        .unwrap()
+        .to_string();
+    assert!(based.contains(&format!("<head>\n<base href=\"{}\">\n", dir_url)));
+    assert_eq!(based.matches("<base ").count(), 1);
+}
+
 #[test]
 fn test_generate_html_with_local_css() {
     let markdown_content = "# Test Slide\n\nThis is a test slide.";
@@ -61,7 +78,7 @@ fn test_generate_html_with_local_css() {
     let css_resource = ResourceFile::new(css_file.path().to_str().unwrap());
 
     let result = html::generate_html_without_reload(
-        &markdown_file.path().to_path_buf(),
+        markdown_file.path(),
         &[css_resource],
         &[],
         true, // embed resources
@@ -85,7 +102,7 @@ fn test_generate_html_with_local_js() {
     let js_resource = ResourceFile::new(js_file.path().to_str().unwrap());
 
     let result = html::generate_html_without_reload(
-        &markdown_file.path().to_path_buf(),
+        markdown_file.path(),
         &[],
         &[js_resource],
         true, // embed resources
@@ -147,6 +164,7 @@ fn test_generate_pptx_basic() {
         title: "Test Presentation".to_string(),
         pattern: "slide_*.png".to_string(),
         aspect_ratio: "16:9".to_string(),
+        ..Default::default()
     };
 
     // Generate the PPTX
@@ -214,18 +232,24 @@ fn test_generate_slides_basic() {
     let render_config = RenderConfig {
         width: 800,
         height: 600,
+        scale: 1.0,
         format: "png".to_string(),
+        quality: None,
         base_name: "test".to_string(),
         timeout_ms: 30000,
         browser_path: None,
+        jobs: 1,
+        slide_timeout_ms: 10000,
+        expected_slides: None,
+        slides: None,
+        incremental: false,
+        min_font_px: 18.0,
+        overflow_report: None,
+        fail_on_overflow: false,
     };
 
     // Generate slides
-    let result = render::generate_slides(
-        &html_file.path().to_path_buf(),
-        &output_dir.path().to_path_buf(),
-        &render_config,
-    );
+    let result = render::generate_slides(html_file.path(), output_dir.path(), &render_config);
 
     assert!(
         result.is_ok(),
@@ -242,16 +266,16 @@ fn test_generate_slides_basic() {
 
 #[test]
 fn test_default_css_js_config_values() {
-    // Verify that Config's default CSS and JS values match the expected URLs
+    // Verify that Config's default CSS and JS come from the built-in theme
     let config = config::Config::default();
 
     assert_eq!(
-        config.default_css, "https://raw.githubusercontent.com/harperreed/big/gh-pages/big.css",
-        "Default CSS URL should match expected value"
+        config.default_css, "builtin:big.css",
+        "Default CSS should be the built-in stylesheet"
     );
     assert_eq!(
-        config.default_js, "https://raw.githubusercontent.com/harperreed/big/gh-pages/big.js",
-        "Default JS URL should match expected value"
+        config.default_js, "builtin:big.js",
+        "Default JS should be the built-in script"
     );
 
     // Create a simple markdown file
@@ -261,34 +285,55 @@ fn test_default_css_js_config_values() {
     // Create ResourceFile from default values
     let css_resource = ResourceFile::new(&config.default_css);
     let js_resource = ResourceFile::new(&config.default_js);
+    assert!(css_resource.is_builtin && !css_resource.is_remote);
 
-    // Generate HTML with the default resources explicitly provided
+    // Even in link mode the built-ins are embedded, as there is nothing to link to
     let result = html::generate_html_without_reload(
-        &markdown_file.path().to_path_buf(),
+        markdown_file.path(),
         &[css_resource],
         &[js_resource],
-        false, // Don't embed resources to make link checking easier
+        false,
     );
 
     assert!(result.is_ok());
     let html = result.unwrap();
 
-    // Verify CSS is included correctly
-    let expected_css_link = format!(r#"<link rel="stylesheet" href="{}">"#, config.default_css);
     assert!(
-        html.contains(&expected_css_link),
-        "HTML should contain default CSS link: {}",
-        expected_css_link
+        !html.contains("builtin:"),
+        "HTML should not reference the builtin scheme"
+    );
+    assert!(
+        html.contains("<style>/* Default big-slides theme"),
+        "HTML should embed the built-in CSS"
     );
-
-    // Verify JS is included correctly
-    let expected_js_link = format!(r#"<script src="{}">"#, config.default_js);
     assert!(
-        html.contains(&expected_js_link),
-        "HTML should contain default JS script tag"
+        html.contains("<script>// Default big-slides navigation"),
+        "HTML should embed the built-in JS"
     );
 }
 
+#[test]
+fn test_builtin_resources() {
+    for name in resources::builtin_names() {
+        let resource = ResourceFile::new(&format!("builtin:{}", name));
+        assert!(!resource.content().unwrap().is_empty());
+    }
+
+    let missing = ResourceFile::new("builtin:nope.css");
+    match missing.content() {
+        Err(BigError::InvalidResourcePath(message)) => assert!(message.contains("dark.css")),
+        other => panic!("Expected an unknown built-in error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_frontmatter_builtin_theme() {
+    let markdown_file = create_temp_markdown_file("---\ntheme: dark\n---\n# Hello\n");
+
+    let html = html::generate_html_without_reload(markdown_file.path(), &[], &[], true).unwrap();
+    assert!(html.contains("/* Dark variant of the default theme"));
+}
+
 #[test]
 fn test_header_format_with_space() {
     // Test the "# Header" format (with space)
@@ -296,7 +341,7 @@ fn test_header_format_with_space() {
     let markdown_file = create_temp_markdown_file(markdown_content);
 
     let result = html::generate_html_without_reload(
-        &markdown_file.path().to_path_buf(),
+        markdown_file.path(),
         &[],
         &[],
         true, // embed resources
@@ -319,7 +364,7 @@ fn test_header_format_without_space() {
     let markdown_file = create_temp_markdown_file(markdown_content);
 
     let result = html::generate_html_without_reload(
-        &markdown_file.path().to_path_buf(),
+        markdown_file.path(),
         &[],
         &[],
         true, // embed resources
@@ -350,7 +395,7 @@ fn test_header_format_complex_case() {
     let markdown_file = create_temp_markdown_file(markdown_content);
 
     let result = html::generate_html_without_reload(
-        &markdown_file.path().to_path_buf(),
+        markdown_file.path(),
         &[],
         &[],
         true, // embed resources
@@ -392,7 +437,7 @@ fn test_header_format_multiple_slides() {
     let markdown_file = create_temp_markdown_file(markdown_content);
 
     let result = html::generate_html_without_reload(
-        &markdown_file.path().to_path_buf(),
+        markdown_file.path(),
         &[],
         &[],
         true, // embed resources
@@ -427,7 +472,7 @@ fn test_slide_with_html_content() {
     let markdown_file = create_temp_markdown_file(markdown_content);
 
     let result = html::generate_html_without_reload(
-        &markdown_file.path().to_path_buf(),
+        markdown_file.path(),
         &[],
         &[],
         true, // embed resources
@@ -448,3 +493,1749 @@ fn test_slide_with_html_content() {
         "HTML should preserve ul and li tags"
     );
 }
+
+#[test]
+fn test_speaker_notes_kept_out_of_html() {
+    let markdown_content = "# First Slide\n\nVisible text.\n\n???\nRemember to smile.\n\n\
+                            # Second Slide\n\n<!-- notes: Mention the demo. -->\nMore text.\n\n\
+                            # Third Slide";
+    let markdown_file = create_temp_markdown_file(markdown_content);
+
+    let html = html::generate_html_without_reload(markdown_file.path(), &[], &[], true).unwrap();
+    assert!(html.contains("<div>First Slide\n<p>Visible text.</p></div>"));
+    assert!(html.contains("More text."));
+    assert!(
+        !html.contains("Remember to smile") && !html.contains("Mention the demo"),
+        "Speaker notes should not be rendered into the slides"
+    );
+
+    let slides = html::parse_slides(markdown_file.path()).unwrap();
+    assert_eq!(slides.len(), 3);
+    assert_eq!(slides[0].notes.as_deref(), Some("Remember to smile."));
+    assert_eq!(slides[1].notes.as_deref(), Some("Mention the demo."));
+    assert_eq!(slides[2].notes, None);
+}
+
+#[test]
+fn test_speaker_notes_stay_with_their_part_of_a_split_section() {
+    let markdown_content = "# One
+
+first body
+
+???
+
+notes for one
+
+---
+
+\
+                            second body
+
+<!-- notes: notes for two -->
+
+# Three
+
+third";
+    let markdown_file = create_temp_markdown_file(markdown_content);
+
+    let html = html::generate_html_without_reload(markdown_file.path(), &[], &[], true).unwrap();
+    assert!(html.contains("first body") && html.contains("second body"));
+    assert!(!html.contains("notes for one") && !html.contains("notes for two"));
+
+    let slides = html::parse_slides(markdown_file.path()).unwrap();
+    assert_eq!(slides.len(), 3);
+    assert_eq!(slides[0].notes.as_deref(), Some("notes for one"));
+    assert_eq!(slides[1].notes.as_deref(), Some("notes for two"));
+    assert!(slides[1].html.contains("second body"));
+    assert_eq!(slides[2].notes, None);
+}
+
+#[test]
+fn test_generate_pptx_with_notes() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let slide_dir = temp_dir.path();
+
+    for i in 1..=2 {
+        let img = image::ImageBuffer::from_fn(100, 100, |_, _| image::Rgb([255u8, 255u8, 255u8]));
+        img.save(slide_dir.join(format!("slide_{:04}.png", i)))
+            .expect("Failed to save image");
+    }
+
+    let markdown_path = slide_dir.join("slides.md");
+    fs::write(&markdown_path, "# One\n\n???\nSay hello & wave\n\n# Two\n").unwrap();
+
+    let output_path = slide_dir.join("output.pptx");
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.png".to_string(),
+        markdown_path: Some(markdown_path),
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+
+    let file = fs::File::open(&output_path).expect("Failed to open PPTX file");
+    let mut archive = zip::ZipArchive::new(file).expect("Failed to read PPTX as ZIP");
+
+    let mut notes_xml = String::new();
+    std::io::Read::read_to_string(
+        &mut archive
+            .by_name("ppt/notesSlides/notesSlide1.xml")
+            .expect("Missing notesSlide1.xml"),
+        &mut notes_xml,
+    )
+    .unwrap();
+    assert!(notes_xml.contains("<a:t>Say hello &amp; wave</a:t>"));
+
+    assert!(archive.by_name("ppt/notesSlides/notesSlide2.xml").is_err());
+    assert!(archive.by_name("ppt/notesMasters/notesMaster1.xml").is_ok());
+
+    let mut presentation_xml = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name("ppt/presentation.xml").unwrap(),
+        &mut presentation_xml,
+    )
+    .unwrap();
+    assert!(presentation_xml.contains("<p:notesMasterId r:id=\"rIdNM\"/>"));
+}
+
+#[test]
+fn test_generate_native_pptx() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let markdown_path = temp_dir.path().join("slides.md");
+    fs::write(
+        &markdown_path,
+        "# Welcome\n\n# Agenda\n\n* **Bold** point\n* See [docs](https://example.com)\n",
+    )
+    .unwrap();
+
+    let output_path = temp_dir.path().join("native.pptx");
+    let pptx_config = PptxConfig {
+        markdown_path: Some(markdown_path),
+        native: true,
+        ..Default::default()
+    };
+    // The slides directory is not used for native slides
+    pptx::generate_pptx(Path::new(""), &output_path, &pptx_config).unwrap();
+
+    let file = fs::File::open(&output_path).expect("Failed to open PPTX file");
+    let mut archive = zip::ZipArchive::new(file).expect("Failed to read PPTX as ZIP");
+
+    let mut slide_xml = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name("ppt/slides/slide2.xml").unwrap(),
+        &mut slide_xml,
+    )
+    .unwrap();
+    assert!(slide_xml.contains("<a:t>Agenda</a:t>"));
+    assert!(slide_xml.contains(r#"b="1" dirty="0"/><a:t>Bold</a:t>"#));
+    assert!(slide_xml.contains(r#"<a:hlinkClick r:id="rId1"/>"#));
+    assert!(
+        !slide_xml.contains("<p:pic>"),
+        "Native slides have no screenshots"
+    );
+
+    let mut rels_xml = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name("ppt/slides/_rels/slide2.xml.rels").unwrap(),
+        &mut rels_xml,
+    )
+    .unwrap();
+    assert!(rels_xml.contains(r#"Target="https://example.com" TargetMode="External""#));
+}
+
+/// Build a one-page PDF whose page size is inherited from its page tree
+fn single_page_pdf(width: i64) -> Vec<u8> {
+    use lopdf::{dictionary, Document, Object};
+
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+            "MediaBox" => vec![0.into(), 0.into(), width.into(), 100.into()],
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+
+    let mut data = Vec::new();
+    doc.save_to(&mut data).expect("Failed to save PDF");
+    data
+}
+
+#[test]
+fn test_merge_pdf_pages_keeps_order() {
+    let pages = vec![
+        single_page_pdf(100),
+        single_page_pdf(200),
+        single_page_pdf(300),
+    ];
+
+    let merged = render::merge_pdf_pages(&pages).expect("Failed to merge PDF pages");
+    let doc = lopdf::Document::load_mem(&merged).expect("Merged PDF should load");
+
+    let widths: Vec<i64> = doc
+        .get_pages()
+        .into_values()
+        .map(|page_id| {
+            let page = doc.get_dictionary(page_id).unwrap();
+            let media_box = match page.get(b"MediaBox") {
+                Ok(media_box) => media_box.clone(),
+                // The first page still inherits from the original page tree
+                Err(_) => {
+                    let parent = page.get(b"Parent").unwrap().as_reference().unwrap();
+                    doc.get_dictionary(parent)
+                        .unwrap()
+                        .get(b"MediaBox")
+                        .unwrap()
+                        .clone()
+                }
+            };
+            media_box.as_array().unwrap()[2].as_i64().unwrap()
+        })
+        .collect();
+
+    assert_eq!(widths, vec![100, 200, 300]);
+}
+
+#[test]
+fn test_parse_build_artifacts() {
+    use std::str::FromStr;
+
+    assert_eq!(Artifact::from_str("html").unwrap(), Artifact::Html);
+    assert_eq!(Artifact::from_str(" PPTX ").unwrap(), Artifact::Pptx);
+    assert_eq!(Artifact::from_str("png").unwrap(), Artifact::Png);
+    assert_eq!(Artifact::from_str("pdf").unwrap(), Artifact::Pdf);
+    assert!(matches!(
+        Artifact::from_str("gif"),
+        Err(BigError::ValidationError(_))
+    ));
+}
+
+#[test]
+fn test_project_config_precedence() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_path = temp_dir.path().join(config::PROJECT_CONFIG_FILE);
+    fs::write(
+        &config_path,
+        "[slides]\nwidth = 1280\nheight = 720\n\n[html]\ncss = [\"theme.css\", \"https://example.com/a.css\"]\noutput = \"out/deck.html\"\n",
+    )
+    .unwrap();
+
+    let markdown_path = temp_dir.path().join("deck.md");
+    fs::write(&markdown_path, "# Slide\n").unwrap();
+
+    let project = config::ProjectConfig::discover(Some(&markdown_path)).unwrap();
+    assert_eq!(project.path.as_deref(), Some(config_path.as_path()));
+
+    // File beats the built-in default, the command line beats the file
+    let width: u32 = project.resolve("slides.width", None, None, 1920).unwrap();
+    let height: u32 = project
+        .resolve("slides.height", Some(600), None, 1080)
+        .unwrap();
+    let format: String = project
+        .resolve("slides.format", None, None, "png".to_string())
+        .unwrap();
+    assert_eq!((width, height, format.as_str()), (1280, 600, "png"));
+
+    // The environment beats the file
+    std::env::set_var("BIG_TEST_PRECEDENCE_WIDTH", "1024");
+    let width: u32 = project
+        .resolve(
+            "slides.width",
+            None,
+            Some("BIG_TEST_PRECEDENCE_WIDTH"),
+            1920,
+        )
+        .unwrap();
+    assert_eq!(width, 1024);
+
+    // Relative paths are resolved against the config file's directory, URLs are kept
+    let css: Vec<String> = project.resolve("html.css", None, None, Vec::new()).unwrap();
+    assert_eq!(
+        css,
+        vec![
+            temp_dir
+                .path()
+                .join("theme.css")
+                .to_string_lossy()
+                .into_owned(),
+            "https://example.com/a.css".to_string()
+        ]
+    );
+
+    let resolved = project.resolved();
+    assert_eq!(resolved[0].source, config::ConfigSource::File(config_path));
+    assert_eq!(resolved[1].source, config::ConfigSource::Cli);
+    assert_eq!(resolved[2].source, config::ConfigSource::Default);
+    assert_eq!(
+        resolved[3].source,
+        config::ConfigSource::Env("BIG_TEST_PRECEDENCE_WIDTH".to_string())
+    );
+
+    // Keys nobody asked for are reported
+    assert_eq!(project.unused_keys(), vec!["html.output".to_string()]);
+}
+
+#[test]
+fn test_project_config_invalid_value() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_path = temp_dir.path().join(config::PROJECT_CONFIG_FILE);
+    fs::write(&config_path, "[slides]\nwidth = \"wide\"\n").unwrap();
+
+    let project = config::ProjectConfig::load(&config_path).unwrap();
+    let result: Result<u32> = project.resolve("slides.width", None, None, 1920);
+    assert!(matches!(result, Err(BigError::ConfigError(_))));
+}
+
+#[test]
+fn test_yaml_frontmatter_metadata() {
+    let markdown_content = "---\ntitle: Q3 & Plan\nauthor: Jane Doe\nlang: de\nkeywords: [sales, q3]\naspect-ratio: \"4:3\"\nowner: finance\n---\n# Hello\n\nWorld\n";
+    let markdown_file = create_temp_markdown_file(markdown_content);
+
+    let deck = html::parse_deck(markdown_file.path()).unwrap();
+    assert_eq!(deck.metadata.title.as_deref(), Some("Q3 & Plan"));
+    assert_eq!(deck.metadata.aspect_ratio.as_deref(), Some("4:3"));
+    assert_eq!(deck.metadata.keywords, vec!["sales", "q3"]);
+    assert_eq!(
+        deck.metadata.extra,
+        vec![("owner".to_string(), "finance".to_string())]
+    );
+    assert_eq!(deck.slides.len(), 1);
+
+    let html = html::generate_html_without_reload(markdown_file.path(), &[], &[], true).unwrap();
+    assert!(html.contains("<html lang=\"de\">"));
+    assert!(html.contains("<title>Q3 &amp; Plan</title>"));
+    assert!(html.contains("<meta name=\"author\" content=\"Jane Doe\">"));
+    assert!(html.contains("<meta name=\"keywords\" content=\"sales, q3\">"));
+    assert!(html.contains("<meta name=\"owner\" content=\"finance\">"));
+    assert!(!html.contains("title:"));
+}
+
+#[test]
+fn test_toml_frontmatter_metadata() {
+    let markdown_content =
+        "+++\ntitle = \"Roadmap\"\ndescription = \"Next year\"\ntags = \"a, b\"\n+++\n# Hello\n";
+    let markdown_file = create_temp_markdown_file(markdown_content);
+
+    let deck = html::parse_deck(markdown_file.path()).unwrap();
+    assert_eq!(deck.metadata.title.as_deref(), Some("Roadmap"));
+    assert_eq!(deck.metadata.description.as_deref(), Some("Next year"));
+    assert_eq!(deck.metadata.keywords, vec!["a", "b"]);
+    assert_eq!(deck.slides.len(), 1);
+}
+
+#[test]
+fn test_leading_rule_is_not_frontmatter() {
+    // A deck that starts with a slide separator keeps all of its slides
+    let markdown_content = "---\n# One\n\n---\n\nTwo\n";
+    let markdown_file = create_temp_markdown_file(markdown_content);
+
+    let deck = html::parse_deck(markdown_file.path()).unwrap();
+    assert_eq!(deck.metadata, html::DeckMetadata::default());
+    assert!(deck.slides.iter().any(|slide| slide.html.contains("One")));
+    assert!(deck.slides.iter().any(|slide| slide.html.contains("Two")));
+}
+
+#[test]
+fn test_pptx_core_properties_from_frontmatter() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let slide_dir = temp_dir.path();
+
+    let img = image::ImageBuffer::from_fn(100, 100, |_, _| image::Rgb([255u8, 255u8, 255u8]));
+    img.save(slide_dir.join("slide_0001.png"))
+        .expect("Failed to save image");
+
+    let markdown_path = slide_dir.join("slides.md");
+    fs::write(
+        &markdown_path,
+        "---\nauthor: Jane <Doe>\ndescription: Numbers\nkeywords: sales, q3\n---\n# One\n",
+    )
+    .unwrap();
+
+    let output_path = slide_dir.join("output.pptx");
+    let pptx_config = PptxConfig {
+        title: "Review".to_string(),
+        pattern: "slide_*.png".to_string(),
+        markdown_path: Some(markdown_path),
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+
+    let file = fs::File::open(&output_path).expect("Failed to open PPTX file");
+    let mut archive = zip::ZipArchive::new(file).expect("Failed to read PPTX as ZIP");
+    let mut core_xml = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name("docProps/core.xml").unwrap(),
+        &mut core_xml,
+    )
+    .unwrap();
+
+    assert!(core_xml.contains("<dc:title>Review</dc:title>"));
+    assert!(core_xml.contains("<dc:creator>Jane &lt;Doe&gt;</dc:creator>"));
+    assert!(core_xml.contains("<dc:description>Numbers</dc:description>"));
+    assert!(core_xml.contains("<cp:keywords>sales, q3</cp:keywords>"));
+    assert!(core_xml.contains("<dcterms:modified xsi:type=\"dcterms:W3CDTF\">"));
+}
+
+#[test]
+fn test_resource_cache_roundtrip() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let cache = cache::ResourceCache::new(temp_dir.path().join("resources"));
+    assert!(cache.entries().unwrap().is_empty());
+
+    let entry = cache::CacheEntry {
+        url: "https://example.com/big.css".to_string(),
+        etag: Some("\"v1\"".to_string()),
+        last_modified: Some("Tue, 01 Oct 2024 10:00:00 GMT".to_string()),
+        fetched_at: chrono::Utc::now(),
+        size: 4,
+    };
+    cache.put(&entry, b"body").unwrap();
+
+    let (cached, content) = cache.get(&entry.url).expect("Entry should be cached");
+    assert_eq!(content, b"body");
+    assert_eq!(cached.etag, entry.etag);
+    assert!(cached.is_fresh());
+    assert!(cache.get("https://example.com/other.css").is_none());
+
+    // Old entries are kept but need revalidating
+    let stale = cache::CacheEntry {
+        fetched_at: chrono::Utc::now() - chrono::Duration::hours(cache::MAX_AGE_HOURS + 1),
+        ..entry
+    };
+    cache.update(&stale).unwrap();
+    assert!(!cache.get(&stale.url).unwrap().0.is_fresh());
+
+    assert_eq!(cache.clear().unwrap(), 1);
+    assert!(cache.get(&stale.url).is_none());
+}
+
+#[test]
+fn test_inline_css_references() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let dir = temp_dir.path();
+    fs::write(dir.join("dot.gif"), b"GIF89a").unwrap();
+    fs::write(dir.join("big.gif"), vec![0u8; 2048]).unwrap();
+    fs::write(dir.join("base.css"), "h1 { background: url('dot.gif') }").unwrap();
+
+    let inliner = inline::Inliner::new(&inline::InlineConfig { max_bytes: 1024 });
+    let location = inline::Location::Dir(dir.to_path_buf());
+    let css = inliner.inline_css(
+        "@import \"base.css\";\np { background: url(dot.gif) }\n.big { background: url(big.gif) }\n.anchor { fill: url(#gradient) }\n.missing { background: url(\"missing.gif\") }",
+        &location,
+    );
+
+    // Imported stylesheets are inlined along with their own references
+    assert!(css.starts_with("@import url(\"data:text/css;base64,"));
+    assert!(css.contains("p { background: url(\"data:image/gif;base64,R0lGODlh\") }"));
+    assert!(css.contains(".big { background: url(big.gif) }"));
+    assert!(css.contains(".anchor { fill: url(#gradient) }"));
+    assert!(css.contains(".missing { background: url(\"missing.gif\") }"));
+
+    let html = inliner.inline_html(
+        "<p><img src=\"dot.gif\" alt=\"dot\" /><img data-src=\"x\" src=\"big.gif\"></p>",
+        &location,
+    );
+    assert_eq!(
+        html,
+        "<p><img src=\"data:image/gif;base64,R0lGODlh\" alt=\"dot\" /><img data-src=\"x\" src=\"big.gif\"></p>"
+    );
+}
+
+#[test]
+fn test_split_slide_range() {
+    assert_eq!(render::split_range(10, 1), vec![0..10]);
+    assert_eq!(render::split_range(10, 3), vec![0..4, 4..7, 7..10]);
+    assert_eq!(render::split_range(2, 4), vec![0..1, 1..2]);
+    assert!(render::split_range(0, 2).is_empty());
+
+    // Shares cover every slide exactly once, in order
+    let ranges = render::split_range(121, 8);
+    let slides: Vec<i64> = ranges.into_iter().flatten().collect();
+    assert_eq!(slides, (0..121).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_slide_selection() {
+    let selection: render::SlideSelection = "3,5, 10-12".parse().unwrap();
+    assert_eq!(selection.to_string(), "3,5,10-12");
+    assert!(selection.contains(11));
+    assert!(!selection.contains(4));
+
+    // Indices are zero-based and in deck order, whatever order they were given in
+    let selection: render::SlideSelection = "8-9,2".parse().unwrap();
+    assert_eq!(selection.indices(10).unwrap(), vec![1, 7, 8]);
+    assert!(selection.indices(8).is_err());
+
+    for invalid in ["", "0", "5-3", "a-b", "1,,2"] {
+        assert!(
+            invalid.parse::<render::SlideSelection>().is_err(),
+            "{:?} should not parse",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn test_splice_slide_images() {
+    let images: Vec<PathBuf> = (1..=4)
+        .map(|n| PathBuf::from(format!("full/slide_{:04}.png", n)))
+        .collect();
+    let updates = vec![
+        PathBuf::from("review/slide_0002.png"),
+        PathBuf::from("review/slide_0005.png"),
+    ];
+
+    let spliced = pptx::splice_slide_images(images, updates).unwrap();
+    assert_eq!(
+        spliced,
+        vec![
+            PathBuf::from("full/slide_0001.png"),
+            PathBuf::from("review/slide_0002.png"),
+            PathBuf::from("full/slide_0003.png"),
+            PathBuf::from("full/slide_0004.png"),
+            PathBuf::from("review/slide_0005.png"),
+        ]
+    );
+
+    // Without slide numbers there is nothing to match on
+    assert!(pptx::splice_slide_images(
+        vec![PathBuf::from("full/cover.png")],
+        vec![PathBuf::from("review/slide_0001.png")]
+    )
+    .is_err());
+}
+
+#[test]
+fn test_slide_hashes() {
+    use crate::manifest::{DeckSources, SlideSource};
+
+    let temp_dir = TempDir::new().unwrap();
+    let image_path = temp_dir.path().join("photo.png");
+    fs::write(&image_path, "first").unwrap();
+    let image_url = url::Url::from_file_path(&image_path).unwrap().to_string();
+
+    let slide = |html: &str, resources: Vec<String>| SlideSource {
+        html: html.to_string(),
+        resources,
+    };
+    let deck = DeckSources {
+        shared: vec!["<head><style>body { color: red; }</style></head>".to_string()],
+        resources: Vec::new(),
+        slides: vec![
+            slide("<div>One</div>", Vec::new()),
+            slide("<div><img src=\"photo.png\"></div>", vec![image_url]),
+        ],
+    };
+    let hashes = deck.slide_hashes("1920x1080 png");
+    assert_eq!(hashes, deck.slide_hashes("1920x1080 png"));
+    assert_ne!(hashes[0], hashes[1]);
+
+    // Editing a slide only changes its own hash
+    let mut edited = deck.clone();
+    edited.slides[0].html = "<div>Uno</div>".to_string();
+    let edited_hashes = edited.slide_hashes("1920x1080 png");
+    assert_ne!(edited_hashes[0], hashes[0]);
+    assert_eq!(edited_hashes[1], hashes[1]);
+
+    // Shared styles and render settings change every slide
+    let mut restyled = deck.clone();
+    restyled.shared[0] = "<head><style>body { color: blue; }</style></head>".to_string();
+    for (new, old) in restyled.slide_hashes("1920x1080 png").iter().zip(&hashes) {
+        assert_ne!(new, old);
+    }
+    assert_ne!(deck.slide_hashes("1280x720 png")[0], hashes[0]);
+
+    // A local image is hashed by content, not just by name
+    fs::write(&image_path, "second").unwrap();
+    let rehashed = deck.slide_hashes("1920x1080 png");
+    assert_eq!(rehashed[0], hashes[0]);
+    assert_ne!(rehashed[1], hashes[1]);
+}
+
+#[test]
+fn test_slide_manifest() {
+    use crate::manifest::SlideManifest;
+
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    assert_eq!(SlideManifest::load(dir), SlideManifest::default());
+
+    for name in [
+        "slide_0001.png",
+        "slide_0002.png",
+        "slide_0003.png",
+        "notes.txt",
+    ] {
+        fs::write(dir.join(name), "image").unwrap();
+    }
+    let mut manifest = SlideManifest::default();
+    for (name, hash) in [
+        ("slide_0001.png", "a"),
+        ("slide_0002.png", "b"),
+        ("slide_0003.png", "c"),
+    ] {
+        manifest.slides.insert(name.to_string(), hash.to_string());
+    }
+    manifest.save(dir).unwrap();
+    let mut manifest = SlideManifest::load(dir);
+    assert_eq!(manifest.slides.len(), 3);
+
+    assert!(manifest.is_current(dir, "slide_0001.png", "a"));
+    assert!(!manifest.is_current(dir, "slide_0001.png", "b"));
+    fs::remove_file(dir.join("slide_0002.png")).unwrap();
+    assert!(!manifest.is_current(dir, "slide_0002.png", "b"));
+
+    // Images of removed slides are deleted; files the manifest doesn't know are left alone
+    let keep = vec!["slide_0001.png".to_string(), "slide_0002.png".to_string()];
+    let removed = manifest.remove_stale(dir, &keep).unwrap();
+    assert_eq!(removed, vec![dir.join("slide_0003.png")]);
+    assert!(!dir.join("slide_0003.png").exists());
+    assert!(dir.join("notes.txt").exists());
+    assert_eq!(manifest.slides.len(), 2);
+}
+
+#[test]
+fn test_pptx_places_hidpi_images_at_slide_size() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let slide_dir = temp_dir.path();
+
+    // The same slide at 1x and 2x
+    image::ImageBuffer::from_fn(160, 90, |_, _| image::Rgb([255u8, 0u8, 0u8]))
+        .save(slide_dir.join("slide_0001.png"))
+        .unwrap();
+    image::ImageBuffer::from_fn(320, 180, |_, _| image::Rgb([255u8, 0u8, 0u8]))
+        .save(slide_dir.join("slide_0002.png"))
+        .unwrap();
+
+    let output_path = slide_dir.join("output.pptx");
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.png".to_string(),
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+
+    let file = fs::File::open(&output_path).unwrap();
+    let mut archive = zip::ZipArchive::new(file).unwrap();
+    for slide in ["ppt/slides/slide1.xml", "ppt/slides/slide2.xml"] {
+        let mut xml = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name(slide).unwrap(), &mut xml).unwrap();
+        assert!(
+            xml.contains(r#"<a:ext cx="9144000" cy="5143500"/>"#),
+            "{} is not full-slide: {}",
+            slide,
+            xml
+        );
+    }
+}
+
+#[test]
+fn test_image_formats() {
+    use crate::images::ImageFormat;
+
+    assert_eq!("PNG".parse::<ImageFormat>().unwrap(), ImageFormat::Png);
+    assert_eq!("jpg".parse::<ImageFormat>().unwrap(), ImageFormat::Jpeg);
+    assert_eq!("webp".parse::<ImageFormat>().unwrap(), ImageFormat::Webp);
+    assert_eq!("avif".parse::<ImageFormat>().unwrap(), ImageFormat::Avif);
+    let err = "gif".parse::<ImageFormat>().unwrap_err();
+    assert!(err.to_string().contains("Unknown image format 'gif'"));
+
+    assert!(!ImageFormat::Png.is_lossy());
+    assert!(ImageFormat::Webp.is_lossy());
+    assert!(ImageFormat::Jpeg.is_pptx_compatible());
+    assert!(!ImageFormat::Avif.is_pptx_compatible());
+}
+
+#[test]
+fn test_encode_avif() {
+    let mut png = Vec::new();
+    image::DynamicImage::ImageRgb8(image::ImageBuffer::from_fn(32, 18, |x, _| {
+        image::Rgb([x as u8 * 8, 0, 255])
+    }))
+    .write_to(
+        &mut std::io::Cursor::new(&mut png),
+        image::ImageOutputFormat::Png,
+    )
+    .unwrap();
+
+    let avif = crate::images::encode_avif(&png, 60).unwrap();
+    assert_eq!(&avif[4..12], b"ftypavif");
+}
+
+#[test]
+fn test_pptx_transcodes_webp_slides() {
+    use base64::Engine;
+
+    let temp_dir = TempDir::new().unwrap();
+    let slide_dir = temp_dir.path();
+
+    // A 1x1 lossless WebP
+    let webp = base64::engine::general_purpose::STANDARD
+        .decode("UklGRhoAAABXRUJQVlA4TA0AAAAvAAAAEAcQERGIiP4HAA==")
+        .unwrap();
+    fs::write(slide_dir.join("slide_0001.webp"), webp).unwrap();
+
+    let output_path = slide_dir.join("output.pptx");
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.webp".to_string(),
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+
+    let file = fs::File::open(&output_path).unwrap();
+    let mut archive = zip::ZipArchive::new(file).unwrap();
+    let mut png = Vec::new();
+    std::io::Read::read_to_end(
+        &mut archive.by_name("ppt/media/image1.png").unwrap(),
+        &mut png,
+    )
+    .unwrap();
+    assert_eq!(image::guess_format(&png).unwrap(), image::ImageFormat::Png);
+    assert!(archive.by_name("ppt/media/image1.webp").is_err());
+
+    // AVIF without its PNG copy can't be converted, which is an error rather than a missing slide
+    fs::write(slide_dir.join("slide_0002.avif"), b"not decodable").unwrap();
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.avif".to_string(),
+        ..Default::default()
+    };
+    let err = pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap_err();
+    assert!(err.to_string().contains("AVIF"));
+}
+
+#[test]
+fn test_pptx_packages_avif_slides_from_png_copy() {
+    let temp_dir = TempDir::new().unwrap();
+    let slide_dir = temp_dir.path();
+
+    let mut png = Vec::new();
+    image::DynamicImage::ImageRgb8(image::ImageBuffer::from_fn(32, 18, |x, _| {
+        image::Rgb([x as u8 * 8, 0, 255])
+    }))
+    .write_to(
+        &mut std::io::Cursor::new(&mut png),
+        image::ImageOutputFormat::Png,
+    )
+    .unwrap();
+    for slide in ["slide_0001.avif", "slide_0002.avif"] {
+        crate::images::save_avif_slide(&png, &slide_dir.join(slide), 60).unwrap();
+    }
+    assert!(slide_dir.join(".big-png/slide_0001.png").is_file());
+
+    let output_path = slide_dir.join("output.pptx");
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.avif".to_string(),
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+
+    let file = fs::File::open(&output_path).unwrap();
+    let mut archive = zip::ZipArchive::new(file).unwrap();
+    for slide in 1..=2 {
+        let mut packaged = Vec::new();
+        std::io::Read::read_to_end(
+            &mut archive
+                .by_name(&format!("ppt/media/image{}.png", slide))
+                .unwrap(),
+            &mut packaged,
+        )
+        .unwrap();
+        assert_eq!(packaged, png);
+    }
+    assert!(archive.by_name("ppt/media/image1.avif").is_err());
+}
+
+#[test]
+fn test_render_session_checks_input_before_launching() {
+    // A missing deck fails without starting a browser, so this runs without Chrome
+    let output_dir = TempDir::new().unwrap();
+    let mut session = render::RenderSession::new();
+    let missing = output_dir.path().join("missing.html");
+
+    let result = session.generate_slides(&missing, output_dir.path(), &RenderConfig::default());
+    assert!(matches!(result, Err(BigError::PathNotFoundError(_))));
+
+    let result = session.generate_pdf(
+        &missing,
+        &output_dir.path().join("deck.pdf"),
+        &RenderConfig::default(),
+    );
+    assert!(matches!(result, Err(BigError::PathNotFoundError(_))));
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires Chrome to be installed
+fn test_render_session_renders_repeatedly() {
+    let html_file = create_temp_html_file(
+        "<!DOCTYPE html><html><body><div>One</div><div>Two</div></body></html>",
+    );
+    let output_dir = TempDir::new().unwrap();
+    let render_config = RenderConfig {
+        width: 400,
+        height: 300,
+        ..Default::default()
+    };
+
+    // The second render reuses the browser started by the first
+    let mut session = render::RenderSession::new();
+    for _ in 0..2 {
+        let files = session
+            .generate_slides(html_file.path(), output_dir.path(), &render_config)
+            .unwrap();
+        assert_eq!(files.len(), 2);
+    }
+
+    let pdf = output_dir.path().join("deck.pdf");
+    assert_eq!(
+        session
+            .generate_pdf(html_file.path(), &pdf, &render_config)
+            .unwrap(),
+        2
+    );
+}
+
+#[test]
+fn test_doctor_checks_output_dirs() {
+    use crate::doctor::{check_output_dir, CheckStatus};
+
+    let temp_dir = TempDir::new().unwrap();
+    let check = check_output_dir(temp_dir.path());
+    assert_eq!(check.status, CheckStatus::Ok);
+    assert!(check.detail.contains("is writable"));
+
+    // Missing directories are checked where they will be created
+    let check = check_output_dir(&temp_dir.path().join("a/b"));
+    assert_eq!(check.status, CheckStatus::Ok);
+    assert!(check.detail.contains("will be created"));
+
+    let file = temp_dir.path().join("file");
+    fs::write(&file, "").unwrap();
+    let check = check_output_dir(&file);
+    assert_eq!(check.status, CheckStatus::Failed);
+    assert!(check.fix.is_some());
+
+    // The probe file doesn't stay behind
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_doctor_reports_missing_browser_and_uncached_resources() {
+    use crate::doctor::{check_browser_setting, check_remote_resource, CheckStatus};
+
+    let check = check_browser_setting(Some("/nonexistent/chrome"));
+    assert_eq!(check.status, CheckStatus::Failed);
+    assert!(check.detail.contains("/nonexistent/chrome"));
+    assert!(check.fix.unwrap().contains("--browser-path"));
+
+    // Offline, only the cache is consulted
+    let url = format!("https://example.invalid/{}.css", uuid::Uuid::new_v4());
+    let check = check_remote_resource(&url, true, std::time::Duration::from_secs(1));
+    assert_eq!(check.status, CheckStatus::Failed);
+    assert!(check.detail.contains("not cached"));
+}
+
+#[test]
+fn test_overflow_report() {
+    use crate::overflow::{LayoutIssue, Overflow, OverflowReport, SlideLayout, SlideReport};
+
+    let fits = SlideLayout {
+        // Sub-pixel rounding is not overflow
+        overflow: Overflow {
+            right: 0.5,
+            ..Default::default()
+        },
+        clipped: Vec::new(),
+        smallest_font_px: Some(24.0),
+    };
+    assert!(fits.issues(18.0).is_empty());
+    assert_eq!(fits.issues(30.0), vec![LayoutIssue::SmallFont]);
+
+    let spills = SlideLayout {
+        overflow: Overflow {
+            bottom: 120.0,
+            ..Default::default()
+        },
+        clipped: vec!["pre.code".to_string()],
+        smallest_font_px: Some(12.0),
+    };
+    let issues = spills.issues(18.0);
+    assert_eq!(
+        issues,
+        vec![
+            LayoutIssue::Overflow,
+            LayoutIssue::Clipped,
+            LayoutIssue::SmallFont
+        ]
+    );
+    assert_eq!(
+        spills.describe(&issues),
+        "content overflows (120px past the bottom); content is clipped in pre.code; text is 12.0px"
+    );
+
+    let report = OverflowReport {
+        width: 1920,
+        height: 1080,
+        min_font_px: 30.0,
+        slides: vec![
+            SlideReport {
+                slide: 1,
+                file: "slide_0001.png".to_string(),
+                issues: fits.issues(30.0),
+                layout: fits,
+            },
+            SlideReport {
+                slide: 2,
+                file: "slide_0002.png".to_string(),
+                issues,
+                layout: spills,
+            },
+        ],
+    };
+
+    // Small text alone doesn't count as overflow
+    let overflowing: Vec<usize> = report.overflowing().iter().map(|s| s.slide).collect();
+    assert_eq!(overflowing, vec![2]);
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("report.json");
+    report.write(&path).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json["slides"][1]["issues"][1], "clipped");
+    assert_eq!(json["slides"][1]["overflow"]["bottom"], 120.0);
+    assert_eq!(json["slides"][1]["clipped"][0], "pre.code");
+    let read_back: OverflowReport =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(read_back, report);
+}
+
+#[test]
+fn test_diff_images_scores_changed_pixels() {
+    use crate::compare::diff_images;
+    use image::{Rgba, RgbaImage};
+
+    let baseline = RgbaImage::from_pixel(10, 10, Rgba([255, 255, 255, 255]));
+    let mut current = baseline.clone();
+
+    // Noise within the tolerance doesn't count
+    current.put_pixel(0, 0, Rgba([250, 250, 250, 255]));
+    let (score, _) = diff_images(&baseline, &current, 8);
+    assert_eq!(score, 0.0);
+
+    current.put_pixel(5, 5, Rgba([0, 0, 0, 255]));
+    let (score, diff) = diff_images(&baseline, &current, 8);
+    assert_eq!(score, 1.0);
+    assert_eq!(*diff.get_pixel(5, 5), Rgba([255, 0, 0, 255]));
+    assert_ne!(*diff.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+}
+
+#[test]
+fn test_compare_slides_pairs_images_by_name() {
+    use crate::compare::{compare_slides, CompareConfig, Difference};
+    use image::{Rgba, RgbaImage};
+
+    let temp_dir = TempDir::new().unwrap();
+    let baseline_dir = temp_dir.path().join("baseline");
+    let current_dir = temp_dir.path().join("current");
+    let diff_dir = temp_dir.path().join("diff");
+    fs::create_dir_all(&baseline_dir).unwrap();
+    fs::create_dir_all(&current_dir).unwrap();
+
+    let white = RgbaImage::from_pixel(4, 4, Rgba([255, 255, 255, 255]));
+    let mut changed = white.clone();
+    changed.put_pixel(1, 1, Rgba([0, 0, 255, 255]));
+    let save = |image: &RgbaImage, path: PathBuf| image.save(path).unwrap();
+
+    save(&white, baseline_dir.join("slide_0001.png"));
+    save(&white, current_dir.join("slide_0001.png"));
+    save(&white, baseline_dir.join("slide_0002.png"));
+    save(&changed, current_dir.join("slide_0002.png"));
+    save(&white, baseline_dir.join("slide_0003.png"));
+    save(&RgbaImage::new(8, 4), current_dir.join("slide_0003.png"));
+    save(&white, baseline_dir.join("slide_0004.png"));
+    save(&white, current_dir.join("slide_0005.png"));
+
+    // A diff image from an earlier run doesn't survive
+    fs::create_dir_all(&diff_dir).unwrap();
+    fs::write(diff_dir.join("slide_0001.diff.png"), "stale").unwrap();
+
+    let config = CompareConfig {
+        baseline_dir,
+        current_dir,
+        diff_dir: Some(diff_dir.clone()),
+        ..CompareConfig::default()
+    };
+    let comparisons = compare_slides(&config).unwrap();
+
+    let files: Vec<&str> = comparisons.iter().map(|c| c.file.as_str()).collect();
+    assert_eq!(
+        files,
+        [
+            "slide_0001.png",
+            "slide_0002.png",
+            "slide_0003.png",
+            "slide_0004.png",
+            "slide_0005.png"
+        ]
+    );
+    assert_eq!(comparisons[0].difference, Difference::Pixels { score: 0.0 });
+    assert!(!comparisons[0].exceeds(config.threshold));
+    assert!(comparisons[0].diff_image.is_none());
+    assert!(!diff_dir.join("slide_0001.diff.png").exists());
+
+    assert_eq!(
+        comparisons[1].difference,
+        Difference::Pixels { score: 6.25 }
+    );
+    assert!(comparisons[1].exceeds(config.threshold));
+    assert_eq!(
+        comparisons[1].diff_image.as_deref(),
+        Some(diff_dir.join("slide_0002.diff.png").as_path())
+    );
+    assert!(diff_dir.join("slide_0002.diff.png").exists());
+
+    assert_eq!(
+        comparisons[2].difference,
+        Difference::Resized {
+            baseline: (4, 4),
+            current: (8, 4)
+        }
+    );
+    assert_eq!(comparisons[3].difference, Difference::Removed);
+    assert_eq!(comparisons[4].difference, Difference::Added);
+    assert!(comparisons[2..].iter().all(|c| c.exceeds(99.0)));
+}
+
+#[test]
+fn test_parse_slide_transitions() {
+    use crate::transition::{parse_duration, SlideTransition, TransitionEffect, TransitionSpeed};
+    use std::time::Duration;
+
+    assert_eq!(parse_duration("5s").unwrap(), Duration::from_secs(5));
+    assert_eq!(parse_duration("1.5").unwrap(), Duration::from_millis(1500));
+    assert_eq!(
+        parse_duration(" 500ms ").unwrap(),
+        Duration::from_millis(500)
+    );
+    assert!(parse_duration("-1s").is_err());
+    assert!(parse_duration("soon").is_err());
+
+    let transition = SlideTransition::parse_comment(" transition: Fade; speed: fast; advance: 5s ")
+        .unwrap()
+        .unwrap();
+    assert_eq!(transition.effect, Some(TransitionEffect::Fade));
+    assert_eq!(transition.speed, Some(TransitionSpeed::Fast));
+    assert_eq!(transition.advance_after, Some(Some(Duration::from_secs(5))));
+
+    let transition = SlideTransition::parse_comment("advance: off")
+        .unwrap()
+        .unwrap();
+    assert_eq!(transition.effect, None);
+    assert_eq!(transition.advance_after, Some(None));
+
+    assert!(SlideTransition::parse_comment(" just a comment ").is_none());
+    assert!(SlideTransition::parse_comment("transition: spin")
+        .unwrap()
+        .is_err());
+    assert!(
+        SlideTransition::parse_comment("transition: fade; loop: yes")
+            .unwrap()
+            .is_err()
+    );
+
+    // Each slide of a section split by a rule keeps its own comment
+    let markdown = create_temp_markdown_file(
+        "# One\n<!-- transition: wipe -->\n<!-- a note to self -->\n\n---\n\n<!-- advance: 2s -->\nTwo\n",
+    );
+    let deck = html::parse_deck(markdown.path()).unwrap();
+    assert_eq!(
+        deck.slides[0].transition.effect,
+        Some(TransitionEffect::Wipe)
+    );
+    assert!(!deck.slides[0].markdown.contains("transition:"));
+    assert!(deck.slides[0].markdown.contains("<!-- a note to self -->"));
+    assert_eq!(deck.slides[1].transition.effect, None);
+    assert_eq!(
+        deck.slides[1].transition.advance_after,
+        Some(Some(Duration::from_secs(2)))
+    );
+}
+
+#[test]
+fn test_generate_pptx_with_transitions() {
+    use crate::transition::{Transition, TransitionEffect, TransitionSpeed};
+    use std::time::Duration;
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let slide_dir = temp_dir.path();
+
+    for i in 1..=4 {
+        let img = image::ImageBuffer::from_fn(100, 100, |_, _| image::Rgb([255u8, 255u8, 255u8]));
+        img.save(slide_dir.join(format!("slide_{:04}.png", i)))
+            .expect("Failed to save image");
+    }
+
+    let markdown_path = slide_dir.join("slides.md");
+    fs::write(
+        &markdown_path,
+        "# One\n\n# Two\n<!-- transition: push; speed: slow; advance: 10s -->\n\n# Three\n<!-- advance: click -->\n\n# Four\n<!-- transition: none -->\n",
+    )
+    .unwrap();
+
+    let output_path = slide_dir.join("output.pptx");
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.png".to_string(),
+        markdown_path: Some(markdown_path),
+        transition: Transition {
+            effect: TransitionEffect::Fade,
+            speed: TransitionSpeed::Fast,
+            advance_after: Some(Duration::from_secs(5)),
+        },
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+
+    let file = fs::File::open(&output_path).expect("Failed to open PPTX file");
+    let mut archive = zip::ZipArchive::new(file).expect("Failed to read PPTX as ZIP");
+    let mut slide_xml = |slide_num: usize| {
+        let mut xml = String::new();
+        std::io::Read::read_to_string(
+            &mut archive
+                .by_name(&format!("ppt/slides/slide{}.xml", slide_num))
+                .unwrap(),
+            &mut xml,
+        )
+        .unwrap();
+        xml
+    };
+
+    let xml = slide_xml(1);
+    assert!(xml.contains(r#"<p:transition spd="fast" advTm="5000"><p:fade/></p:transition>"#));
+    // The transition follows the colour mapping, as the schema requires
+    assert!(xml.find("</p:clrMapOvr>").unwrap() < xml.find("<p:transition").unwrap());
+
+    assert!(
+        slide_xml(2).contains(r#"<p:transition spd="slow" advTm="10000"><p:push/></p:transition>"#)
+    );
+    assert!(slide_xml(3).contains(r#"<p:transition spd="fast"><p:fade/></p:transition>"#));
+    assert!(slide_xml(4).contains(r#"<p:transition spd="fast" advTm="5000"></p:transition>"#));
+
+    // Without transitions or timing, slides have no transition element
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.png".to_string(),
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+    let file = fs::File::open(&output_path).unwrap();
+    let mut archive = zip::ZipArchive::new(file).unwrap();
+    let mut xml = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name("ppt/slides/slide1.xml").unwrap(),
+        &mut xml,
+    )
+    .unwrap();
+    assert!(!xml.contains("<p:transition"));
+}
+
+#[test]
+fn test_generate_pptx_with_link_overlays() {
+    use crate::links::{LinkArea, LinkManifest, LINKS_FILE};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let slide_dir = temp_dir.path();
+    for i in 1..=2 {
+        let img = image::ImageBuffer::from_fn(100, 100, |_, _| image::Rgb([255u8, 255u8, 255u8]));
+        img.save(slide_dir.join(format!("slide_{:04}.png", i)))
+            .expect("Failed to save image");
+    }
+
+    let area = |href: &str, left: f64, top: f64| LinkArea {
+        href: href.to_string(),
+        left,
+        top,
+        width: 960.0,
+        height: 108.0,
+    };
+    let mut links = LinkManifest {
+        width: 1920,
+        height: 1080,
+        ..Default::default()
+    };
+    links.set(
+        "slide_0001.png",
+        vec![
+            // A link wrapped over two lines shares one relationship
+            area("https://github.com/tmcw/big", 0.0, 540.0),
+            area("https://github.com/tmcw/big", 960.0, 648.0),
+            area("mailto:hi@example.com?a=1&b=2", 0.0, 0.0),
+            area("file:///tmp/deck.html#3", 0.0, 0.0),
+        ],
+    );
+    links.set("slide_0002.png", Vec::new());
+    links.save(slide_dir).unwrap();
+    assert_eq!(LinkManifest::load(slide_dir).unwrap(), links);
+
+    let output_path = slide_dir.join("output.pptx");
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.png".to_string(),
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+
+    let file = fs::File::open(&output_path).expect("Failed to open PPTX file");
+    let mut archive = zip::ZipArchive::new(file).expect("Failed to read PPTX as ZIP");
+    let mut read = |name: &str| {
+        let mut xml = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name(name).unwrap(), &mut xml).unwrap();
+        xml
+    };
+
+    let slide_xml = read("ppt/slides/slide1.xml");
+    assert_eq!(
+        slide_xml.matches("<a:hlinkClick r:id=\"rId2\"/>").count(),
+        2
+    );
+    assert_eq!(
+        slide_xml.matches("<a:hlinkClick r:id=\"rId3\"/>").count(),
+        1
+    );
+    assert!(!slide_xml.contains("file:///"));
+    // Half way across and down the 16:9 slide, half as wide and a tenth as high
+    assert!(slide_xml.contains(r#"<a:off x="0" y="2571750"/>"#));
+    assert!(slide_xml.contains(r#"<a:off x="4572000" y="3086100"/>"#));
+    assert!(slide_xml.contains(r#"<a:ext cx="4572000" cy="514350"/>"#));
+
+    let rels = read("ppt/slides/_rels/slide1.xml.rels");
+    assert!(rels.contains(r#"Target="https://github.com/tmcw/big" TargetMode="External""#));
+    assert!(rels.contains(r#"Target="mailto:hi@example.com?a=1&amp;b=2" TargetMode="External""#));
+
+    assert!(!read("ppt/slides/slide2.xml").contains("hlinkClick"));
+
+    // A manifest without links is removed rather than left behind
+    LinkManifest::default().save(slide_dir).unwrap();
+    assert!(!slide_dir.join(LINKS_FILE).exists());
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires Chrome to be installed
+fn test_generate_slides_records_links() {
+    use crate::links::LinkManifest;
+
+    let html_file = create_temp_html_file(
+        r#"<!DOCTYPE html><html><body style="margin:0">
+<div><a href="https://example.com/" style="position:absolute;left:100px;top:50px;font-size:20px">Example</a></div>
+<div>No links</div>
+</body></html>"#,
+    );
+    let output_dir = TempDir::new().unwrap();
+    let render_config = RenderConfig {
+        width: 400,
+        height: 300,
+        ..Default::default()
+    };
+    render::generate_slides(html_file.path(), output_dir.path(), &render_config).unwrap();
+
+    let links = LinkManifest::load(output_dir.path()).expect("Missing link manifest");
+    assert_eq!((links.width, links.height), (400, 300));
+    let areas = &links.slides["slide_0001.png"];
+    assert_eq!(areas.len(), 1);
+    assert_eq!(areas[0].href, "https://example.com/");
+    assert!((areas[0].left - 100.0).abs() < 1.0);
+    assert!(!links.slides.contains_key("slide_0002.png"));
+}
+
+#[test]
+fn test_slide_text_for_accessibility() {
+    use crate::native::slide_text;
+
+    let text = slide_text(
+        "# JS+CSS [github.com/ tmcw/ big](https://github.com/tmcw/big)\n\n* **Fast**\n  to write\n\n![A cat](cat.png)\n",
+    );
+    assert_eq!(text.title.as_deref(), Some("JS+CSS github.com/ tmcw/ big"));
+    assert_eq!(text.body, "Fast to write\nA cat");
+    assert_eq!(
+        text.description(),
+        "JS+CSS github.com/ tmcw/ big\nFast to write\nA cat"
+    );
+
+    // Slides without a heading are titled by their first line, or failing that their number
+    let text = slide_text("Just a thought\n\nAnd another\n");
+    assert_eq!(text.title, None);
+    assert_eq!(text.title_or_first_line(4), "Just a thought");
+    assert_eq!(
+        slide_text("<div></div>\n").title_or_first_line(4),
+        "Slide 4"
+    );
+}
+
+#[test]
+fn test_generate_pptx_with_alt_text_and_titles() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let slide_dir = temp_dir.path();
+    for i in 1..=2 {
+        let img = image::ImageBuffer::from_fn(100, 100, |_, _| image::Rgb([255u8, 255u8, 255u8]));
+        img.save(slide_dir.join(format!("slide_{:04}.png", i)))
+            .expect("Failed to save image");
+    }
+
+    let markdown_path = slide_dir.join("slides.md");
+    fs::write(
+        &markdown_path,
+        "# Results & Plans\n\nRevenue is up\n\n# Two\n\n---\n\nNo heading here\n",
+    )
+    .unwrap();
+
+    let read_slide = |output_path: &Path, slide_num: usize| {
+        let file = fs::File::open(output_path).expect("Failed to open PPTX file");
+        let mut archive = zip::ZipArchive::new(file).expect("Failed to read PPTX as ZIP");
+        let mut xml = String::new();
+        std::io::Read::read_to_string(
+            &mut archive
+                .by_name(&format!("ppt/slides/slide{}.xml", slide_num))
+                .unwrap(),
+            &mut xml,
+        )
+        .unwrap();
+        xml
+    };
+
+    let output_path = slide_dir.join("output.pptx");
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.png".to_string(),
+        markdown_path: Some(markdown_path.clone()),
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+
+    let xml = read_slide(&output_path, 1);
+    assert!(xml.contains(r#"descr="Results &amp; Plans&#xA;Revenue is up""#));
+    assert!(xml.contains(r#"name="Title 3" hidden="1""#));
+    assert!(xml.contains(r#"<p:ph type="title"/>"#));
+    assert!(xml.contains("<a:t>Results &amp; Plans</a:t>"));
+    // The title is read before the picture
+    assert!(xml.find("<p:ph type=\"title\"/>").unwrap() < xml.find("<p:pic>").unwrap());
+    assert!(read_slide(&output_path, 2).contains("<a:t>Two</a:t>"));
+
+    // Native slides use their heading as the title, or a hidden one without a heading
+    let pptx_config = PptxConfig {
+        markdown_path: Some(markdown_path),
+        native: true,
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+    let xml = read_slide(&output_path, 1);
+    assert!(xml.contains(r#"<p:nvPr><p:ph type="title"/></p:nvPr>"#));
+    assert!(!xml.contains(r#"hidden="1""#));
+    let xml = read_slide(&output_path, 3);
+    assert!(xml.contains(r#"hidden="1""#));
+    assert!(xml.contains("<a:t>No heading here</a:t>"));
+
+    // Without the markdown there is nothing to describe the image with
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.png".to_string(),
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+    let xml = read_slide(&output_path, 1);
+    assert!(xml.contains(r#"descr="""#));
+    assert!(!xml.contains("<p:ph"));
+}
+
+/// Write a small template package with three layouts, a custom slide size, an embedded
+/// font, a logo on the master and a slide of its own
+fn write_template(path: &Path) {
+    let ns = r#"xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main""#;
+    let rel = |id: &str, kind: &str, target: &str| {
+        format!(
+            r#"<Relationship Id="{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/{}" Target="{}"/>"#,
+            id, kind, target
+        )
+    };
+    let rels = |relationships: &[String]| {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+            relationships.concat()
+        )
+    };
+    let layout = |kind: &str, name: &str| {
+        format!(
+            r#"<p:sldLayout {} type="{}"><p:cSld name="{}"><p:spTree/></p:cSld></p:sldLayout>"#,
+            ns, kind, name
+        )
+    };
+    let to_master = rels(&[rel(
+        "rId1",
+        "slideMaster",
+        "../slideMasters/slideMaster1.xml",
+    )]);
+
+    let parts: Vec<(&str, Vec<u8>)> = vec![
+        ("[Content_Types].xml", br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="xml" ContentType="application/xml"/><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="png" ContentType="image/png"/><Default Extension="fntdata" ContentType="application/x-fontdata"/><Override PartName="/ppt/presentation.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.template.main+xml"/><Override PartName="/ppt/slideMasters/slideMaster1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideMaster+xml"/><Override PartName="/ppt/slideLayouts/slideLayout1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideLayout+xml"/><Override PartName="/ppt/slideLayouts/slideLayout2.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideLayout+xml"/><Override PartName="/ppt/slideLayouts/slideLayout3.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideLayout+xml"/><Override PartName="/ppt/theme/theme1.xml" ContentType="application/vnd.openxmlformats-officedocument.theme+xml"/><Override PartName="/ppt/slides/slide1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slide+xml"/></Types>"#.to_vec()),
+        ("_rels/.rels", rels(&[rel("rId1", "officeDocument", "ppt/presentation.xml")]).into_bytes()),
+        ("ppt/presentation.xml", format!(
+            r#"<p:presentation {}><p:sldMasterIdLst><p:sldMasterId id="2147483700" r:id="rId1"/></p:sldMasterIdLst><p:sldIdLst><p:sldId id="256" r:id="rId2"/></p:sldIdLst><p:sldSz cx="12192000" cy="6858000"/><p:notesSz cx="6858000" cy="9144000"/><p:embeddedFontLst><p:embeddedFont><p:font typeface="Company Sans"/><p:regular r:id="rId4"/></p:embeddedFont></p:embeddedFontLst><p:defaultTextStyle><a:lvl1pPr><a:defRPr sz="2400"/></a:lvl1pPr></p:defaultTextStyle></p:presentation>"#,
+            ns
+        ).into_bytes()),
+        ("ppt/_rels/presentation.xml.rels", rels(&[
+            rel("rId1", "slideMaster", "slideMasters/slideMaster1.xml"),
+            rel("rId2", "slide", "slides/slide1.xml"),
+            rel("rId3", "theme", "theme/theme1.xml"),
+            rel("rId4", "font", "fonts/font1.fntdata"),
+        ]).into_bytes()),
+        ("ppt/slideMasters/slideMaster1.xml", format!(r#"<p:sldMaster {}><p:cSld><p:spTree/></p:cSld></p:sldMaster>"#, ns).into_bytes()),
+        ("ppt/slideMasters/_rels/slideMaster1.xml.rels", rels(&[
+            rel("rId1", "slideLayout", "../slideLayouts/slideLayout1.xml"),
+            rel("rId2", "slideLayout", "../slideLayouts/slideLayout2.xml"),
+            rel("rId3", "slideLayout", "../slideLayouts/slideLayout3.xml"),
+            rel("rId4", "theme", "../theme/theme1.xml"),
+            rel("rId5", "image", "../media/image1.png"),
+            r#"<Relationship Id="rId6" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="https://example.com" TargetMode="External"/>"#.to_string(),
+        ]).into_bytes()),
+        ("ppt/slideLayouts/slideLayout1.xml", layout("title", "Title Slide").into_bytes()),
+        ("ppt/slideLayouts/slideLayout2.xml", layout("blank", "Blank").into_bytes()),
+        ("ppt/slideLayouts/slideLayout3.xml", layout("obj", "Company Content").into_bytes()),
+        ("ppt/slideLayouts/_rels/slideLayout1.xml.rels", to_master.clone().into_bytes()),
+        ("ppt/slideLayouts/_rels/slideLayout2.xml.rels", to_master.clone().into_bytes()),
+        ("ppt/slideLayouts/_rels/slideLayout3.xml.rels", to_master.into_bytes()),
+        ("ppt/theme/theme1.xml", br#"<a:theme xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" name="Company Theme"/>"#.to_vec()),
+        ("ppt/media/image1.png", b"logo".to_vec()),
+        ("ppt/fonts/font1.fntdata", vec![0, 159, 255]),
+        ("ppt/slides/slide1.xml", format!(r#"<p:sld {}><p:cSld><p:spTree/></p:cSld></p:sld>"#, ns).into_bytes()),
+        ("ppt/slides/_rels/slide1.xml.rels", rels(&[rel("rId1", "slideLayout", "../slideLayouts/slideLayout1.xml")]).into_bytes()),
+    ];
+
+    let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+    for (name, data) in parts {
+        zip.start_file(name, zip::write::FileOptions::default())
+            .unwrap();
+        zip.write_all(&data).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[test]
+fn test_generate_pptx_with_template() {
+    use crate::template::TemplateConfig;
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let slide_dir = temp_dir.path();
+    let img = image::ImageBuffer::from_fn(100, 100, |_, _| image::Rgb([255u8, 255u8, 255u8]));
+    img.save(slide_dir.join("slide_0001.png"))
+        .expect("Failed to save image");
+    let markdown_path = slide_dir.join("slides.md");
+    fs::write(&markdown_path, "# One\n\n<!-- notes: Thank the team -->\n").unwrap();
+    let template_path = slide_dir.join("company.potx");
+    write_template(&template_path);
+
+    let read_part = |output_path: &Path, name: &str| {
+        let file = fs::File::open(output_path).expect("Failed to open PPTX file");
+        let mut archive = zip::ZipArchive::new(file).expect("Failed to read PPTX as ZIP");
+        let mut data = Vec::new();
+        std::io::Read::read_to_end(&mut archive.by_name(name).unwrap(), &mut data).unwrap();
+        data
+    };
+    let read_xml =
+        |output_path: &Path, name: &str| String::from_utf8(read_part(output_path, name)).unwrap();
+
+    let output_path = slide_dir.join("output.pptx");
+    let template = TemplateConfig {
+        path: template_path.clone(),
+        layout: None,
+        keep_size: true,
+    };
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.png".to_string(),
+        markdown_path: Some(markdown_path),
+        template: Some(template.clone()),
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+
+    // The template's size, masters, fonts and text style replace the built-in ones
+    let presentation = read_xml(&output_path, "ppt/presentation.xml");
+    assert!(presentation.contains(r#"<p:sldSz cx="12192000" cy="6858000"/>"#));
+    assert!(presentation.contains(r#"<p:sldMasterId id="2147483700" r:id="rIdSM1"/>"#));
+    assert!(presentation.contains(r#"<p:regular r:id="rIdFont1"/>"#));
+    assert!(presentation.contains(r#"embedTrueTypeFonts="1""#));
+    assert!(presentation.contains(r#"<a:defRPr sz="2400"/>"#));
+    let pres_rels = read_xml(&output_path, "ppt/_rels/presentation.xml.rels");
+    assert!(pres_rels.contains(r#"Id="rIdFont1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/font" Target="fonts/font1.fntdata""#));
+    assert!(read_xml(&output_path, "ppt/theme/theme1.xml").contains("Company Theme"));
+    assert_eq!(
+        read_part(&output_path, "ppt/fonts/font1.fntdata"),
+        vec![0, 159, 255]
+    );
+    let content_types = read_xml(&output_path, "[Content_Types].xml");
+    assert!(content_types.contains(r#"<Default Extension="fntdata""#));
+    assert!(content_types.contains("/ppt/slideLayouts/slideLayout3.xml"));
+    assert!(!content_types.contains("template.main+xml"));
+
+    // Slides use the blank layout, and the slide image doesn't overwrite the template's logo
+    let slide_rels = read_xml(&output_path, "ppt/slides/_rels/slide1.xml.rels");
+    assert!(slide_rels.contains(r#"Target="../slideLayouts/slideLayout2.xml""#));
+    assert!(slide_rels.contains(r#"Target="../media/big1_image1.png""#));
+    assert_eq!(read_part(&output_path, "ppt/media/image1.png"), b"logo");
+    assert!(read_xml(&output_path, "ppt/slides/slide1.xml").contains("<p:pic>"));
+    assert!(
+        read_xml(&output_path, "ppt/notesMasters/_rels/notesMaster1.xml.rels")
+            .contains("../theme/theme2.xml")
+    );
+
+    // Every part is written once
+    let file = fs::File::open(&output_path).unwrap();
+    let archive = zip::ZipArchive::new(file).unwrap();
+    let mut names: Vec<&str> = archive.file_names().collect();
+    let count = names.len();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), count);
+
+    // A named layout and an explicit aspect ratio override the defaults
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.png".to_string(),
+        aspect_ratio: "4:3".to_string(),
+        template: Some(TemplateConfig {
+            layout: Some("company content".to_string()),
+            keep_size: false,
+            ..template.clone()
+        }),
+        ..Default::default()
+    };
+    pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap();
+    assert!(read_xml(&output_path, "ppt/slides/_rels/slide1.xml.rels")
+        .contains(r#"Target="../slideLayouts/slideLayout3.xml""#));
+    assert!(read_xml(&output_path, "ppt/presentation.xml")
+        .contains(r#"<p:sldSz cx="9144000" cy="6858000" type="screen4x3"/>"#));
+
+    // Unknown layouts are reported with the ones the template has
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.png".to_string(),
+        template: Some(TemplateConfig {
+            layout: Some("Two Content".to_string()),
+            ..template
+        }),
+        ..Default::default()
+    };
+    let err = pptx::generate_pptx(slide_dir, &output_path, &pptx_config).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Its layouts are: Title Slide, Blank, Company Content"));
+}
+
+/// Give the presentation in `path` PowerPoint sections holding the slides with these ids
+fn add_sections(path: &Path, sections: &[(&str, &[u32])]) {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+    let mut parts = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).unwrap();
+        let mut data = Vec::new();
+        std::io::Read::read_to_end(&mut entry, &mut data).unwrap();
+        parts.push((entry.name().to_string(), data));
+    }
+
+    let section_list: String = sections
+        .iter()
+        .enumerate()
+        .map(|(i, (name, ids))| {
+            let ids: String = ids
+                .iter()
+                .map(|id| format!(r#"<p14:sldId id="{}"/>"#, id))
+                .collect();
+            format!(
+                r#"<p14:section name="{}" id="{{0000000{}-0000-0000-0000-000000000000}}"><p14:sldIdLst>{}</p14:sldIdLst></p14:section>"#,
+                name, i, ids
+            )
+        })
+        .collect();
+    let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+    for (name, data) in parts {
+        let data = if name == "ppt/presentation.xml" {
+            String::from_utf8(data)
+                .unwrap()
+                .replace(
+                    "</p:presentation>",
+                    &format!(
+                        r#"<p:extLst><p:ext uri="{{521415D9-36F7-43E2-AB2F-B90AF26B5E84}}"><p14:sectionLst xmlns:p14="http://schemas.microsoft.com/office/powerpoint/2010/main">{}</p14:sectionLst></p:ext></p:extLst></p:presentation>"#,
+                        section_list
+                    ),
+                )
+                .into_bytes()
+        } else {
+            data
+        };
+        zip.start_file(name, zip::write::FileOptions::default())
+            .unwrap();
+        zip.write_all(&data).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+/// Replace the part `name` of the package at `path` with `content`
+fn replace_part(path: &Path, name: &str, content: &str) {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+    let mut parts = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).unwrap();
+        let mut data = Vec::new();
+        std::io::Read::read_to_end(&mut entry, &mut data).unwrap();
+        parts.push((entry.name().to_string(), data));
+    }
+
+    let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+    for (part, data) in parts {
+        zip.start_file(part.as_str(), zip::write::FileOptions::default())
+            .unwrap();
+        if part == name {
+            zip.write_all(content.as_bytes()).unwrap();
+        } else {
+            zip.write_all(&data).unwrap();
+        }
+    }
+    zip.finish().unwrap();
+}
+
+#[test]
+fn test_merge_pptx_into_sections() {
+    use crate::merge::{self, MergeConfig, Placement};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let deck_dir = temp_dir.path().join("deck");
+    let new_dir = temp_dir.path().join("new");
+    fs::create_dir(&deck_dir).unwrap();
+    fs::create_dir(&new_dir).unwrap();
+    for i in 1..=3 {
+        let img = image::ImageBuffer::from_fn(100, 100, |_, _| image::Rgb([255u8, 255u8, 255u8]));
+        img.save(deck_dir.join(format!("slide_{:04}.png", i)))
+            .expect("Failed to save image");
+    }
+    let img = image::ImageBuffer::from_fn(100, 100, |_, _| image::Rgb([0u8, 0u8, 255u8]));
+    img.save(new_dir.join("slide_0001.png"))
+        .expect("Failed to save image");
+    let markdown_path = temp_dir.path().join("new.md");
+    fs::write(
+        &markdown_path,
+        "# Generated\n\n<!-- notes: Fresh numbers -->\n",
+    )
+    .unwrap();
+
+    // A hand-made deck of three slides in two sections, without any notes
+    let deck_path = temp_dir.path().join("deck.pptx");
+    let pptx_config = PptxConfig {
+        pattern: "slide_*.png".to_string(),
+        ..Default::default()
+    };
+    pptx::generate_pptx(&deck_dir, &deck_path, &pptx_config).unwrap();
+    add_sections(&deck_path, &[("Intro", &[256]), ("Results", &[257, 258])]);
+    // Saved by PowerPoint, the document properties list the slide titles too
+    replace_part(
+        &deck_path,
+        "docProps/app.xml",
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/extended-properties" xmlns:vt="http://schemas.openxmlformats.org/officeDocument/2006/docPropsVTypes"><Slides>3</Slides><HeadingPairs><vt:vector size="4" baseType="variant"><vt:variant><vt:lpstr>Theme</vt:lpstr></vt:variant><vt:variant><vt:i4>1</vt:i4></vt:variant><vt:variant><vt:lpstr>Slide Titles</vt:lpstr></vt:variant><vt:variant><vt:i4>3</vt:i4></vt:variant></vt:vector></HeadingPairs><TitlesOfParts><vt:vector size="4" baseType="lpstr"><vt:lpstr>Office Theme</vt:lpstr><vt:lpstr>One</vt:lpstr><vt:lpstr>Two</vt:lpstr><vt:lpstr>Three</vt:lpstr></vt:vector></TitlesOfParts></Properties>"#,
+    );
+
+    let read_xml = |path: &Path, name: &str| {
+        let mut archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+        let mut xml = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name(name).unwrap(), &mut xml).unwrap();
+        xml
+    };
+    let part_names = |path: &Path| {
+        let archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+        archive
+            .file_names()
+            .map(str::to_string)
+            .collect::<Vec<String>>()
+    };
+    let new_config = PptxConfig {
+        pattern: "slide_*.png".to_string(),
+        markdown_path: Some(markdown_path),
+        ..Default::default()
+    };
+
+    // Replacing a section swaps its slides and drops what only they used
+    let output_path = temp_dir.path().join("replaced.pptx");
+    let merge_config = MergeConfig {
+        deck: deck_path.clone(),
+        placement: Placement::Section("results".to_string()),
+        layout: None,
+    };
+    merge::merge_pptx(&new_dir, &output_path, &new_config, &merge_config).unwrap();
+    let presentation = read_xml(&output_path, "ppt/presentation.xml");
+    assert!(presentation.contains(
+        r#"<p:sldIdLst><p:sldId id="256" r:id="rId1"/><p:sldId id="257" r:id="rId2"/></p:sldIdLst>"#
+    ));
+    assert!(presentation.contains(r#"<p14:section name="Results" id="{00000001-0000-0000-0000-000000000000}"><p14:sldIdLst><p14:sldId id="257"/></p14:sldIdLst></p14:section>"#));
+    assert!(presentation.contains("<p:notesMasterIdLst>"));
+    let names = part_names(&output_path);
+    assert!(names.contains(&"ppt/slides/slide2.xml".to_string()));
+    assert!(!names.contains(&"ppt/slides/slide3.xml".to_string()));
+    assert!(!names.contains(&"ppt/media/image3.png".to_string()));
+    assert!(names.contains(&"ppt/notesSlides/notesSlide1.xml".to_string()));
+    let content_types = read_xml(&output_path, "[Content_Types].xml");
+    assert!(!content_types.contains("/ppt/slides/slide3.xml"));
+    assert!(content_types.contains("/ppt/notesSlides/notesSlide1.xml"));
+    let app = read_xml(&output_path, "docProps/app.xml");
+    assert!(app.contains("<Slides>2</Slides>"));
+    assert!(
+        app.contains("<vt:lpstr>Slide Titles</vt:lpstr></vt:variant><vt:variant><vt:i4>2</vt:i4>")
+    );
+    assert!(app.contains(r#"<TitlesOfParts><vt:vector size="3" baseType="lpstr"><vt:lpstr>Office Theme</vt:lpstr><vt:lpstr>PowerPoint Presentation</vt:lpstr><vt:lpstr>Generated</vt:lpstr></vt:vector>"#));
+    assert!(read_xml(&output_path, "ppt/slides/_rels/slide2.xml.rels")
+        .contains(r#"Target="../slideLayouts/slideLayout1.xml""#));
+
+    // Inserting at a position renumbers around the slides that are already there
+    let merge_config = MergeConfig {
+        placement: Placement::Before(1),
+        ..merge_config
+    };
+    merge::merge_pptx(&new_dir, &output_path, &new_config, &merge_config).unwrap();
+    let presentation = read_xml(&output_path, "ppt/presentation.xml");
+    assert!(presentation
+        .contains(r#"<p:sldIdLst><p:sldId id="259" r:id="rId4"/><p:sldId id="256" r:id="rId1"/>"#));
+    assert!(presentation.contains(r#"<p14:section name="Intro" id="{00000000-0000-0000-0000-000000000000}"><p14:sldIdLst><p14:sldId id="259"/><p14:sldId id="256"/></p14:sldIdLst>"#));
+    let slide_rels = read_xml(&output_path, "ppt/slides/_rels/slide4.xml.rels");
+    assert!(slide_rels.contains(r#"Target="../media/image4.png""#));
+    let app = read_xml(&output_path, "docProps/app.xml");
+    assert!(app.contains("<Slides>4</Slides>"));
+    assert!(app.contains(r#"<vt:vector size="5" baseType="lpstr"><vt:lpstr>Office Theme</vt:lpstr><vt:lpstr>Generated</vt:lpstr><vt:lpstr>PowerPoint Presentation</vt:lpstr>"#));
+    // The original images are untouched
+    let names = part_names(&output_path);
+    for i in 1..=4 {
+        assert!(names.contains(&format!("ppt/media/image{}.png", i)));
+    }
+
+    // Unknown sections and positions past the end are rejected
+    let err = merge::merge_pptx(
+        &new_dir,
+        &output_path,
+        &new_config,
+        &MergeConfig {
+            placement: Placement::Section("Outro".to_string()),
+            ..merge_config.clone()
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Its sections are: Intro, Results"));
+    let err = merge::merge_pptx(
+        &new_dir,
+        &output_path,
+        &new_config,
+        &MergeConfig {
+            placement: Placement::Before(5),
+            ..merge_config
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("the deck has 3 slides"));
+}
@@ -0,0 +1,118 @@
+// Default big-slides navigation: every top-level div is a slide, sized to fill the window
+(function () {
+  function start() {
+    var slides = document.querySelectorAll('body > div');
+    var timer;
+
+    if (!slides.length) return;
+
+    function fit(slide) {
+      var size = 1000;
+      slide.style.fontSize = size + 'px';
+      while (size > 1 && (slide.offsetWidth > window.innerWidth || slide.offsetHeight > window.innerHeight)) {
+        size -= size > 100 ? 10 : 2;
+        slide.style.fontSize = size + 'px';
+      }
+      slide.style.marginTop = Math.max(0, (window.innerHeight - slide.offsetHeight) / 2) + 'px';
+    }
+
+    function show(n) {
+      var slide = slides[n];
+      var first = slide.firstElementChild;
+
+      for (var i = 0; i < slides.length; i++) {
+        slides[i].style.display = 'none';
+      }
+      slide.style.display = 'inline';
+      document.body.className = slide.getAttribute('data-bodyclass') || '';
+
+      // A slide that opens with an image uses it as the background
+      if (first && first.nodeName === 'IMG') {
+        document.body.style.backgroundImage = 'url(' + first.src + ')';
+        first.style.display = 'none';
+        slide.classList.add('imageText');
+      } else {
+        document.body.style.backgroundImage = '';
+        document.body.style.backgroundColor = slide.style.backgroundColor;
+      }
+
+      fit(slide);
+
+      if (timer !== undefined) window.clearTimeout(timer);
+      var seconds = parseInt(slide.getAttribute('data-time-to-next') || '0', 10);
+      if (seconds > 0) timer = window.setTimeout(big.forward, seconds * 1000);
+    }
+
+    var big = (window.big = {
+      current: 0,
+      length: slides.length,
+      go: function (n) {
+        big.current = Math.min(slides.length - 1, Math.max(0, n));
+        show(big.current);
+        if (window.location.hash !== '#' + big.current) {
+          window.history.replaceState(null, '', '#' + big.current);
+        }
+      },
+      forward: function () {
+        big.go(big.current + 1);
+      },
+      reverse: function () {
+        big.go(big.current - 1);
+      },
+      updateDisplay: function () {
+        show(big.current);
+      }
+    });
+
+    document.addEventListener('click', function (e) {
+      if (e.target.closest && e.target.closest('a')) return;
+      big.forward();
+    });
+
+    document.addEventListener('keydown', function (e) {
+      switch (e.key) {
+        case 'ArrowRight':
+        case 'ArrowDown':
+        case 'PageDown':
+        case ' ':
+        case 'l':
+        case 'j':
+          big.forward();
+          break;
+        case 'ArrowLeft':
+        case 'ArrowUp':
+        case 'PageUp':
+        case 'h':
+        case 'k':
+          big.reverse();
+          break;
+        case 'Home':
+          big.go(0);
+          break;
+        case 'End':
+          big.go(slides.length - 1);
+          break;
+        default:
+          return;
+      }
+      e.preventDefault();
+    });
+
+    window.addEventListener('hashchange', function () {
+      var n = parseInt(window.location.hash.slice(1), 10);
+      if (!isNaN(n) && n !== big.current) big.go(n);
+    });
+
+    window.addEventListener('resize', function () {
+      fit(slides[big.current]);
+    });
+
+    big.go(parseInt(window.location.hash.slice(1), 10) || 0);
+  }
+
+  if (document.readyState === 'loading') {
+    document.addEventListener('DOMContentLoaded', start);
+  } else {
+    start();
+  }
+})();
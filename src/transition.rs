@@ -0,0 +1,220 @@
+// ABOUTME: Slide transitions and auto-advance timing for the generated PPTX
+// ABOUTME: Parses deck-wide settings and per-slide markdown comments into <p:transition> XML
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::errors::{BigError, Result};
+
+/// Keys a transition comment such as `<!-- transition: fade; advance: 5s -->` can set
+const TRANSITION_KEYS: [&str; 3] = ["transition", "speed", "advance"];
+
+/// Effect played when moving onto a slide
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransitionEffect {
+    #[default]
+    None,
+    Fade,
+    Push,
+    Wipe,
+}
+
+impl FromStr for TransitionEffect {
+    type Err = BigError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "none" => Ok(TransitionEffect::None),
+            "fade" => Ok(TransitionEffect::Fade),
+            "push" => Ok(TransitionEffect::Push),
+            "wipe" => Ok(TransitionEffect::Wipe),
+            other => Err(BigError::ValidationError(format!(
+                "Unknown transition '{}', expected one of: fade, push, wipe, none",
+                other
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for TransitionEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TransitionEffect::None => "none",
+            TransitionEffect::Fade => "fade",
+            TransitionEffect::Push => "push",
+            TransitionEffect::Wipe => "wipe",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How quickly a transition plays
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransitionSpeed {
+    Slow,
+    #[default]
+    Medium,
+    Fast,
+}
+
+impl TransitionSpeed {
+    /// Value of the `spd` attribute
+    fn xml_value(self) -> &'static str {
+        match self {
+            TransitionSpeed::Slow => "slow",
+            TransitionSpeed::Medium => "med",
+            TransitionSpeed::Fast => "fast",
+        }
+    }
+}
+
+impl FromStr for TransitionSpeed {
+    type Err = BigError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "slow" => Ok(TransitionSpeed::Slow),
+            "medium" | "med" => Ok(TransitionSpeed::Medium),
+            "fast" => Ok(TransitionSpeed::Fast),
+            other => Err(BigError::ValidationError(format!(
+                "Unknown transition speed '{}', expected one of: slow, medium, fast",
+                other
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for TransitionSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TransitionSpeed::Slow => "slow",
+            TransitionSpeed::Medium => "medium",
+            TransitionSpeed::Fast => "fast",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The transition and timing of a slide
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Transition {
+    pub effect: TransitionEffect,
+    pub speed: TransitionSpeed,
+
+    /// Move on to the next slide after this long; slides wait for a click if `None`
+    pub advance_after: Option<Duration>,
+}
+
+impl Transition {
+    /// The `<p:transition>` element for the slide, or `None` if the slide has neither an
+    /// effect nor an auto-advance time
+    pub fn to_xml(&self) -> Option<String> {
+        let effect = match self.effect {
+            TransitionEffect::None if self.advance_after.is_none() => return None,
+            TransitionEffect::None => "",
+            TransitionEffect::Fade => "<p:fade/>",
+            TransitionEffect::Push => "<p:push/>",
+            TransitionEffect::Wipe => "<p:wipe/>",
+        };
+        let advance = self
+            .advance_after
+            .map(|after| {
+                format!(
+                    r#" advTm="{}""#,
+                    after.as_millis().min(u128::from(u32::MAX))
+                )
+            })
+            .unwrap_or_default();
+        Some(format!(
+            r#"<p:transition spd="{}"{}>{}</p:transition>"#,
+            self.speed.xml_value(),
+            advance,
+            effect
+        ))
+    }
+}
+
+/// Transition settings a slide overrides from its markdown; unset values come from the deck
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SlideTransition {
+    pub effect: Option<TransitionEffect>,
+    pub speed: Option<TransitionSpeed>,
+
+    /// `Some(None)` turns auto-advance off for the slide
+    pub advance_after: Option<Option<Duration>>,
+}
+
+impl SlideTransition {
+    /// The slide's transition, falling back to `deck` for anything the slide doesn't set
+    pub fn apply(&self, deck: &Transition) -> Transition {
+        Transition {
+            effect: self.effect.unwrap_or(deck.effect),
+            speed: self.speed.unwrap_or(deck.speed),
+            advance_after: self.advance_after.unwrap_or(deck.advance_after),
+        }
+    }
+
+    /// Parse the text of a transition comment, such as `transition: fade; advance: 5s`.
+    /// Returns `None` if the comment isn't about transitions.
+    pub fn parse_comment(text: &str) -> Option<Result<Self>> {
+        let first_key = text.split(':').next()?.trim().to_lowercase();
+        if !TRANSITION_KEYS.contains(&first_key.as_str()) {
+            return None;
+        }
+        Some(text.parse())
+    }
+}
+
+impl FromStr for SlideTransition {
+    type Err = BigError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut transition = SlideTransition::default();
+        for setting in s.split(';').filter(|setting| !setting.trim().is_empty()) {
+            let Some((key, value)) = setting.split_once(':') else {
+                return Err(BigError::ValidationError(format!(
+                    "Expected `key: value` in transition settings, got '{}'",
+                    setting.trim()
+                )));
+            };
+            match key.trim().to_lowercase().as_str() {
+                "transition" => transition.effect = Some(value.parse()?),
+                "speed" => transition.speed = Some(value.parse()?),
+                "advance" => {
+                    transition.advance_after = Some(match value.trim().to_lowercase().as_str() {
+                        "none" | "off" | "click" => None,
+                        _ => Some(parse_duration(value)?),
+                    })
+                }
+                other => {
+                    return Err(BigError::ValidationError(format!(
+                        "Unknown transition setting '{}', expected one of: {}",
+                        other,
+                        TRANSITION_KEYS.join(", ")
+                    )))
+                }
+            }
+        }
+        Ok(transition)
+    }
+}
+
+/// Parse a duration such as `5s`, `1.5s` or `500ms`. A bare number is in seconds.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let (number, millis) = match s.strip_suffix("ms") {
+        Some(number) => (number, true),
+        None => (s.strip_suffix('s').unwrap_or(s), false),
+    };
+    let value: f64 = number.trim().parse().map_err(|_| {
+        BigError::ValidationError(format!(
+            "Invalid duration '{}', expected seconds such as 5s or milliseconds such as 500ms",
+            s
+        ))
+    })?;
+    let seconds = if millis { value / 1000.0 } else { value };
+    Duration::try_from_secs_f64(seconds).map_err(|_| {
+        BigError::ValidationError(format!("Duration must not be negative, got '{}'", s))
+    })
+}
@@ -18,6 +18,8 @@ use tungstenite::{accept, Message};
 use crate::config::Config as AppConfig;
 use crate::errors::{BigError, Result};
 use crate::html;
+use crate::inline::InlineConfig;
+use crate::overflow::OVERFLOW_REPORT_FILE;
 use crate::pptx;
 use crate::render;
 use crate::resources::ResourceFile;
@@ -46,6 +48,9 @@ pub struct WatchConfig {
     /// Whether to embed resources in HTML
     pub embed_resources: bool,
 
+    /// Inline every referenced asset so the HTML is self-contained
+    pub inline: Option<InlineConfig>,
+
     /// Debounce time in milliseconds
     pub debounce_ms: u64,
 
@@ -72,6 +77,7 @@ impl Default for WatchConfig {
             css_files: Vec::new(),
             js_files: Vec::new(),
             embed_resources: true,
+            inline: None,
             debounce_ms: 500,
             serve: false,
             port: 8080,
@@ -405,8 +411,11 @@ pub fn watch_markdown(config: WatchConfig, app_config: &AppConfig) -> Result<()>
         None
     };
 
+    // One browser renders every regeneration instead of starting Chrome each time
+    let mut session = render::RenderSession::new();
+
     // Initial generation
-    regenerate_outputs(&config, app_config, ws_port)?;
+    regenerate_outputs(&config, app_config, ws_port, &mut session)?;
 
     // Start local server if requested
     let ws_manager = if config.serve {
@@ -493,7 +502,7 @@ pub fn watch_markdown(config: WatchConfig, app_config: &AppConfig) -> Result<()>
                     && now.duration_since(last_processed)
                         > Duration::from_millis(config.debounce_ms)
                 {
-                    match regenerate_outputs(&config, app_config, ws_port) {
+                    match regenerate_outputs(&config, app_config, ws_port, &mut session) {
                         Ok(_) => {
                             info!("Regenerated outputs successfully");
                             last_processed = now;
@@ -575,6 +584,7 @@ fn regenerate_outputs(
     config: &WatchConfig,
     app_config: &AppConfig,
     ws_port: Option<u16>,
+    session: &mut render::RenderSession,
 ) -> Result<()> {
     info!("Regenerating outputs...");
 
@@ -591,6 +601,7 @@ fn regenerate_outputs(
         &config.css_files,
         &config.js_files,
         config.embed_resources,
+        config.inline.as_ref(),
         auto_reload_js_script,
     )?;
 
@@ -600,15 +611,17 @@ fn regenerate_outputs(
 
     // Generate slides if output directory is specified
     if let Some(slides_dir) = &config.slides_output_dir {
-        // Create render configuration
-        let render_config = app_config.get_render_config(
+        // Create render configuration; only slides that changed are re-rendered
+        let mut render_config = app_config.get_render_config(
             None, // Use defaults from app_config
             None, None, None, None,
         );
+        render_config.incremental = true;
+        render_config.overflow_report = Some(slides_dir.join(OVERFLOW_REPORT_FILE));
 
         // Generate slides (screenshots)
         let output_files =
-            render::generate_slides(&config.html_output, slides_dir, &render_config)?;
+            session.generate_slides(&config.html_output, slides_dir, &render_config)?;
 
         info!(
             "Slides regenerated: {} slides in {:?}",
@@ -618,10 +631,13 @@ fn regenerate_outputs(
 
         // Generate PPTX if output path is specified
         if let Some(pptx_output) = &config.pptx_output {
-            // Create PPTX configuration
+            // Create PPTX configuration, titled from the deck's frontmatter
+            let metadata = html::parse_deck(&config.markdown_path)?.metadata;
             let pptx_config = app_config.get_pptx_config(
-                None, // Use defaults from app_config
-                None, None,
+                metadata.title,
+                None,
+                metadata.aspect_ratio,
+                Some(config.markdown_path.clone()), // Carry speaker notes through
             );
 
             // Generate PowerPoint presentation from images
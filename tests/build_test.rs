@@ -0,0 +1,175 @@
+use std::fs;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn run_command(args: &[&str]) -> Output {
+    Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .args(args)
+        .output()
+        .expect("Failed to execute command")
+}
+
+/// Write a small deck with local CSS and JS so the build never reaches the network
+fn write_deck(dir: &std::path::Path) -> (String, String, String) {
+    let markdown_path = dir.join("deck.md");
+    fs::write(&markdown_path, "# First\n\nHello\n\n# Second\n\nWorld\n")
+        .expect("Failed to write markdown file");
+
+    let css_path = dir.join("test.css");
+    fs::write(&css_path, "body { font-family: Arial; }").expect("Failed to write CSS file");
+
+    let js_path = dir.join("test.js");
+    fs::write(&js_path, "console.log('loaded');").expect("Failed to write JS file");
+
+    (
+        markdown_path.to_string_lossy().into_owned(),
+        css_path.to_string_lossy().into_owned(),
+        js_path.to_string_lossy().into_owned(),
+    )
+}
+
+#[test]
+fn test_build_html_only() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+    let (markdown, css, js) = write_deck(temp_path);
+    let output_dir = temp_path.join("out");
+
+    let output = run_command(&[
+        "build",
+        "-i",
+        &markdown,
+        "-o",
+        output_dir.to_str().unwrap(),
+        "--artifacts",
+        "html",
+        "--css",
+        &css,
+        "--js",
+        &js,
+    ]);
+
+    assert!(output.status.success(), "Command failed: {:?}", output);
+
+    // Only the HTML is written, named after the markdown file
+    let html = fs::read_to_string(output_dir.join("deck.html")).expect("HTML was not created");
+    assert!(html.contains("<div>First"));
+    assert!(html.contains("<div>Second"));
+    assert!(!output_dir.join("slides").exists());
+    assert!(!output_dir.join("deck.pptx").exists());
+
+    // No render copies are left beside the markdown
+    let leftovers = fs::read_dir(temp_path)
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_name().to_string_lossy().starts_with('.'))
+        .count();
+    assert_eq!(leftovers, 0);
+}
+
+#[test]
+fn test_build_rejects_unknown_artifact() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+    let (markdown, _css, _js) = write_deck(temp_path);
+
+    let output = run_command(&[
+        "build",
+        "-i",
+        &markdown,
+        "-o",
+        temp_path.join("out").to_str().unwrap(),
+        "--artifacts",
+        "html,gif",
+    ]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown artifact 'gif'"));
+}
+
+#[test]
+fn test_build_rejects_unexpected_slide_count() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+    let (markdown, _css, _js) = write_deck(temp_path);
+
+    let output = run_command(&[
+        "build",
+        "-i",
+        &markdown,
+        "-o",
+        temp_path.join("out").to_str().unwrap(),
+        "--artifacts",
+        "html",
+        "--expected-slides",
+        "3",
+    ]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Expected 3 slides but found 2"));
+    assert!(!temp_path.join("out").join("deck.html").exists());
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires a headless browser
+fn test_build_full_pipeline() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+    let (markdown, css, js) = write_deck(temp_path);
+    let output_dir = temp_path.join("out");
+
+    let output = run_command(&[
+        "build",
+        "-i",
+        &markdown,
+        "-o",
+        output_dir.to_str().unwrap(),
+        "--artifacts",
+        "pptx,pdf",
+        "--css",
+        &css,
+        "--js",
+        &js,
+        "--width",
+        "800",
+        "--height",
+        "600",
+    ]);
+
+    assert!(output.status.success(), "Command failed: {:?}", output);
+    assert!(output_dir.join("deck.pptx").exists());
+    assert!(output_dir.join("deck.pdf").exists());
+
+    // Intermediates are cleaned up unless asked for
+    assert!(!output_dir.join("deck.html").exists());
+    assert!(!output_dir.join("slides").exists());
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires a headless browser
+fn test_build_keep_intermediates() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+    let (markdown, css, js) = write_deck(temp_path);
+    let output_dir = temp_path.join("out");
+
+    let output = run_command(&[
+        "build",
+        "-i",
+        &markdown,
+        "-o",
+        output_dir.to_str().unwrap(),
+        "--keep-intermediates",
+        "--css",
+        &css,
+        "--js",
+        &js,
+    ]);
+
+    assert!(output.status.success(), "Command failed: {:?}", output);
+    assert!(output_dir.join("deck.pptx").exists());
+    assert!(output_dir.join("deck.html").exists());
+    assert!(output_dir.join("slides").join("slide_0001.png").exists());
+}
@@ -0,0 +1,130 @@
+use big_slides::cache::{CacheEntry, ResourceCache};
+use chrono::Utc;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn run_command(cache_home: &Path, args: &[&str]) -> Output {
+    Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .args(args)
+        .env("XDG_CACHE_HOME", cache_home)
+        .output()
+        .expect("Failed to execute command")
+}
+
+#[test]
+fn test_offline_uses_cached_resource() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+    let cache_home = temp_path.join("cache");
+
+    // Seed the cache with a stylesheet that can't be downloaded
+    let url = "http://127.0.0.1:9/theme.css";
+    let cache = ResourceCache::new(cache_home.join("big-slides").join("resources"));
+    let entry = CacheEntry {
+        url: url.to_string(),
+        etag: Some("\"abc\"".to_string()),
+        last_modified: None,
+        fetched_at: Utc::now(),
+        size: 22,
+    };
+    cache
+        .put(&entry, b"body { color: purple; }")
+        .expect("Failed to seed cache");
+
+    let markdown_path = temp_path.join("test.md");
+    fs::write(&markdown_path, "# Cached\n").expect("Failed to write markdown file");
+    let output_path = temp_path.join("output.html");
+
+    let output = run_command(
+        &cache_home,
+        &[
+            "generate-html",
+            "--offline",
+            "-i",
+            markdown_path.to_str().unwrap(),
+            "-o",
+            output_path.to_str().unwrap(),
+            "--css",
+            url,
+        ],
+    );
+    assert!(output.status.success(), "Command failed: {:?}", output);
+
+    let html_content = fs::read_to_string(&output_path).expect("Failed to read output file");
+    assert!(html_content.contains("<style>body { color: purple; }</style>"));
+
+    // The cache can be listed and cleared from the command line
+    let output = run_command(&cache_home, &["cache", "list"]);
+    assert!(output.status.success(), "Command failed: {:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(url));
+
+    let output = run_command(&cache_home, &["cache", "clear"]);
+    assert!(output.status.success(), "Command failed: {:?}", output);
+    assert!(cache.entries().unwrap().is_empty());
+}
+
+#[test]
+fn test_offline_native_pptx_uses_cached_image() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+    let cache_home = temp_path.join("cache");
+
+    // Seed the cache with an image that can't be downloaded
+    let url = "http://127.0.0.1:9/picture.png";
+    let mut png = Vec::new();
+    image::RgbImage::from_pixel(4, 3, image::Rgb([200, 40, 40]))
+        .write_to(
+            &mut std::io::Cursor::new(&mut png),
+            image::ImageOutputFormat::Png,
+        )
+        .expect("Failed to encode image");
+    let cache = ResourceCache::new(cache_home.join("big-slides").join("resources"));
+    let entry = CacheEntry {
+        url: url.to_string(),
+        etag: None,
+        last_modified: None,
+        fetched_at: Utc::now(),
+        size: png.len() as u64,
+    };
+    cache.put(&entry, &png).expect("Failed to seed cache");
+
+    let markdown_path = temp_path.join("test.md");
+    fs::write(&markdown_path, format!("# Cached\n\n![Picture]({})\n", url))
+        .expect("Failed to write markdown file");
+    let output_path = temp_path.join("output.pptx");
+
+    let output = run_command(
+        &cache_home,
+        &[
+            "generate-pptx",
+            "--offline",
+            "--native",
+            "--markdown",
+            markdown_path.to_str().unwrap(),
+            "-o",
+            output_path.to_str().unwrap(),
+        ],
+    );
+    assert!(output.status.success(), "Command failed: {:?}", output);
+
+    let file = fs::File::open(&output_path).expect("Failed to open PPTX file");
+    let mut archive = zip::ZipArchive::new(file).expect("Failed to read PPTX as ZIP");
+    let mut media = archive
+        .file_names()
+        .filter(|name| name.starts_with("ppt/media/"))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(media.len(), 1, "Expected the cached image in {:?}", media);
+
+    let mut packaged = Vec::new();
+    std::io::Read::read_to_end(
+        &mut archive.by_name(&media.remove(0)).unwrap(),
+        &mut packaged,
+    )
+    .unwrap();
+    assert_eq!(packaged, png);
+}
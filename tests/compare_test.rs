@@ -0,0 +1,83 @@
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn run_command(args: &[&str]) -> Output {
+    Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .args(args)
+        .output()
+        .expect("Failed to execute command")
+}
+
+fn write_slide(dir: &Path, name: &str, changed_pixels: u32) {
+    let mut image = RgbaImage::from_pixel(20, 10, Rgba([255, 255, 255, 255]));
+    for x in 0..changed_pixels {
+        image.put_pixel(x, 0, Rgba([0, 0, 0, 255]));
+    }
+    std::fs::create_dir_all(dir).expect("Failed to create slide dir");
+    image.save(dir.join(name)).expect("Failed to write slide");
+}
+
+#[test]
+fn test_compare_passes_for_identical_slides() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let baseline = temp_dir.path().join("baseline");
+    let current = temp_dir.path().join("current");
+    write_slide(&baseline, "slide_0001.png", 0);
+    write_slide(&current, "slide_0001.png", 0);
+
+    let output = run_command(&[
+        "compare",
+        "--baseline",
+        baseline.to_str().unwrap(),
+        "--current",
+        current.to_str().unwrap(),
+    ]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "Compare should pass: {}", stdout);
+    assert!(stdout.contains("slide_0001.png: 0.000% of pixels changed"));
+    assert!(!current.join("diff/slide_0001.diff.png").exists());
+}
+
+#[test]
+fn test_compare_fails_above_threshold() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let baseline = temp_dir.path().join("baseline");
+    let current = temp_dir.path().join("current");
+    let diff_dir = temp_dir.path().join("diff");
+    write_slide(&baseline, "slide_0001.png", 0);
+    write_slide(&current, "slide_0001.png", 2);
+    write_slide(&baseline, "slide_0002.png", 0);
+    write_slide(&current, "slide_0002.png", 20);
+
+    let args = [
+        "compare",
+        "--baseline",
+        baseline.to_str().unwrap(),
+        "--current",
+        current.to_str().unwrap(),
+        "--diff-dir",
+        diff_dir.to_str().unwrap(),
+    ];
+
+    // 1% and 10% of pixels changed
+    let output = run_command(&[&args[..], &["--threshold", "5"]].concat());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "Compare should fail: {}", stdout);
+    assert!(stderr.contains("slide_0002.png"));
+    assert!(!stderr.contains("slide_0001.png"));
+    assert!(diff_dir.join("slide_0001.diff.png").exists());
+    assert!(diff_dir.join("slide_0002.diff.png").exists());
+
+    let output = run_command(&[&args[..], &["--threshold", "10"]].concat());
+    assert!(
+        output.status.success(),
+        "Compare should pass at the threshold: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,32 @@
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn run_command(args: &[&str]) -> Output {
+    Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .args(args)
+        .output()
+        .expect("Failed to execute command")
+}
+
+#[test]
+fn test_doctor_reports_missing_browser() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let output_dir = temp_dir.path().join("out");
+
+    let output = run_command(&[
+        "--browser-path",
+        "/nonexistent/chrome",
+        "doctor",
+        "--output-dir",
+        output_dir.to_str().unwrap(),
+    ]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "Doctor should fail: {}", stdout);
+    assert!(stdout.contains("[FAIL] Browser path: /nonexistent/chrome does not exist"));
+    assert!(stdout.contains("fix:"));
+    assert!(stdout.contains("will be created"));
+    assert!(!output_dir.exists(), "Doctor must not create directories");
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn run_command(args: &[&str]) -> Output {
+    Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .args(args)
+        .output()
+        .expect("Failed to execute command")
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires Chrome to be installed
+fn test_generate_pdf_command() {
+    // Create temporary directory
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+
+    // Create sample HTML file with three slides
+    let html_path = temp_path.join("test.html");
+    let html_content = r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Test Slides</title>
+</head>
+<body>
+<div>Slide 1</div>
+<div>Slide 2</div>
+<div>Slide 3</div>
+</body>
+</html>"#;
+    fs::write(&html_path, html_content).expect("Failed to write HTML file");
+
+    let pdf_path = temp_path.join("slides.pdf");
+
+    // Run command
+    let output = run_command(&[
+        "generate-pdf",
+        "-i",
+        html_path.to_str().unwrap(),
+        "-o",
+        pdf_path.to_str().unwrap(),
+        "--width",
+        "800",
+        "--height",
+        "600",
+    ]);
+
+    // Check command executed successfully
+    assert!(output.status.success(), "Command failed: {:?}", output);
+
+    // Check that a PDF was written
+    let pdf_data = fs::read(&pdf_path).expect("Failed to read PDF");
+    assert!(pdf_data.starts_with(b"%PDF"), "Output is not a PDF");
+}
+
+#[test]
+fn test_generate_pdf_missing_input() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+
+    let output = run_command(&[
+        "generate-pdf",
+        "-i",
+        temp_path.join("missing.html").to_str().unwrap(),
+        "-o",
+        temp_path.join("slides.pdf").to_str().unwrap(),
+    ]);
+
+    // Missing input should fail before a browser is launched
+    assert!(!output.status.success());
+    assert!(!temp_path.join("slides.pdf").exists());
+}
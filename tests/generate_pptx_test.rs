@@ -61,3 +61,185 @@ fn test_generate_pptx_command() {
     let metadata = fs::metadata(&output_path).expect("Failed to get file metadata");
     assert!(metadata.len() > 0, "PPTX file is empty");
 }
+
+#[test]
+fn test_generate_pptx_with_transition() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let slide_dir = temp_dir.path().join("slides");
+    fs::create_dir(&slide_dir).expect("Failed to create slides directory");
+    for i in 1..=2 {
+        ImageBuffer::from_fn(100, 100, |_, _| Rgb([255u8, 255u8, 255u8]))
+            .save(slide_dir.join(format!("slide_{:04}.png", i)))
+            .expect("Failed to save image");
+    }
+
+    // The second slide holds for longer than the rest
+    let markdown_path = temp_dir.path().join("slides.md");
+    fs::write(&markdown_path, "# One\n\n# Two\n<!-- advance: 20s -->\n").unwrap();
+
+    let output_path = temp_dir.path().join("output.pptx");
+    let output = run_command(&[
+        "generate-pptx",
+        "-i",
+        slide_dir.to_str().unwrap(),
+        "-o",
+        output_path.to_str().unwrap(),
+        "--markdown",
+        markdown_path.to_str().unwrap(),
+        "--transition",
+        "wipe",
+        "--advance-after",
+        "5s",
+    ]);
+    assert!(output.status.success(), "Command failed: {:?}", output);
+
+    let file = fs::File::open(&output_path).expect("Failed to open PPTX file");
+    let mut archive = zip::ZipArchive::new(file).expect("Failed to read PPTX as ZIP");
+    let mut slide_xml = |slide_num: usize| {
+        let mut xml = String::new();
+        std::io::Read::read_to_string(
+            &mut archive
+                .by_name(&format!("ppt/slides/slide{}.xml", slide_num))
+                .expect("Missing slide XML"),
+            &mut xml,
+        )
+        .unwrap();
+        xml
+    };
+    assert!(
+        slide_xml(1).contains(r#"<p:transition spd="med" advTm="5000"><p:wipe/></p:transition>"#)
+    );
+    assert!(
+        slide_xml(2).contains(r#"<p:transition spd="med" advTm="20000"><p:wipe/></p:transition>"#)
+    );
+
+    // Unknown transitions are rejected
+    let output = run_command(&[
+        "generate-pptx",
+        "-i",
+        slide_dir.to_str().unwrap(),
+        "-o",
+        output_path.to_str().unwrap(),
+        "--transition",
+        "spin",
+    ]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_generate_pptx_with_template() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let slide_dir = temp_dir.path().join("slides");
+    fs::create_dir(&slide_dir).expect("Failed to create slides directory");
+    ImageBuffer::from_fn(100, 100, |_, _| Rgb([255u8, 255u8, 255u8]))
+        .save(slide_dir.join("slide_0001.png"))
+        .expect("Failed to save image");
+
+    // A 4:3 deck serves as the template
+    let template_path = temp_dir.path().join("template.pptx");
+    let output = run_command(&[
+        "generate-pptx",
+        "-i",
+        slide_dir.to_str().unwrap(),
+        "-o",
+        template_path.to_str().unwrap(),
+        "--aspect-ratio",
+        "4:3",
+    ]);
+    assert!(output.status.success(), "Command failed: {:?}", output);
+
+    let output_path = temp_dir.path().join("output.pptx");
+    let output = run_command(&[
+        "generate-pptx",
+        "-i",
+        slide_dir.to_str().unwrap(),
+        "-o",
+        output_path.to_str().unwrap(),
+        "--template",
+        template_path.to_str().unwrap(),
+        "--layout",
+        "blank",
+    ]);
+    assert!(output.status.success(), "Command failed: {:?}", output);
+
+    let file = fs::File::open(&output_path).expect("Failed to open PPTX file");
+    let mut archive = zip::ZipArchive::new(file).expect("Failed to read PPTX as ZIP");
+    let mut presentation = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name("ppt/presentation.xml").unwrap(),
+        &mut presentation,
+    )
+    .unwrap();
+    assert!(presentation.contains(r#"<p:sldSz cx="9144000" cy="6858000" type="screen4x3"/>"#));
+
+    // Layouts the template doesn't have are rejected
+    let output = run_command(&[
+        "generate-pptx",
+        "-i",
+        slide_dir.to_str().unwrap(),
+        "-o",
+        output_path.to_str().unwrap(),
+        "--template",
+        template_path.to_str().unwrap(),
+        "--layout",
+        "Title Slide",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Its layouts are: Blank"));
+}
+
+#[test]
+fn test_generate_pptx_into_existing_deck() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let slide_dir = temp_dir.path().join("slides");
+    fs::create_dir(&slide_dir).expect("Failed to create slides directory");
+    for i in 1..=2 {
+        ImageBuffer::from_fn(100, 100, |_, _| Rgb([255u8, 255u8, 255u8]))
+            .save(slide_dir.join(format!("slide_{:04}.png", i)))
+            .expect("Failed to save image");
+    }
+
+    let deck_path = temp_dir.path().join("deck.pptx");
+    let output = run_command(&[
+        "generate-pptx",
+        "-i",
+        slide_dir.to_str().unwrap(),
+        "-o",
+        deck_path.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "Command failed: {:?}", output);
+
+    // Without -o the deck is updated in place
+    let output = run_command(&[
+        "generate-pptx",
+        "-i",
+        slide_dir.to_str().unwrap(),
+        "--into",
+        deck_path.to_str().unwrap(),
+        "--position",
+        "2",
+    ]);
+    assert!(output.status.success(), "Command failed: {:?}", output);
+
+    let file = fs::File::open(&deck_path).expect("Failed to open PPTX file");
+    let mut archive = zip::ZipArchive::new(file).expect("Failed to read PPTX as ZIP");
+    let mut read = |name: &str| {
+        let mut xml = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name(name).unwrap(), &mut xml).unwrap();
+        xml
+    };
+    assert_eq!(read("ppt/presentation.xml").matches("<p:sldId ").count(), 4);
+    assert!(read("docProps/app.xml").contains("<Slides>4</Slides>"));
+
+    // Positions past the end of the deck are rejected
+    let output = run_command(&[
+        "generate-pptx",
+        "-i",
+        slide_dir.to_str().unwrap(),
+        "--into",
+        deck_path.to_str().unwrap(),
+        "--position",
+        "9",
+    ]);
+    assert!(!output.status.success());
+}
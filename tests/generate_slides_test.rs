@@ -11,6 +11,28 @@ fn run_command(args: &[&str]) -> Output {
         .expect("Failed to execute command")
 }
 
+#[test]
+fn test_generate_slides_rejects_unknown_format() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let html_path = temp_dir.path().join("test.html");
+    fs::write(&html_path, "<html><body><div>Slide</div></body></html>")
+        .expect("Failed to write HTML file");
+
+    // Checked before a browser is started, so no Chrome is needed
+    let output = run_command(&[
+        "generate-slides",
+        "-i",
+        html_path.to_str().unwrap(),
+        "-o",
+        temp_dir.path().join("slides").to_str().unwrap(),
+        "--format",
+        "gif",
+    ]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown image format 'gif'"));
+}
+
 #[test]
 #[ignore] // Ignore by default as it requires Chrome to be installed
 fn test_generate_slides_command() {
@@ -110,3 +132,334 @@ fn test_generate_slides_command() {
     // Note: In a real headless browser environment with JavaScript, we would expect 3 slides
     // But in the basic test, we'll just check that at least one was created
 }
+
+#[test]
+#[ignore] // Ignore by default as it requires Chrome to be installed
+fn test_generate_slides_parallel_matches_sequential() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+
+    let html_path = temp_path.join("test.html");
+    let slides: String = (1..=5)
+        .map(|n| format!("<div>Slide {}</div>\n", n))
+        .collect();
+    fs::write(
+        &html_path,
+        format!(
+            "<!DOCTYPE html>\n<html>\n<body>\n{}</body>\n</html>",
+            slides
+        ),
+    )
+    .expect("Failed to write HTML file");
+
+    let mut listings = Vec::new();
+    for jobs in ["1", "3"] {
+        let output_dir = temp_path.join(format!("slides-{}", jobs));
+        let output = run_command(&[
+            "generate-slides",
+            "-i",
+            html_path.to_str().unwrap(),
+            "-o",
+            output_dir.to_str().unwrap(),
+            "--width",
+            "400",
+            "--height",
+            "300",
+            "--jobs",
+            jobs,
+        ]);
+        assert!(output.status.success(), "Command failed: {:?}", output);
+
+        let mut files: Vec<String> = fs::read_dir(&output_dir)
+            .expect("Failed to read output directory")
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        listings.push(files);
+    }
+
+    assert_eq!(listings[0].len(), 5);
+    assert_eq!(listings[0], listings[1]);
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires Chrome to be installed
+fn test_generate_slides_waits_for_big_ready() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+
+    // The page only turns red once its bigReady() promise resolves
+    let html_path = temp_path.join("test.html");
+    fs::write(
+        &html_path,
+        r#"<!DOCTYPE html>
+<html>
+<body style="margin: 0; background: white">
+<div>Slide 1</div>
+<script>
+    window.bigReady = function() {
+        return new Promise(function(resolve) {
+            setTimeout(function() {
+                document.body.style.background = 'rgb(255, 0, 0)';
+                resolve();
+            }, 1500);
+        });
+    };
+</script>
+</body>
+</html>"#,
+    )
+    .expect("Failed to write HTML file");
+
+    let output_dir = temp_path.join("slides");
+    let output = run_command(&[
+        "generate-slides",
+        "-i",
+        html_path.to_str().unwrap(),
+        "-o",
+        output_dir.to_str().unwrap(),
+        "--width",
+        "400",
+        "--height",
+        "300",
+    ]);
+    assert!(output.status.success(), "Command failed: {:?}", output);
+
+    let slide = image::open(output_dir.join("slide_0001.png"))
+        .expect("Failed to open slide image")
+        .to_rgb8();
+    assert_eq!(slide.get_pixel(399, 299).0, [255, 0, 0]);
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires Chrome to be installed
+fn test_generate_slides_slide_count_checks() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+
+    // A page without slides is an error rather than a guess
+    let empty_path = temp_path.join("empty.html");
+    fs::write(
+        &empty_path,
+        "<!DOCTYPE html>\n<html>\n<body><p>No slides</p></body>\n</html>",
+    )
+    .expect("Failed to write HTML file");
+    let output = run_command(&[
+        "generate-slides",
+        "-i",
+        empty_path.to_str().unwrap(),
+        "-o",
+        temp_path.join("empty").to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("has no slides"));
+
+    // A deck with a different number of slides than expected is rejected
+    let deck_path = temp_path.join("deck.html");
+    fs::write(
+        &deck_path,
+        "<!DOCTYPE html>\n<html>\n<body>\n<div>One</div>\n<div>Two</div>\n</body>\n</html>",
+    )
+    .expect("Failed to write HTML file");
+    let output = run_command(&[
+        "generate-slides",
+        "-i",
+        deck_path.to_str().unwrap(),
+        "-o",
+        temp_path.join("deck").to_str().unwrap(),
+        "--expected-slides",
+        "3",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Expected 3 slides but found 2"));
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires Chrome to be installed
+fn test_generate_slides_incremental() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+    let html_path = temp_path.join("test.html");
+    let output_dir = temp_path.join("slides");
+
+    let render = |slides: &[&str]| {
+        let body: String = slides
+            .iter()
+            .map(|slide| format!("<div>{}</div>\n", slide))
+            .collect();
+        fs::write(
+            &html_path,
+            format!("<!DOCTYPE html>\n<html>\n<body>\n{}</body>\n</html>", body),
+        )
+        .expect("Failed to write HTML file");
+
+        let output = run_command(&[
+            "generate-slides",
+            "-i",
+            html_path.to_str().unwrap(),
+            "-o",
+            output_dir.to_str().unwrap(),
+            "--width",
+            "400",
+            "--height",
+            "300",
+            "--incremental",
+        ]);
+        assert!(output.status.success(), "Command failed: {:?}", output);
+    };
+    let modified = |n: usize| {
+        fs::metadata(output_dir.join(format!("slide_{:04}.png", n)))
+            .and_then(|metadata| metadata.modified())
+            .expect("Slide image is missing")
+    };
+
+    render(&["One", "Two", "Three"]);
+    let before = [modified(1), modified(2), modified(3)];
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    // Only the edited slide is captured again, and the removed one is deleted
+    render(&["One", "Deux"]);
+    assert_eq!(modified(1), before[0]);
+    assert_ne!(modified(2), before[1]);
+    assert!(!output_dir.join("slide_0003.png").exists());
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires Chrome to be installed
+fn test_generate_slides_scale() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+
+    // A bar across the left half of a 400px-wide viewport
+    let html_path = temp_path.join("test.html");
+    fs::write(
+        &html_path,
+        r#"<!DOCTYPE html>
+<html>
+<body style="margin: 0; background: white">
+<div style="display: block; width: 50vw; height: 100vh; background: rgb(255, 0, 0)"></div>
+</body>
+</html>"#,
+    )
+    .expect("Failed to write HTML file");
+
+    let output_dir = temp_path.join("slides");
+    let output = run_command(&[
+        "generate-slides",
+        "-i",
+        html_path.to_str().unwrap(),
+        "-o",
+        output_dir.to_str().unwrap(),
+        "--width",
+        "400",
+        "--height",
+        "300",
+        "--scale",
+        "2",
+    ]);
+    assert!(output.status.success(), "Command failed: {:?}", output);
+
+    // Twice the pixels, with the layout of a 400x300 viewport
+    let slide = image::open(output_dir.join("slide_0001.png"))
+        .expect("Failed to open slide image")
+        .to_rgb8();
+    assert_eq!(slide.dimensions(), (800, 600));
+    assert_eq!(slide.get_pixel(390, 300).0, [255, 0, 0]);
+    assert_eq!(slide.get_pixel(410, 300).0, [255, 255, 255]);
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires Chrome to be installed
+fn test_generate_slides_webp_and_avif() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+    let html_path = temp_path.join("test.html");
+    fs::write(
+        &html_path,
+        "<!DOCTYPE html>\n<html>\n<body>\n<div>Slide 1</div>\n</body>\n</html>",
+    )
+    .expect("Failed to write HTML file");
+
+    for (format, magic_at, magic) in [("webp", 8, &b"WEBP"[..]), ("avif", 4, &b"ftypavif"[..])] {
+        let output_dir = temp_path.join(format);
+        let output = run_command(&[
+            "generate-slides",
+            "-i",
+            html_path.to_str().unwrap(),
+            "-o",
+            output_dir.to_str().unwrap(),
+            "--width",
+            "400",
+            "--height",
+            "300",
+            "--format",
+            format,
+            "--quality",
+            "50",
+        ]);
+        assert!(output.status.success(), "Command failed: {:?}", output);
+
+        let image = fs::read(output_dir.join(format!("slide_0001.{}", format)))
+            .expect("Slide image is missing");
+        assert_eq!(&image[magic_at..magic_at + magic.len()], magic);
+    }
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires Chrome to be installed
+fn test_generate_slides_fail_on_overflow() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+
+    // The second slide is wider than the viewport and its text is tiny
+    let html_path = temp_path.join("test.html");
+    fs::write(
+        &html_path,
+        r#"<!DOCTYPE html>
+<html>
+<body style="margin: 0">
+<div style="font-size: 40px">Fits</div>
+<div><span style="display: inline-block; width: 800px; font-size: 8px">Too wide</span></div>
+</body>
+</html>"#,
+    )
+    .expect("Failed to write HTML file");
+
+    let output_dir = temp_path.join("slides");
+    let args = [
+        "generate-slides",
+        "-i",
+        html_path.to_str().unwrap(),
+        "-o",
+        output_dir.to_str().unwrap(),
+        "--width",
+        "400",
+        "--height",
+        "300",
+    ];
+
+    // Without the flag overflow is only reported
+    let output = run_command(&args);
+    assert!(output.status.success(), "Command failed: {:?}", output);
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(output_dir.join("overflow-report.json")).unwrap())
+            .unwrap();
+    let slides = report["slides"].as_array().unwrap();
+    assert_eq!(slides.len(), 2);
+    assert_eq!(slides[0]["issues"], serde_json::json!([]));
+    assert_eq!(slides[1]["slide"], 2);
+    assert_eq!(
+        slides[1]["issues"],
+        serde_json::json!(["overflow", "small_font"])
+    );
+    assert!(slides[1]["overflow"]["right"].as_f64().unwrap() >= 399.0);
+
+    let output = run_command(&[&args[..], &["--fail-on-overflow"]].concat());
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Content overflows or is clipped on slide(s) 2"),
+        "{}",
+        stderr
+    );
+}
@@ -101,20 +101,60 @@ fn test_default_css_js_inclusion() {
         "Missing markdown content"
     );
 
-    // Verify default CSS is included
-    let default_css = "https://raw.githubusercontent.com/harperreed/big/gh-pages/big.css";
+    // Verify the built-in CSS and JS are embedded even in link mode
     assert!(
-        html_content.contains(&format!(
-            r#"<link rel="stylesheet" href="{}">"#,
-            default_css
-        )),
-        "Missing default CSS link"
+        html_content.contains("<style>/* Default big-slides theme"),
+        "Missing default CSS"
     );
+    assert!(
+        html_content.contains("<script>// Default big-slides navigation"),
+        "Missing default JS"
+    );
+    assert!(
+        !html_content.contains("raw.githubusercontent.com"),
+        "Default resources should not need the network"
+    );
+}
+
+#[test]
+fn test_self_contained_html() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let temp_path = temp_dir.path();
+
+    // A deck with an image next to it and a stylesheet that uses a font
+    fs::create_dir_all(temp_path.join("theme")).expect("Failed to create theme dir");
+    fs::write(temp_path.join("photo.gif"), b"GIF89a").expect("Failed to write image");
+    fs::write(temp_path.join("theme/font.woff2"), b"wOF2").expect("Failed to write font");
+    fs::write(
+        temp_path.join("theme/style.css"),
+        "@font-face { font-family: Deck; src: url(font.woff2); }",
+    )
+    .expect("Failed to write CSS file");
+
+    let markdown_path = temp_path.join("test.md");
+    fs::write(&markdown_path, "# Photo\n\n![photo](photo.gif)\n")
+        .expect("Failed to write markdown file");
+    let output_path = temp_path.join("output.html");
 
-    // Verify default JS is included
-    let default_js = "https://raw.githubusercontent.com/harperreed/big/gh-pages/big.js";
+    let output = run_command(&[
+        "generate-html",
+        "-i",
+        markdown_path.to_str().unwrap(),
+        "-o",
+        output_path.to_str().unwrap(),
+        "--css",
+        temp_path.join("theme/style.css").to_str().unwrap(),
+        "--self-contained",
+    ]);
+    assert!(output.status.success(), "Command failed: {:?}", output);
+
+    let html_content = fs::read_to_string(&output_path).expect("Failed to read output file");
+    assert!(
+        html_content.contains(r#"<img src="data:image/gif;base64,R0lGODlh""#),
+        "Image was not inlined"
+    );
     assert!(
-        html_content.contains(&format!(r#"<script src="{}">"#, default_js)),
-        "Missing default JS script tag"
+        html_content.contains(r#"src: url("data:font/woff2;base64,d09GMg==")"#),
+        "Font was not inlined"
     );
 }